@@ -50,6 +50,7 @@ impl_bit!(u32, a, b);
 impl_bit!(u32, a, b, c);
 impl_bit!(u32, a, b, c, d);
 impl_bit!(u32, a, b, c, d, e);
+impl_bit!(u32, a, b, c, d, e, f);
 
 impl_bit!(u64, a, b);
 impl_bit!(u64, a, b, c);
@@ -0,0 +1,168 @@
+//! Equipment sets of arena quests, resolved into pieces and skill totals.
+//!
+//! Armor defense is the base defense raised by the upgrade table up to the level of the set.
+//! Skill totals add up armor, decoration and talisman skills,
+//! without set bonuses or rampage decorations.
+
+use super::pedia::*;
+use crate::rsz::*;
+use std::collections::{BTreeMap, HashMap};
+
+pub struct ArenaLookup<'a> {
+    armors: HashMap<PlArmorId, (&'a ArmorSeries<'a>, &'a Armor<'a>)>,
+    decos: HashMap<DecorationsId, (PlEquipSkillId, &'a Deco<'a>)>,
+}
+
+impl<'a> ArenaLookup<'a> {
+    pub fn new(pedia_ex: &'a PediaEx<'a>) -> ArenaLookup<'a> {
+        let mut armors = HashMap::new();
+        for series in pedia_ex.armors.values() {
+            for piece in series.pieces.iter().flatten() {
+                armors.insert(piece.data.pl_armor_id, (series, piece));
+            }
+        }
+        let mut decos = HashMap::new();
+        for (&skill_id, skill) in &pedia_ex.skills {
+            for deco in &skill.decos {
+                decos.insert(deco.data.id, (skill_id, deco));
+            }
+        }
+        ArenaLookup { armors, decos }
+    }
+
+    pub fn armor(&self, id: PlArmorId) -> Option<(&'a ArmorSeries<'a>, &'a Armor<'a>)> {
+        self.armors.get(&id).copied()
+    }
+
+    /// The decoration and the skill it belongs to
+    pub fn deco(&self, id: DecorationsId) -> Option<(PlEquipSkillId, &'a Deco<'a>)> {
+        self.decos.get(&id).copied()
+    }
+}
+
+pub struct ArenaArmor<'a> {
+    pub id: PlArmorId,
+    pub piece: Option<(&'a ArmorSeries<'a>, &'a Armor<'a>)>,
+    pub lv: u32,
+    pub defense: Option<i32>,
+    pub decos: &'a [DecorationsId],
+}
+
+pub struct ArenaSkill {
+    pub id: PlEquipSkillId,
+    pub lv: i32,
+    /// `None` if the skill is unknown
+    pub max_lv: Option<i32>,
+}
+
+pub struct ArenaLoadout<'a> {
+    /// Helm, chest, arm, waist and leg
+    pub armors: [ArenaArmor<'a>; 5],
+    pub defense: i32,
+    /// Fire, water, thunder, ice and dragon
+    pub resistance: [i32; 5],
+    /// Highest level first
+    pub skills: Vec<ArenaSkill>,
+}
+
+pub fn armor_defense(piece: &Armor, lv: u32, pedia_ex: &PediaEx) -> i32 {
+    let mut defense = piece.data.def_val;
+    if let Some(table) = pedia_ex.armor_buildup.get(&piece.data.buildup_table) {
+        for level in 2..=lv {
+            // The table is sorted by limit_lv
+            if let Some(step) = table.iter().find(|step| level <= step.limit_lv) {
+                defense += step.up_val;
+            }
+        }
+    }
+    defense
+}
+
+pub fn resolve_arena_loadout<'a>(
+    pl: &'a ArenaPlayer,
+    lookup: &ArenaLookup<'a>,
+    pedia_ex: &PediaEx,
+) -> ArenaLoadout<'a> {
+    let armor = |id: PlArmorId, lv: u32, decos: &'a [DecorationsId]| {
+        let piece = lookup.armor(id);
+        ArenaArmor {
+            id,
+            piece,
+            lv,
+            defense: piece.map(|(_, piece)| armor_defense(piece, lv, pedia_ex)),
+            decos,
+        }
+    };
+    let armors = [
+        armor(pl.armor_helm, pl.armor_lv_helm, &pl.deco_helm),
+        armor(pl.armor_body, pl.armor_lv_body, &pl.deco_body),
+        armor(pl.armor_arm, pl.armor_lv_arm, &pl.deco_arm),
+        armor(pl.armor_waist, pl.armor_lv_waist, &pl.deco_waist),
+        armor(pl.armor_leg, pl.armor_lv_leg, &pl.deco_leg),
+    ];
+
+    let mut defense = 0;
+    let mut resistance = [0; 5];
+    let mut skills: BTreeMap<PlEquipSkillId, i32> = BTreeMap::new();
+    let mut add_skill = |id: PlEquipSkillId, lv: i32| {
+        if id != PlEquipSkillId::None && lv > 0 {
+            *skills.entry(id).or_default() += lv;
+        }
+    };
+
+    for armor in &armors {
+        if let Some((_, piece)) = armor.piece {
+            defense += armor.defense.unwrap_or_default();
+            let data = &piece.data;
+            for (r, v) in resistance.iter_mut().zip([
+                data.fire_reg_val,
+                data.water_reg_val,
+                data.thunder_reg_val,
+                data.ice_reg_val,
+                data.dragon_reg_val,
+            ]) {
+                *r += v;
+            }
+            for (&skill, &lv) in data.skill_list.iter().zip(&data.skill_lv_list) {
+                add_skill(skill, lv);
+            }
+        }
+    }
+
+    let decos = armors
+        .iter()
+        .flat_map(|armor| armor.decos)
+        .chain(&pl.deco_wep)
+        .chain(&pl.deco_talisman);
+    for &deco in decos {
+        if let Some((_, deco)) = lookup.deco(deco) {
+            for (&skill, &lv) in deco.data.skill_id_list.iter().zip(&deco.data.skill_lv_list) {
+                add_skill(skill, lv);
+            }
+        }
+    }
+
+    for skill in &pl.talisman_skill {
+        add_skill(skill.id, skill.lv);
+    }
+
+    let mut skills: Vec<ArenaSkill> = skills
+        .into_iter()
+        .map(|(id, lv)| ArenaSkill {
+            id,
+            lv,
+            max_lv: pedia_ex
+                .skills
+                .get(&id)
+                .map(|skill| skill.levels.len() as i32),
+        })
+        .collect();
+    skills.sort_by_key(|skill| std::cmp::Reverse(skill.lv));
+
+    ArenaLoadout {
+        armors,
+        defense,
+        resistance,
+        skills,
+    }
+}
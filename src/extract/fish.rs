@@ -0,0 +1,102 @@
+//! Fish spawn chances at fishing points.
+//!
+//! Each spawn group of a fishing point fills one fish slot,
+//! picking a species weighted by `spawn_rate` within the group.
+//! Groups are assumed to be drawn independently and in order,
+//! so only the first `fish_num_max` groups fill the slots of the point.
+//! This keeps the average count of all fish within the number of fish at the point.
+
+use super::gen_map::get_fish_item_id;
+use super::pedia::*;
+use super::prepare_map::*;
+use crate::rsz::*;
+use std::collections::BTreeMap;
+
+pub struct FishingSpot<'a> {
+    pub map: i32,
+    /// Index in `GameMap::pops`, which is also the icon ID on the map page
+    pub pop_index: usize,
+    pub point: &'a FishingPoint,
+}
+
+pub struct FishChance {
+    pub fish_id: i32,
+    pub item: Option<ItemId>,
+    /// Probability that at least one of the fish is at the spot
+    pub appear: f64,
+    /// Average number of the fish at the spot
+    pub expected: f64,
+}
+
+impl FishingSpot<'_> {
+    pub fn groups(&self, rank: QuestRank) -> &[FishSpawnGroupInfo] {
+        let data = self.point.fish_spawn_data.unwrap();
+        match rank {
+            QuestRank::Low => &data.spawn_group_list_info_low,
+            QuestRank::High => &data.spawn_group_list_info_high,
+            QuestRank::Master => &data.spawn_group_list_info_master,
+        }
+    }
+
+    /// Chance of each fish at the spot in a quest rank, most likely first
+    pub fn chances(&self, rank: QuestRank) -> Vec<FishChance> {
+        fish_chances(self.groups(rank), self.point.fish_num_max)
+    }
+}
+
+pub fn fish_chances(groups: &[FishSpawnGroupInfo], fish_num_max: i32) -> Vec<FishChance> {
+    let slots = usize::try_from(fish_num_max).unwrap_or(0);
+    // fish_id -> (probability of not appearing, expected count)
+    let mut stats: BTreeMap<i32, (f64, f64)> = BTreeMap::new();
+    for group in groups.iter().take(slots) {
+        let total: f64 = group
+            .fish_spawn_rate_list
+            .iter()
+            .map(|f| f64::from(f.spawn_rate))
+            .sum();
+        if total <= 0.0 {
+            continue;
+        }
+
+        // The same fish can be listed more than once in a group
+        let mut group_chances: BTreeMap<i32, f64> = BTreeMap::new();
+        for fish in &group.fish_spawn_rate_list {
+            *group_chances.entry(fish.fish_id).or_default() += f64::from(fish.spawn_rate) / total;
+        }
+
+        for (fish_id, chance) in group_chances {
+            let (absent, expected) = stats.entry(fish_id).or_insert((1.0, 0.0));
+            *absent *= 1.0 - chance;
+            *expected += chance;
+        }
+    }
+
+    let mut result: Vec<FishChance> = stats
+        .into_iter()
+        .filter(|&(_, (_, expected))| expected > 0.0)
+        .map(|(fish_id, (absent, expected))| FishChance {
+            fish_id,
+            item: get_fish_item_id(fish_id),
+            appear: 1.0 - absent,
+            expected,
+        })
+        .collect();
+    result.sort_by(|a, b| b.appear.total_cmp(&a.appear));
+    result
+}
+
+pub fn prepare_fishing_spots(pedia: &Pedia) -> Vec<FishingSpot<'_>> {
+    let mut result = vec![];
+    for (&map, game_map) in &pedia.maps {
+        for (pop_index, pop) in game_map.pops.iter().enumerate() {
+            if let MapPopKind::FishingPoint { behavior, .. } = &pop.kind {
+                result.push(FishingSpot {
+                    map,
+                    pop_index,
+                    point: behavior,
+                });
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,302 @@
+use super::gen_common::*;
+use super::gen_item::*;
+use super::gen_map::*;
+use super::gen_website::*;
+use super::hash_store::*;
+use super::pedia::*;
+use super::prepare_map::*;
+use super::sink::*;
+use crate::rsz::*;
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use typed_html::{dom::*, elements::*, html, text};
+
+pub fn ec_page(ec_type: i32) -> String {
+    match get_ec_id(ec_type) {
+        Some(id) => format!("{id}.html"),
+        None => format!("unknown_{ec_type}.html"),
+    }
+}
+
+pub fn gen_ec_label(ec_type: i32, pedia_ex: &PediaEx) -> Box<a<String>> {
+    html!(<a href={format!("ec/{}", ec_page(ec_type))} class="mh-icon-text">
+        {get_ec_icon(ec_type)}
+        {gen_ec_name(ec_type, pedia_ex)}
+    </a>)
+}
+
+fn has_effect(ec: &EndemicLife, f: impl Fn(&EcEffects) -> bool) -> bool {
+    ec.pops.iter().any(|pop| f(pop.effects))
+}
+
+fn ec_items(ec: &EndemicLife) -> BTreeSet<ItemId> {
+    ec.pops.iter().filter_map(|pop| pop.effects.item).collect()
+}
+
+/// Short tags of the effects for the list page
+fn effect_tags(ec: &EndemicLife) -> Vec<&'static str> {
+    let mut tags = vec![];
+    if !ec_items(ec).is_empty() {
+        tags.push("Item");
+    }
+    if has_effect(ec, |e| e.lv_buff) {
+        tags.push("Spiribird");
+    }
+    if has_effect(ec, |e| e.buff) {
+        tags.push("Buff");
+    }
+    if has_effect(ec, |e| e.wire_buff) {
+        tags.push("Wirebug");
+    }
+    if has_effect(ec, |e| e.trap) {
+        tags.push("Trap");
+    }
+    if has_effect(ec, |e| e.long_wire) {
+        tags.push("Long jump");
+    }
+    if has_effect(ec, |e| e.drop) {
+        tags.push("Drop");
+    }
+    if has_effect(ec, |e| e.photo.is_some()) {
+        tags.push("Photo");
+    }
+    tags
+}
+
+pub fn gen_ec_list(
+    hash_store: &HashStore,
+    pedia: &Pedia,
+    pedia_ex: &PediaEx,
+    output: &impl Sink,
+) -> Result<()> {
+    let doc: DOMTree<String> = html!(
+        <html lang="en">
+            <head itemscope=true>
+                <title>{text!("Endemic life - MHRice")}</title>
+                { head_common(hash_store, output) }
+            </head>
+            <body>
+                { navbar() }
+                <main>
+                <header><h1>"Endemic life"</h1></header>
+                <div class="mh-table"><table>
+                <thead><tr>
+                    <th>"Name"</th>
+                    <th>"Effect"</th>
+                    <th>"Maps"</th>
+                </tr></thead>
+                <tbody>{pedia_ex.endemic_life.iter().map(|(&type_, ec)| {
+                    let maps: BTreeSet<i32> = ec.pops.iter().map(|pop| pop.map).collect();
+                    html!(<tr>
+                        <td>{gen_ec_label(type_, pedia_ex)}</td>
+                        <td>{effect_tags(ec).into_iter().map(|tag|
+                            html!(<span class="tag">{text!("{}", tag)}</span>))}</td>
+                        <td>{maps.into_iter().map(|map|
+                            html!(<div>{gen_map_label(map, pedia)}</div>))}</td>
+                    </tr>)
+                })}</tbody>
+                </table></div>
+                </main>
+                { right_aside() }
+            </body>
+        </html>
+    );
+
+    output
+        .create_html("ec.html")?
+        .write_all(doc.to_string().as_bytes())?;
+
+    Ok(())
+}
+
+fn gen_stage_time(time: &StageTime) -> String {
+    format!("{:02}:{:02}", time.hour, time.minute)
+}
+
+fn gen_ec(
+    hash_store: &HashStore,
+    type_: i32,
+    ec: &EndemicLife,
+    pedia: &Pedia,
+    pedia_ex: &PediaEx<'_>,
+    config: &WebsiteConfig,
+    path: &impl Sink,
+    toc: &mut Toc,
+) -> Result<()> {
+    let (mut output, mut toc_sink) = path.create_html_with_toc(&ec_page(type_), toc)?;
+
+    if let Some(name) = ec.name {
+        toc_sink.add(name);
+    }
+
+    let mut sections = vec![];
+
+    let mut effects: Vec<Box<li<String>>> = ec_items(ec)
+        .into_iter()
+        .map(|item| {
+            html!(<li>"Captured as "
+                <div class="il">{gen_item_label_from_id(item, pedia_ex)}</div>
+            </li>)
+        })
+        .collect();
+    let mut add_effect = |f: fn(&EcEffects) -> bool, explain: &str| {
+        if has_effect(ec, f) {
+            effects.push(html!(<li>{text!("{}", explain)}</li>))
+        }
+    };
+    add_effect(
+        |e| e.lv_buff,
+        "Raises a status when picked up, up to the limit of the petalace",
+    );
+    add_effect(|e| e.buff, "Grants a temporary buff when touched");
+    add_effect(|e| e.wire_buff, "Grants a wirebug buff");
+    add_effect(|e| e.trap, "Can be used as a trap against monsters");
+    add_effect(|e| e.long_wire, "Can be used for a long wire jump");
+    add_effect(|e| e.drop, "Drops an item when hit");
+    if let Some(photo) = ec.pops.iter().find_map(|pop| pop.effects.photo.as_ref()) {
+        effects.push(html!(<li>{text!(
+            "Can be photographed from {} to {}",
+            gen_stage_time(&photo.start_time),
+            gen_stage_time(&photo.end_time)
+        )}</li>))
+    }
+
+    sections.push(Section {
+        title: "Effect".to_owned(),
+        content: html!(
+            <section id="s-effect">
+            <h2 >"Effect"</h2>
+            {if effects.is_empty() {
+                html!(<div>"No effect on the hunter"</div>)
+            } else {
+                html!(<div><ul class="mh-item-list">{effects}</ul></div>)
+            }}
+            </section>
+        ),
+    });
+
+    if has_effect(ec, |e| e.lv_buff) {
+        let mut petalace: Vec<_> = pedia_ex.buff_cage.values().collect();
+        petalace.sort_unstable_by_key(|p| (p.data.sort_index, p.data.id));
+        let stat_cell = |petalace: &BuffCage, i: usize| {
+            html!(<td>{text!(
+                "+{} (rainbow +{}) / {}",
+                petalace.data.status_buff_add_value[i],
+                petalace.data.status_buff_all_add_value[i],
+                petalace.data.status_buff_limit[i]
+            )}</td>)
+        };
+        sections.push(Section {
+            title: "Stat gain".to_owned(),
+            content: html!(
+                <section id="s-stat">
+                <h2 >"Stat gain"</h2>
+                <div>{text!("Gain per spiribird and limit of each status, by the equipped petalace. {}",
+                    "A spiribird only raises the status of its color, while a rainbow spiribird raises all of them.")}</div>
+                <div class="mh-table"><table>
+                <thead><tr>
+                    <th>"Petalace"</th>
+                    <th>"Health"</th>
+                    <th>"Stamina"</th>
+                    <th>"Attack"</th>
+                    <th>"Defense"</th>
+                </tr></thead>
+                <tbody>{petalace.into_iter().map(|petalace| html!(<tr>
+                    <td>{gen_buff_cage_label(petalace)}</td>
+                    {(0..4).map(|i| stat_cell(petalace, i))}
+                </tr>))}</tbody>
+                </table></div>
+                </section>
+            ),
+        });
+    }
+
+    let mut maps: BTreeMap<i32, Vec<&EndemicLifePop>> = BTreeMap::new();
+    for pop in &ec.pops {
+        maps.entry(pop.map).or_default().push(pop);
+    }
+
+    sections.push(Section {
+        title: "Location".to_owned(),
+        content: html!(
+            <section id="s-location">
+            <h2 >"Location"</h2>
+            <div class="mh-table"><table>
+            <thead><tr>
+                <th>"Map"</th>
+                <th>"Count"</th>
+                <th>"Respawn time"</th>
+                <th>"Points"</th>
+            </tr></thead>
+            <tbody>{maps.into_iter().map(|(map, pops)| {
+                let mut respawn: Vec<f32> = pops.iter().map(|pop| pop.behavior.base.repop_wait_time).collect();
+                respawn.sort_by(f32::total_cmp);
+                respawn.dedup();
+                let respawn: Vec<String> = respawn.into_iter().map(|t| t.to_string()).collect();
+                html!(<tr>
+                    <td>{gen_map_label(map, pedia)}</td>
+                    <td>{text!("{}", pops.len())}</td>
+                    <td>{text!("{}", respawn.join(", "))}</td>
+                    <td>{pops.iter().enumerate().map(|(i, pop)| html!(<span>
+                        <a href={format!("map/{}#icon={}", map_page(map), pop.pop_index)}>
+                            {text!("#{}", i + 1)}
+                        </a>" "
+                    </span>))}</td>
+                </tr>)
+            })}</tbody>
+            </table></div>
+            </section>
+        ),
+    });
+
+    let plain_title = format!("Endemic life {type_}");
+    let doc: DOMTree<String> = html!(
+        <html lang="en">
+            <head itemscope=true>
+                <title>{text!("{}", plain_title)}</title>
+                { head_common(hash_store, path) }
+                { ec.name.map(title_multi_lang).unwrap_or_default() }
+                { open_graph(ec.name, &plain_title,
+                    None, "", None, toc_sink.path(), config) }
+            </head>
+            <body>
+                { navbar() }
+                { gen_menu(&sections, toc_sink.path()) }
+                <main>
+                <header>
+                    <div class="mh-title-icon">
+                        {get_ec_icon(type_)}
+                    </div>
+                    <h1>{gen_ec_name(type_, pedia_ex)}</h1>
+                </header>
+
+                { sections.into_iter().map(|s|s.content) }
+
+                </main>
+                { right_aside() }
+            </body>
+        </html>
+    );
+
+    output.write_all(doc.to_string().as_bytes())?;
+
+    Ok(())
+}
+
+pub fn gen_ecs(
+    hash_store: &HashStore,
+    pedia: &Pedia,
+    pedia_ex: &PediaEx<'_>,
+    config: &WebsiteConfig,
+    output: &impl Sink,
+    toc: &mut Toc,
+) -> Result<()> {
+    let ec_path = output.sub_sink("ec")?;
+    for (&type_, ec) in &pedia_ex.endemic_life {
+        gen_ec(
+            hash_store, type_, ec, pedia, pedia_ex, config, &ec_path, toc,
+        )?;
+    }
+    Ok(())
+}
@@ -690,6 +690,145 @@ fn gen_item_source_map(
     }
 }
 
+fn gen_item_source_gather(
+    item_id: ItemId,
+    pedia: &Pedia,
+    pedia_ex: &PediaEx,
+) -> Option<Box<div<String>>> {
+    struct Row {
+        rank: &'static str,
+        map: i32,
+        one_time: bool,
+        chance: u32,
+        yields: Vec<(u32, u32)>,
+        lot_count: u32,
+        points: Vec<usize>,
+    }
+
+    let ranks = [
+        (QuestRank::Low, "Low rank"),
+        (QuestRank::High, "High rank"),
+        (QuestRank::Master, "Master rank"),
+    ];
+
+    // Points sharing the same table on a map are merged into one row
+    let mut rows: BTreeMap<(usize, i32, i32, bool), Row> = BTreeMap::new();
+    for point in &pedia_ex.gather_points {
+        for (rank_index, &(rank, rank_name)) in ranks.iter().enumerate() {
+            let yields: Vec<(u32, u32)> = point
+                .yields(rank)
+                .filter(|&(id, _, _)| id == item_id)
+                .map(|(_, num, probability)| (num, probability))
+                .collect();
+            if yields.is_empty() {
+                continue;
+            }
+            let one_time = point.behavior.one_time_only_flag;
+            rows.entry((rank_index, point.map, point.behavior.pop_id, one_time))
+                .or_insert_with(|| Row {
+                    rank: rank_name,
+                    map: point.map,
+                    one_time,
+                    chance: yields.iter().map(|&(_, probability)| probability).sum(),
+                    yields,
+                    lot_count: point.lot.lot_count,
+                    points: vec![],
+                })
+                .points
+                .push(point.pop_index);
+        }
+    }
+
+    if rows.is_empty() {
+        return None;
+    }
+
+    let mut rows: Vec<Row> = rows.into_values().collect();
+    rows.sort_by(|a, b| b.chance.cmp(&a.chance));
+
+    Some(html!(<div class="mh-item-in-out">
+        <h3>"From gathering points: "</h3>
+        <div class="mh-table"><table>
+        <thead><tr>
+            <th>"Rank"</th>
+            <th>"Map"</th>
+            <th>"Chance per draw"</th>
+            <th>"Amount"</th>
+            <th>"Draws"</th>
+            <th>"Respawn"</th>
+            <th>"Points"</th>
+        </tr></thead>
+        <tbody>{rows.into_iter().map(|row| html!(<tr>
+            <td>{text!("{}", row.rank)}</td>
+            <td>{gen_map_label(row.map, pedia)}</td>
+            <td>{text!("{}%", row.chance)}</td>
+            <td>{row.yields.iter().map(|&(num, probability)|
+                html!(<div>{text!("{}x ({}%)", num, probability)}</div>))}</td>
+            <td>{text!("{}", row.lot_count)}</td>
+            <td>{text!("{}", if row.one_time { "No" } else { "Yes" })}</td>
+            <td>{row.points.iter().enumerate().map(|(i, &pop_index)| html!(<span>
+                <a href={format!("map/{}#icon={pop_index}", map_page(row.map))}>
+                    {text!("#{}", i + 1)}
+                </a>" "
+            </span>))}</td>
+        </tr>))}</tbody>
+        </table></div>
+    </div>))
+}
+
+fn gen_item_source_fishing(
+    item_id: ItemId,
+    pedia: &Pedia,
+    pedia_ex: &PediaEx,
+) -> Option<Box<div<String>>> {
+    let ranks = [
+        (QuestRank::Low, "Low rank"),
+        (QuestRank::High, "High rank"),
+        (QuestRank::Master, "Master rank"),
+    ];
+
+    let mut rows = vec![];
+    for spot in &pedia_ex.fishing_spots {
+        for (rank, rank_name) in ranks {
+            if let Some(chance) = spot
+                .chances(rank)
+                .into_iter()
+                .find(|chance| chance.item == Some(item_id))
+            {
+                rows.push((rank_name, spot, chance));
+            }
+        }
+    }
+
+    if rows.is_empty() {
+        return None;
+    }
+
+    rows.sort_by(|(_, _, a), (_, _, b)| b.appear.total_cmp(&a.appear));
+
+    Some(html!(<div class="mh-item-in-out">
+        <h3>"Best spots to catch: "</h3>
+        <div class="mh-table"><table>
+        <thead><tr>
+            <th>"Rank"</th>
+            <th>"Map"</th>
+            <th>"Spot"</th>
+            <th>"Chance to appear"</th>
+            <th>"Average count"</th>
+        </tr></thead>
+        <tbody>{rows.into_iter().map(|(rank_name, spot, chance)| html!(<tr>
+            <td>{text!("{}", rank_name)}</td>
+            <td>{gen_map_label(spot.map, pedia)}</td>
+            <td><a href={format!("map/{}#icon={}", map_page(spot.map), spot.pop_index)}>
+                {text!("#{}", spot.point.fishing_point_id)}
+            </a></td>
+            <td>{text!("{:.1}%", chance.appear * 100.0)}</td>
+            <td>{text!("{:.2}", chance.expected)}</td>
+        </tr>))}</tbody>
+        </table></div>
+    </div>))
+}
+
 fn gen_item_source_mix(
     item_id: ItemId,
     pedia: &Pedia,
@@ -938,6 +1077,8 @@ pub fn gen_item(
             {gen_item_source_monster(item.param.id, pedia_ex)}
             {gen_item_source_quest(item.param.id, pedia_ex)}
             {gen_item_source_map(item.param.id, pedia, pedia_ex)}
+            {gen_item_source_gather(item.param.id, pedia, pedia_ex)}
+            {gen_item_source_fishing(item.param.id, pedia, pedia_ex)}
             {gen_item_source_weapon(item.param.id, pedia_ex)}
             {gen_item_source_armor(item.param.id, pedia_ex)}
             {gen_item_source_mix(item.param.id, pedia, pedia_ex)}
@@ -1,4 +1,6 @@
+use super::fish::*;
 use super::gen_common::*;
+use super::gen_ec::*;
 use super::gen_item::*;
 use super::gen_monster::*;
 use super::gen_pedia::ITEM_ICON_SPECIAL_COLOR;
@@ -7,13 +9,15 @@ use super::hash_store::*;
 use super::pedia::*;
 use super::prepare_map::*;
 use super::sink::*;
+use super::spiribird::*;
 use crate::msg::*;
 use crate::rsz;
 use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
 use std::io::Write;
 use typed_html::{dom::*, elements::*, html, text};
 
-fn map_page(id: i32) -> String {
+pub fn map_page(id: i32) -> String {
     format!("{id:02}.html")
 }
 
@@ -44,6 +48,106 @@ pub fn gen_map_label(id: i32, pedia: &Pedia) -> Box<a<String>> {
     }
 }
 
+fn monster_route_json(id: i32) -> String {
+    format!("{id:02}_monster.json")
+}
+
+/// Static map marking where a monster spawns and sleeps
+pub fn gen_monster_route_diagram(map_id: i32, route: &MonsterRoute) -> Box<div<String>> {
+    let marker = |class: &'static str, label: String, point: &RoutePoint| {
+        point.map_position.map(|(x, y)| -> Box<div<String>> {
+            html!(<div class={class} style={format!("left:{x}%;top:{y}%")}>
+                {text!("{}", label)}
+            </div>)
+        })
+    };
+
+    let mut spawn_seen = HashSet::new();
+    let spawns = route
+        .spawn_sets
+        .iter()
+        .flat_map(|set| &set.choices)
+        .filter(|choice| spawn_seen.insert(choice.point.unique_id))
+        .filter_map(|choice| {
+            marker(
+                "mh-route-spawn",
+                choice.point.zone.to_string(),
+                &choice.point,
+            )
+        });
+    let sleeps = route
+        .sleep_points
+        .iter()
+        .filter_map(|point| marker("mh-route-sleep", "Z".to_owned(), point));
+
+    html!(<div class="mh-route-diagram">
+        <img alt="Map" src={format!("resources/map{map_id:02}_0/0/0_0.png")}/>
+        {spawns}
+        {sleeps}
+    </div>)
+}
+
+/// Tables of spawn chances and zone-to-zone movement of a monster on a map
+pub fn gen_monster_route_tables(route: &MonsterRoute) -> Vec<Box<div<String>>> {
+    let mut tables = vec![];
+    if route.spawn_sets.iter().any(|set| !set.choices.is_empty()) {
+        tables.push(html!(<div class="mh-table"><table>
+        <thead><tr>
+            <th>"Set"</th>
+            <th>"Zone"</th>
+            <th>"Spawn point"</th>
+            <th>"Probability"</th>
+        </tr></thead>
+        <tbody>
+        {route.spawn_sets.iter().flat_map(|set| set.choices.iter().map(move |choice| html!(<tr>
+            <td>{text!("{}", set.set_name)}</td>
+            <td>{text!("{}", choice.point.zone)}</td>
+            <td>{text!("@{}", choice.point.unique_id)}</td>
+            <td>{text!("{}%", choice.lot)}</td>
+        </tr>)))}
+        </tbody>
+    </table></div>));
+    }
+
+    if !route.routes.is_empty() {
+        tables.push(html!(<div class="mh-table"><table>
+        <thead><tr>
+            <th>"Route"</th>
+            <th>"Zone"</th>
+            <th>"From zone"</th>
+            <th>"Condition"</th>
+            <th>"Next zone"</th>
+        </tr></thead>
+        <tbody>
+        {route.routes.iter().flat_map(|r| r.block_info_list.iter().flat_map(move |block| {
+            block.lot_prev_block_info.iter().map(move |prev| {
+                let total: u32 = prev.lot_info_list.iter().map(|lot| lot.lot_value).sum();
+                let condition = match prev.move_status {
+                    rsz::MoveStatusType::None => "",
+                    rsz::MoveStatusType::LowStamina => "Exhausted",
+                    rsz::MoveStatusType::Dying => "Limping",
+                    rsz::MoveStatusType::LowStmDying => "Exhausted and limping",
+                };
+                html!(<tr>
+                    <td>{text!("{}", r.route_no)}</td>
+                    <td>{text!("{}", block.block_no)}</td>
+                    <td>{text!("{}", prev.prev_block_no)}</td>
+                    <td>{text!("{}", condition)}</td>
+                    <td>{prev.lot_info_list.iter().filter(|lot| lot.lot_value != 0).map(|lot| {
+                        let percent = lot.lot_value as f32 / total as f32 * 100.0;
+                        html!(<div>{text!("Zone {}, {:.0}% {}",
+                            lot.next_block_no, percent, lot.move_pattern.display())}</div>)
+                    })}</td>
+                </tr>)
+            })
+        }))}
+        </tbody>
+    </table></div>));
+    }
+
+    tables
+}
+
 // This is unfortunately hardcoded in the game code
 // So let's also hard code it here
 //
@@ -75,6 +179,14 @@ pub fn get_fish_item_id(fish_id: i32) -> Option<rsz::ItemId> {
     }
 }
 
+pub fn gen_fish_label(fish_id: i32, pedia_ex: &PediaEx) -> Box<div<String>> {
+    if let Some(item) = get_fish_item_id(fish_id) {
+        html!(<div class="il">{gen_item_label_from_id(item, pedia_ex)}</div>)
+    } else {
+        html!(<div class="il">{text!("Unknown fish {}", fish_id)}</div>)
+    }
+}
+
 // class snow.gui.SnowGuiCommonUtility.Icon {
 //     snow.gui.SnowGuiCommonUtility.Icon.ItemIconPatternNo getEcIconState(snow.envCreature.EnvironmentCreatureType type);
 // }
@@ -175,6 +287,39 @@ static EC_ID_MAP: &[&str] = &[
     "056_02", "057_00",
 ];
 
+/// Tag of an endemic life type in file names and messages, such as "002_01"
+pub fn get_ec_id(ec_type: i32) -> Option<&'static str> {
+    usize::try_from(ec_type)
+        .ok()
+        .and_then(|i| EC_ID_MAP.get(i))
+        .copied()
+}
+
+pub fn get_ec_name<'a>(ec_type: i32, pedia_ex: &PediaEx<'a>) -> Option<&'a MsgEntry> {
+    pedia_ex.ec_names.get(&ec_type).copied()
+}
+
+pub fn gen_ec_name(ec_type: i32, pedia_ex: &PediaEx) -> Box<span<String>> {
+    get_ec_name(ec_type, pedia_ex).map_or_else(
+        || html!(<span>{text!("Unknown {}", ec_type)}</span>),
+        gen_multi_lang,
+    )
+}
+
+pub fn get_ec_icon(ec_type: i32) -> Box<div<String>> {
+    let pattern = get_ec_icon_pattern(ec_type);
+    if ITEM_ICON_SPECIAL_COLOR.contains(&pattern) {
+        let icon_path = format!("resources/item/{pattern:03}.png");
+        html!(<div class="mh-icon-container">
+            <img alt="map icon" src={icon_path}
+                class="mh-wire-long-jump-icon undraggable" /*style={rotate}*/ draggable=false/>
+        </div>)
+    } else {
+        let icon_path = format!("resources/item/{pattern:03}");
+        gen_colored_icon_inner(get_ec_icon_color(ec_type), &icon_path, [], false)
+    }
+}
+
 pub fn gen_active_area(
     active_area_land: bool,
     active_area_water: bool,
@@ -226,16 +371,9 @@ fn gen_map(
                     } );
                     is_first = false;
 
-                    let item_id = get_fish_item_id(f.fish_id);
-                    let item = if let Some(item) = item_id {
-                        html!(<div class="il">{gen_item_label_from_id(item, pedia_ex)}</div>)
-                    } else {
-                        html!(<div class="il">{text!("Unknown fish {}", f.fish_id)}</div>)
-                    };
-
                     html!(<tr>
                         {first}
-                        <td>{ item }</td>
+                        <td>{ gen_fish_label(f.fish_id, pedia_ex) }</td>
                         <td>{text!("{}%", f.spawn_rate)}</td>
                     </tr>)
                 })
@@ -243,61 +381,28 @@ fn gen_map(
         } </tbody></table></div></div>)
     };
 
-    let ec_name = pedia.ec_name.get_name_map();
-    let ec_name_mr = pedia.ec_name_mr.get_name_map();
-    let get_ec_name = |type_: i32| {
-        usize::try_from(type_)
-            .ok()
-            .and_then(|i| EC_ID_MAP.get(i))
-            .and_then(|id| {
-                let tag = format!("EC_NAME_{id}");
-                if let Some(msg) = ec_name.get(&tag) {
-                    return Some(*msg);
-                }
-                let tag_mr = format!("EC_NAME_{id}_MR");
-                if let Some(msg) = ec_name_mr.get(&tag_mr) {
-                    return Some(*msg);
-                }
-                None
-            })
-            .map_or_else(
-                || html!(<span>{text!("Unknown {}", type_)}</span>),
-                gen_multi_lang,
-            )
-    };
-    let get_ec_icon = |type_: i32| {
-        let pattern = get_ec_icon_pattern(type_);
-        if ITEM_ICON_SPECIAL_COLOR.contains(&pattern) {
-            let icon_path = format!("resources/item/{pattern:03}.png");
-            html!(<div class="mh-icon-container">
-                <img alt="map icon" src={icon_path}
-                    class="mh-wire-long-jump-icon undraggable" /*style={rotate}*/ draggable=false/>
-            </div>)
-        } else {
-            let icon_path = format!("resources/item/{pattern:03}");
-            gen_colored_icon_inner(get_ec_icon_color(type_), &icon_path, [], false)
-        }
+    let gen_fish_chance_table = |tag: &str, chances: Vec<FishChance>| -> Box<div<String>> {
+        html!(<div class="mh-reward-box"><div class="mh-table"><table>
+        <thead><tr>
+        <th>{text!("{}", tag)}</th>
+        <th>"Chance to appear"</th>
+        <th>"Average count"</th>
+        </tr></thead>
+        <tbody> {
+            chances.into_iter().map(|chance| html!(<tr>
+                <td>{ gen_fish_label(chance.fish_id, pedia_ex) }</td>
+                <td>{text!("{:.1}%", chance.appear * 100.0)}</td>
+                <td>{text!("{:.2}", chance.expected)}</td>
+            </tr>))
+        } </tbody></table></div></div>)
     };
 
+    let get_ec_name = |type_: i32| gen_ec_name(type_, pedia_ex);
+
     let mut map_icons = vec![];
     let mut map_explains = vec![];
     for (i, pop) in map.pops.iter().enumerate() {
-        let mut px = pop.position.x;
-        let mut py = pop.position.y;
-        // snow.gui.QuestUIManager.isInNo42SafeZone
-        if id == 15
-            && (-43.0..=136.0).contains(&pop.position.x)
-            && (163.0..=197.0).contains(&pop.position.z)
-            && (56.0..=252.0).contains(&pop.position.y)
-        {
-            // Some magic constant from code and gui resource...
-            let magic_size = 1080.0;
-            px += -200.0 / magic_size * map.map_scale;
-            py += 100.0 / magic_size * map.map_scale;
-        }
-
-        let x = (px + map.x_offset) / map.map_scale * 100.0;
-        let y = (py + map.y_offset) / map.map_scale * 100.0;
+        let (x, y) = map.map_position(id, &pop.position);
 
         let icon_inner: Box<dyn Fn() -> Box<div<String>>>;
         let explain_inner;
@@ -337,9 +442,15 @@ fn gen_map(
                     .get(&(behavior.pop_id, id))
                     .or_else(|| pedia_ex.item_pop.get(&(behavior.pop_id, -1)))
                 {
+                    let respawn = if behavior.one_time_only_flag {
+                        "Can only be gathered once per quest"
+                    } else {
+                        "Respawns during the quest"
+                    };
                     explain_inner = html!(
                         <div class="mh-reward-tables">
                         { relic_explain }
+                        <p>{text!("{} draws per gathering. {}", lot.lot_count, respawn)}</p>
                         <div class="mh-reward-box"><div class="mh-table"><table>
                             <thead><tr>
                             <th>"Low rank material"</th>
@@ -429,13 +540,19 @@ fn gen_map(
                 floor = pop_marker.as_ref().map(|pop| pop.map_floor_type);
                 icon_inner = Box::new(|| gen_colored_icon(0, "resources/item/046", [], false));
 
+                let spawn = behavior.fish_spawn_data.unwrap();
                 explain_inner = html!(<div class="mh-reward-tables">
-                    { gen_fish_table("Low rank fish",
-                        &behavior.fish_spawn_data.unwrap().spawn_group_list_info_low) }
-                    { gen_fish_table("High rank fish",
-                        &behavior.fish_spawn_data.unwrap().spawn_group_list_info_high) }
-                    { gen_fish_table("Master rank fish",
-                        &behavior.fish_spawn_data.unwrap().spawn_group_list_info_master) }
+                    <p>{text!("Up to {} fish", behavior.fish_num_max)}</p>
+                    { gen_fish_chance_table("Low rank fish",
+                        fish_chances(&spawn.spawn_group_list_info_low, behavior.fish_num_max)) }
+                    { gen_fish_chance_table("High rank fish",
+                        fish_chances(&spawn.spawn_group_list_info_high, behavior.fish_num_max)) }
+                    { gen_fish_chance_table("Master rank fish",
+                        fish_chances(&spawn.spawn_group_list_info_master, behavior.fish_num_max)) }
+                    { gen_fish_table("Low rank fish group", &spawn.spawn_group_list_info_low) }
+                    { gen_fish_table("High rank fish group", &spawn.spawn_group_list_info_high) }
+                    { gen_fish_table("Master rank fish group",
+                        &spawn.spawn_group_list_info_master) }
                 </div>);
 
                 filter = "fish";
@@ -458,10 +575,12 @@ fn gen_map(
 
                 filter = "camp";
             }
-            MapPopKind::Ec { behavior } => {
+            MapPopKind::Ec { behavior, .. } => {
                 floor = Some(behavior.base.map_floor_type);
                 icon_inner = Box::new(|| get_ec_icon(behavior.base.type_));
-                let name = get_ec_name(behavior.base.type_);
+                let name = html!(<a href={format!("ec/{}", ec_page(behavior.base.type_))}>
+                    {get_ec_name(behavior.base.type_)}
+                </a>);
 
                 let extra = match &behavior.extra {
                     rsz::ec::Extra::Ec055(extra) => vec![
@@ -833,6 +952,63 @@ fn gen_map(
         </div>))
     }
 
+    // Petalaces with the same stats share one route
+    let mut petalaces: Vec<&BuffCage> = pedia_ex.buff_cage.values().collect();
+    petalaces.sort_unstable_by_key(|p| (p.data.sort_index, p.data.id));
+    let mut petalace_groups: Vec<Vec<&BuffCage>> = vec![];
+    for petalace in petalaces {
+        let stats = |p: &BuffCage| {
+            (
+                p.data.status_buff_add_value,
+                p.data.status_buff_all_add_value,
+                p.data.status_buff_limit,
+            )
+        };
+        match petalace_groups
+            .iter_mut()
+            .find(|group| stats(group[0]) == stats(petalace))
+        {
+            Some(group) => group.push(petalace),
+            None => petalace_groups.push(vec![petalace]),
+        }
+    }
+    let spiribird_routes: Vec<(Vec<&BuffCage>, SpiribirdRoute)> = petalace_groups
+        .into_iter()
+        .filter_map(|group| {
+            let route = plan_spiribird_route(map, group[0].data)?;
+            Some((group, route))
+        })
+        .collect();
+
+    let spiribird_overlays = spiribird_routes.iter().enumerate().map(|(k, (_, route))| {
+        let step = |label: String, pop_index: usize| {
+            let (x, y) = map.map_position(id, &map.pops[pop_index].position);
+            html!(<div class="mh-spiribird-step" style={format!("left:{x}%;top:{y}%")}>
+                {text!("{}", label)}
+            </div>)
+        };
+        let html_id = format!("mh-spiribird-route-{k}");
+        html!(<div class="mh-spiribird-route mh-hidden" id={html_id.as_str()}>
+            {step("C".to_owned(), route.start)}
+            {route.stops.iter().enumerate().map(|(n, stop)| step((n + 1).to_string(), stop.pop_index))}
+        </div>)
+    });
+
+    let spiribird_select = (!spiribird_routes.is_empty()).then(|| {
+        html!(<div class="select"><select id="mh-spiribird-select">
+            <option value="none">"No spiribird route"</option>
+            {spiribird_routes.iter().enumerate().map(|(k, (group, _))| {
+                let names: Vec<String> = group
+                    .iter()
+                    .map(|p| translate_msg_plain(&p.name.content[1]))
+                    .collect();
+                html!(<option value={k.to_string()}>
+                    {text!("Spiribird route: {}", names.join(" / "))}
+                </option>)
+            })}
+        </select></div>)
+    });
+
     let name = get_map_name(id, pedia);
 
     let title = if let Some(name) = name {
@@ -857,10 +1033,11 @@ fn gen_map(
             <li id="mh-map-filter-button-fish" class="mh-map-filter-button"><a>"Fishing points"</a></li>
             <li id="mh-map-filter-button-ec" class="mh-map-filter-button"><a>"Endemic life"</a></li>
             <li id="mh-map-filter-button-bush" class="mh-map-filter-button"><a>"Bush"</a></li>
-            <li id="mh-map-filter-button-behavior" class="mh-map-filter-button"><a>"Behavior"</a></li>
-            <li id="mh-map-filter-button-spawn" class="mh-map-filter-button"><a>"Spawn"</a></li>
+            <li id="mh-map-filter-button-behavior" class="mh-map-filter-button"><a>"Monster routes"</a></li>
+            <li id="mh-map-filter-button-spawn" class="mh-map-filter-button"><a>"Monster spawns"</a></li>
             <li id="mh-map-filter-button-fg" class="mh-map-filter-button"><a>"Other"</a></li>
             </ul></div>
+            { spiribird_select }
 
             <div class="columns">
 
@@ -870,17 +1047,23 @@ fn gen_map(
                     <div class="mh-map" id="mh-map">
                     {(0..map.layer_count).map(|j| {
                         let c = if j == 0 {
-                            "mh-map-layer undraggable"
+                            "mh-map-layer"
                         } else {
-                            "mh-map-layer undraggable mh-hidden"
+                            "mh-map-layer mh-hidden"
                         };
                         let html_id = format!("mh-map-layer-{j}");
+                        let tiles = format!("resources/map{id:02}_{j}");
+                        let levels = map.tile_levels.get(j).copied().unwrap_or(1).to_string();
                         html!(
-                            <img alt="Map" class={c} id={html_id.as_str()} draggable=false
-                                src={format!("resources/map{id:02}_{j}.png")}/>
+                            <div class={c} id={html_id.as_str()} data-tiles={tiles.as_str()}
+                                data-levels=levels>
+                                <img alt="Map" class="mh-map-tile-base undraggable" draggable=false
+                                    src={format!("{tiles}/0/0_0.png")}/>
+                            </div>
                         )
                     })}
                     { map_icons }
+                    { spiribird_overlays }
                     </div>
                 </div>
 
@@ -891,6 +1074,9 @@ fn gen_map(
                     <button class="button" id="button-scale-up">
                         <span class="icon"><i class="fas fa-magnifying-glass-plus"></i></span>
                     </button>
+                    <button class="button" id="button-map-reset">
+                        <span>"Reset"</span>
+                    </button>
                     {
                         (map.layer_count > 1).then(||html!(
                             <button class="button" id="button-map-layer">
@@ -940,6 +1126,190 @@ fn gen_map(
         });
     };
 
+    let fishing_spots: Vec<&FishingSpot> = pedia_ex
+        .fishing_spots
+        .iter()
+        .filter(|spot| spot.map == id)
+        .collect();
+    if !fishing_spots.is_empty() {
+        let ranks = [
+            (rsz::QuestRank::Low, "Low rank"),
+            (rsz::QuestRank::High, "High rank"),
+            (rsz::QuestRank::Master, "Master rank"),
+        ];
+        let mut rows = vec![];
+        for (rank, rank_name) in ranks {
+            // fish_id -> (best chance, best spot, number of spots)
+            let mut best: BTreeMap<i32, (f64, usize, usize)> = BTreeMap::new();
+            for spot in &fishing_spots {
+                for chance in spot.chances(rank) {
+                    let entry = best
+                        .entry(chance.fish_id)
+                        .or_insert((0.0, spot.pop_index, 0));
+                    if chance.appear > entry.0 {
+                        entry.0 = chance.appear;
+                        entry.1 = spot.pop_index;
+                    }
+                    entry.2 += 1;
+                }
+            }
+            rows.extend(
+                best.into_iter()
+                    .map(|(fish_id, (appear, pop_index, spot_count))| {
+                        html!(<tr>
+                    <td>{text!("{}", rank_name)}</td>
+                    <td>{gen_fish_label(fish_id, pedia_ex)}</td>
+                    <td><a href={format!("map/{}#icon={pop_index}", map_page(id))}>
+                        {text!("{:.1}%", appear * 100.0)}
+                    </a></td>
+                    <td>{text!("{}", spot_count)}</td>
+                </tr>)
+                    }),
+            );
+        }
+
+        sections.push(Section {
+            title: "Fishing".to_owned(),
+            content: html!(
+                <section id="s-fishing">
+                <h2>"Fishing"</h2>
+                <div class="mh-table"><table>
+                    <thead><tr>
+                        <th>"Rank"</th>
+                        <th>"Fish"</th>
+                        <th>"Best chance to appear"</th>
+                        <th>"Number of spots"</th>
+                    </tr></thead>
+                    <tbody>{rows}</tbody>
+                </table></div>
+                </section>
+            ),
+        });
+    }
+
+    let endemic_life: Vec<_> = pedia_ex
+        .endemic_life
+        .iter()
+        .filter_map(|(&type_, ec)| {
+            let pops: Vec<&EndemicLifePop> = ec.pops.iter().filter(|pop| pop.map == id).collect();
+            (!pops.is_empty()).then_some((type_, pops))
+        })
+        .collect();
+    if !endemic_life.is_empty() {
+        sections.push(Section {
+            title: "Endemic life".to_owned(),
+            content: html!(
+                <section id="s-ec">
+                <h2>"Endemic life"</h2>
+                <div class="mh-table"><table>
+                    <thead><tr>
+                        <th>"Name"</th>
+                        <th>"Count"</th>
+                        <th>"Points"</th>
+                    </tr></thead>
+                    <tbody>{endemic_life.into_iter().map(|(type_, pops)| html!(<tr>
+                        <td>{gen_ec_label(type_, pedia_ex)}</td>
+                        <td>{text!("{}", pops.len())}</td>
+                        <td>{pops.iter().enumerate().map(|(i, pop)| html!(<span>
+                            <a href={format!("map/{}#icon={}", map_page(id), pop.pop_index)}>
+                                {text!("#{}", i + 1)}
+                            </a>" "
+                        </span>))}</td>
+                    </tr>))}</tbody>
+                </table></div>
+                </section>
+            ),
+        });
+    }
+
+    if !spiribird_routes.is_empty() {
+        let summaries = spiribird_routes.iter().enumerate().map(|(k, (group, route))| {
+            let html_id = format!("mh-spiribird-summary-{k}");
+            let gains = SPIRIBIRD_STATS.iter().enumerate().map(|(i, stat)| {
+                html!(<p class="mh-kv"><span>{text!("{}", stat)}</span>
+                <span>{text!("+{} / {}", route.gain[i], route.limit[i])}</span></p>)
+            });
+            html!(<div class="mh-spiribird-summary mh-hidden" id={html_id.as_str()}>
+                <ul class="mh-item-list">{group.iter().map(|petalace| html!(<li>
+                    {gen_buff_cage_label(petalace)}
+                </li>))}</ul>
+                <div class="mh-kvlist">
+                <p class="mh-kv"><span>"Start"</span>
+                <span><a href={format!("map/{}#icon={}", map_page(id), route.start)}>"Camp"</a></span></p>
+                <p class="mh-kv"><span>"Total distance"</span>
+                <span>{text!("{:.0}", route.distance)}</span></p>
+                {gains}
+                </div>
+                {(!route.complete).then(|| html!(<p>
+                    "There are not enough spiribirds on this map to reach all limits."
+                </p>))}
+                <div class="mh-table"><table>
+                    <thead><tr>
+                        <th>"Step"</th>
+                        <th>"Spiribird"</th>
+                        <th>"Point"</th>
+                        <th>"Distance"</th>
+                    </tr></thead>
+                    <tbody>{route.stops.iter().enumerate().map(|(n, stop)| {
+                        let bird = match &map.pops[stop.pop_index].kind {
+                            MapPopKind::Ec { behavior, .. } => gen_ec_label(behavior.base.type_, pedia_ex),
+                            _ => unreachable!(),
+                        };
+                        html!(<tr>
+                            <td>{text!("{}", n + 1)}</td>
+                            <td>{bird}</td>
+                            <td><a href={format!("map/{}#icon={}", map_page(id), stop.pop_index)}>
+                                {text!("#{}", stop.pop_index)}
+                            </a></td>
+                            <td>{text!("{:.0}", stop.distance)}</td>
+                        </tr>)
+                    })}</tbody>
+                </table></div>
+            </div>)
+        });
+        sections.push(Section {
+            title: "Spiribird route".to_owned(),
+            content: html!(
+                <section id="s-spiribird">
+                <h2>"Spiribird route"</h2>
+                <p>"Spiribirds to collect from a camp until the stat limits of a petalace are reached."</p>
+                <p>"Pick a petalace above the map to show the steps on it."</p>
+                <p>"Distances are in straight lines and ignore the terrain."</p>
+                <div class="mh-spiribird-summary" id="mh-spiribird-summary-none">
+                    "No petalace selected."
+                </div>
+                { summaries }
+                </section>
+            ),
+        });
+    }
+
+    let routes = pedia_ex
+        .monster_routes
+        .get(&id)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if !routes.is_empty() {
+        serde_json::to_writer(path.create(&monster_route_json(id))?, routes)?;
+        sections.push(Section {
+            title: "Monster spawns".to_owned(),
+            content: html!(
+                <section id="s-monster-route">
+                <h2>"Monster spawns"</h2>
+                <p>"Numbers mark the zones where a monster may start. Z marks where it sleeps. "
+                <a href={format!("map/{}", monster_route_json(id))}>"Download as JSON"</a>
+                </p>
+                <ul class="mh-route-list">{
+                    routes.iter().map(|route| html!(<li>
+                        {gen_monster_tag(pedia_ex, route.em_type, false, false, None, None)}
+                        {gen_monster_route_diagram(id, route)}
+                    </li>))
+                }</ul>
+                </section>
+            ),
+        });
+    }
+
     let doc: DOMTree<String> = html!(
         <html lang="en">
             <head itemscope=true>
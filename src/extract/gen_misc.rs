@@ -1076,6 +1076,7 @@ fn gen_misc_page(hash_store: &HashStore, folder: &impl Sink) -> Result<()> {
                 <a href="misc/meowcenaries.html">"Meowcenaries"</a>
                 <a href="misc/scraps.html">"Trade for scraps"</a>
                 <a href="dlc.html">"DLC"</a>
+                <a href="ec.html">"Endemic life"</a>
                 <a href="misc/award.html">"Awards"</a>
                 <a href="misc/achievement.html">"Guild card titles"</a>
                 </div>
@@ -2451,8 +2451,14 @@ pub fn gen_monster(
                 </td>)
             };
 
+            let route = pedia_ex
+                .monster_routes
+                .get(&map)
+                .and_then(|routes| routes.iter().find(|r| r.em_type == monster.em_type));
+
             html!(<section>
             <h3>{gen_map_label(map, pedia)}</h3>
+            {route.map(|route| gen_monster_route_diagram(map, route))}
             <div class="mh-kvlist">
             {behavior.block_move.iter().flat_map(|block_move|
                 [
@@ -2527,6 +2533,7 @@ pub fn gen_monster(
             })}
             </tbody>
             </table></div>
+            {route.into_iter().flat_map(gen_monster_route_tables)}
             </section>)
         })}
         </section>),
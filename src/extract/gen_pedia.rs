@@ -1,3 +1,5 @@
+use super::fish::*;
+use super::gen_map::get_ec_id;
 use super::logger::*;
 use super::pedia::*;
 use super::prepare_map::*;
@@ -6,7 +8,7 @@ use crate::gpu::*;
 use crate::gui::*;
 use crate::mesh::*;
 use crate::msg::*;
-use crate::pak::PakReader;
+use crate::pak::{PakReader, PakSource};
 use crate::pfb::Pfb;
 use crate::rcol::Rcol;
 use crate::rsz::*;
@@ -22,7 +24,7 @@ use std::collections::BTreeSet;
 use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Write as _;
-use std::io::{Cursor, Read, Seek, Write};
+use std::io::{Cursor, Write};
 use std::ops::Deref;
 
 pub static ITEM_ICON_SPECIAL_COLOR: [i32; 7] = [93, 115, 121, 123, 178, 179, 189];
@@ -260,7 +262,7 @@ pub fn gen_collider_mapping(rcol: Rcol) -> Result<ColliderMapping> {
 }
 
 pub fn pfb_user<T: FromRsz + 'static>(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     pfb: &Pfb,
     version_hint: Option<u32>,
 ) -> Result<T> {
@@ -279,7 +281,7 @@ pub fn pfb_user<T: FromRsz + 'static>(
 
 #[allow(clippy::too_many_arguments)]
 pub fn gen_monsters(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     pfb_path_gen: fn(u32, u32) -> String,
     boss_init_path_gen: fn(u32, u32) -> Option<String>,
     collider_path_gen: fn(u32, u32) -> String,
@@ -479,13 +481,37 @@ pub fn gen_monsters(
     Ok(monsters)
 }
 
-fn get_msg(pak: &mut PakReader<impl Read + Seek>, path: &str) -> Result<Msg> {
+/// Loads a MSG file, and logs the tags and colors in it that the markup parser doesn't know
+fn get_msg(pak: &PakReader<impl PakSource>, path: &str, logger: &mut Logger) -> Result<Msg> {
     let index = pak.find_file(path)?;
-    Msg::new(Cursor::new(pak.read_file(index)?))
+    let msg = Msg::new(Cursor::new(pak.read_file(index)?))?;
+
+    // Counts and the first entry of each warning
+    let mut warnings: BTreeMap<String, (usize, String)> = BTreeMap::new();
+    for entry in &msg.entries {
+        for content in &entry.content {
+            for warning in markup::Markup::parse(content).warnings {
+                if !warning.is_parse_error() {
+                    warnings
+                        .entry(warning.to_string())
+                        .or_insert_with(|| (0, entry.name.clone()))
+                        .0 += 1;
+                }
+            }
+        }
+    }
+    if !warnings.is_empty() {
+        lscope!(logger, "{path}");
+        for (warning, (count, first)) in warnings {
+            writeln!(logger, "{warning}, {count} time(s), first in {first}")?;
+        }
+    }
+
+    Ok(msg)
 }
 
 fn get_user<T: FromUser>(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     path: &str,
     version_hint: Option<u32>,
 ) -> Result<T> {
@@ -497,7 +523,7 @@ fn get_user<T: FromUser>(
 }
 
 fn get_user_opt<T: FromUser>(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     path: &str,
     version_hint: Option<u32>,
 ) -> Result<Option<T>> {
@@ -514,15 +540,15 @@ fn get_user_opt<T: FromUser>(
     Ok(Some(user))
 }
 
-fn get_singleton<T: 'static + SingletonUser>(
-    pak: &mut PakReader<impl Read + Seek>,
+pub fn get_singleton<T: 'static + SingletonUser>(
+    pak: &PakReader<impl PakSource>,
     version_hint: Option<u32>,
 ) -> Result<T> {
     Ok(T::from_rsz(get_user(pak, T::PATH, version_hint)?))
 }
 
 fn get_singleton_opt<T: 'static + SingletonUser>(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     version_hint: Option<u32>,
 ) -> Result<Option<T>> {
     if let Some(user) = get_user_opt(pak, T::PATH, version_hint)? {
@@ -533,9 +559,10 @@ fn get_singleton_opt<T: 'static + SingletonUser>(
 }
 
 fn get_weapon_list<BaseData: FromUser>(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     weapon_class: &str,
     version_hint: Option<u32>,
+    logger: &mut Logger,
 ) -> Result<WeaponList<BaseData>> {
     Ok(WeaponList {
         base_data: get_user(
@@ -580,24 +607,28 @@ fn get_weapon_list<BaseData: FromUser>(
         name: get_msg(
             pak,
             &format!("data/Define/Player/Weapon/{weapon_class}/{weapon_class}_Name.msg"),
+            logger,
         )?,
         explain: get_msg(
             pak,
             &format!("data/Define/Player/Weapon/{weapon_class}/{weapon_class}_Explain.msg"),
+            logger,
         )?,
         name_mr: get_msg(
             pak,
             &format!("data/Define/Player/Weapon/{weapon_class}/{weapon_class}_Name_MR.msg"),
+            logger,
         )?,
         explain_mr: get_msg(
             pak,
             &format!("data/Define/Player/Weapon/{weapon_class}/{weapon_class}_Explain_MR.msg"),
+            logger,
         )?,
     })
 }
 
 fn get_version_hint<T: 'static + SingletonUser, U: FromRsz>(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
 ) -> Result<u32> {
     let index = pak.find_file(T::PATH)?;
     let user = User::new(Cursor::new(pak.read_file(index)?))?;
@@ -613,11 +644,7 @@ fn get_version_hint<T: 'static + SingletonUser, U: FromRsz>(
     bail!("Type not found for version hint")
 }
 
-pub fn gen_pedia(
-    pak: &mut PakReader<impl Read + Seek>,
-    sha: bool,
-    logger: &mut Logger,
-) -> Result<Pedia> {
+pub fn gen_pedia(pak: &PakReader<impl PakSource>, sha: bool, logger: &mut Logger) -> Result<Pedia> {
     lscope!(logger, "pedia");
     let sha = if sha { pak.sha256()? } else { vec![] };
 
@@ -669,288 +696,423 @@ pub fn gen_pedia(
     )
     .context("Generating small monsters")?;
 
-    let monster_names = get_msg(pak, "Message/Tag/Tag_EM_Name.msg")?;
-    let monster_aliases = get_msg(pak, "Message/Tag/Tag_EM_Name_Alias.msg")?;
-    let monster_explains = get_msg(pak, "Message/HunterNote/HN_MonsterListMsg.msg")?;
+    let monster_names = get_msg(pak, "Message/Tag/Tag_EM_Name.msg", logger)?;
+    let monster_aliases = get_msg(pak, "Message/Tag/Tag_EM_Name_Alias.msg", logger)?;
+    let monster_explains = get_msg(pak, "Message/HunterNote/HN_MonsterListMsg.msg", logger)?;
 
-    let monster_names_mr = get_msg(pak, "Message/Tag_MR/Tag_EM_Name_MR.msg")?;
-    let monster_aliases_mr = get_msg(pak, "Message/Tag_MR/Tag_EM_Name_Alias_MR.msg")?;
-    let monster_explains_mr = get_msg(pak, "Message/HunterNote_MR/HN_MonsterListMsg_MR.msg")?;
+    let monster_names_mr = get_msg(pak, "Message/Tag_MR/Tag_EM_Name_MR.msg", logger)?;
+    let monster_aliases_mr = get_msg(pak, "Message/Tag_MR/Tag_EM_Name_Alias_MR.msg", logger)?;
+    let monster_explains_mr = get_msg(
+        pak,
+        "Message/HunterNote_MR/HN_MonsterListMsg_MR.msg",
+        logger,
+    )?;
 
     let condition_preset: EnemyConditionPresetData = get_singleton(pak, version_hint)?;
     condition_preset.verify()?;
 
-    let hunter_note_msg = get_msg(pak, "Message/HunterNote/HN_Hunternote_Menu.msg")?;
-    let hunter_note_msg_mr = get_msg(pak, "Message/HunterNote_MR/HN_Hunternote_Menu_MR.msg")?;
-
-    let quest_hall_msg = get_msg(pak, "Message/Quest/QuestData_Hall.msg")?;
-    let quest_hall_msg_mr = get_msg(pak, "Message/Quest/QuestData_Hall_MR.msg")?;
-    let quest_hall_msg_mr2 = get_msg(pak, "Message/Quest/QuestData_Hall2_MR.msg")?;
-    let quest_village_msg = get_msg(pak, "Message/Quest/QuestData_Village.msg")?;
-    let quest_village_msg_mr = get_msg(pak, "Message/Quest/QuestData_Village_MR.msg")?;
-    let quest_tutorial_msg = get_msg(pak, "Message/Quest/QuestData_Tutorial.msg")?;
-    let quest_arena_msg = get_msg(pak, "Message/Quest/QuestData_Arena.msg")?;
-    let quest_dlc_msg = get_msg(pak, "Message/Quest/QuestData_Dlc.msg")?;
-    let npc_mission_msg = get_msg(pak, "Message/Quest/QuestData_NpcMission.msg")?;
-    let npc_mission_msg_mr = get_msg(pak, "Message/Quest/QuestData_NpcMission_MR.msg")?;
-
-    let armor_head_name_msg = get_msg(pak, "data/Define/Player/Armor/Head/A_Head_Name.msg")?;
-    let armor_chest_name_msg = get_msg(pak, "data/Define/Player/Armor/Chest/A_Chest_Name.msg")?;
-    let armor_arm_name_msg = get_msg(pak, "data/Define/Player/Armor/Arm/A_Arm_Name.msg")?;
-    let armor_waist_name_msg = get_msg(pak, "data/Define/Player/Armor/Waist/A_Waist_Name.msg")?;
-    let armor_leg_name_msg = get_msg(pak, "data/Define/Player/Armor/Leg/A_Leg_Name.msg")?;
-    let armor_head_explain_msg = get_msg(pak, "data/Define/Player/Armor/Head/A_Head_Explain.msg")?;
-    let armor_chest_explain_msg =
-        get_msg(pak, "data/Define/Player/Armor/Chest/A_Chest_Explain.msg")?;
-    let armor_arm_explain_msg = get_msg(pak, "data/Define/Player/Armor/Arm/A_Arm_Explain.msg")?;
-    let armor_waist_explain_msg =
-        get_msg(pak, "data/Define/Player/Armor/Waist/A_Waist_Explain.msg")?;
-    let armor_leg_explain_msg = get_msg(pak, "data/Define/Player/Armor/Leg/A_Leg_Explain.msg")?;
-    let armor_series_name_msg =
-        get_msg(pak, "data/Define/Player/Armor/ArmorSeries_Hunter_Name.msg")?;
-
-    let armor_head_name_msg_mr = get_msg(pak, "data/Define/Player/Armor/Head/A_Head_Name_MR.msg")?;
-    let armor_chest_name_msg_mr =
-        get_msg(pak, "data/Define/Player/Armor/Chest/A_Chest_Name_MR.msg")?;
-    let armor_arm_name_msg_mr = get_msg(pak, "data/Define/Player/Armor/Arm/A_Arm_Name_MR.msg")?;
-    let armor_waist_name_msg_mr =
-        get_msg(pak, "data/Define/Player/Armor/Waist/A_Waist_Name_MR.msg")?;
-    let armor_leg_name_msg_mr = get_msg(pak, "data/Define/Player/Armor/Leg/A_Leg_Name_MR.msg")?;
-    let armor_head_explain_msg_mr =
-        get_msg(pak, "data/Define/Player/Armor/Head/A_Head_Explain_MR.msg")?;
-    let armor_chest_explain_msg_mr =
-        get_msg(pak, "data/Define/Player/Armor/Chest/A_Chest_Explain_MR.msg")?;
-    let armor_arm_explain_msg_mr =
-        get_msg(pak, "data/Define/Player/Armor/Arm/A_Arm_Explain_MR.msg")?;
-    let armor_waist_explain_msg_mr =
-        get_msg(pak, "data/Define/Player/Armor/Waist/A_Waist_Explain_MR.msg")?;
-    let armor_leg_explain_msg_mr =
-        get_msg(pak, "data/Define/Player/Armor/Leg/A_Leg_Explain_MR.msg")?;
+    let hunter_note_msg = get_msg(pak, "Message/HunterNote/HN_Hunternote_Menu.msg", logger)?;
+    let hunter_note_msg_mr = get_msg(
+        pak,
+        "Message/HunterNote_MR/HN_Hunternote_Menu_MR.msg",
+        logger,
+    )?;
+
+    let quest_hall_msg = get_msg(pak, "Message/Quest/QuestData_Hall.msg", logger)?;
+    let quest_hall_msg_mr = get_msg(pak, "Message/Quest/QuestData_Hall_MR.msg", logger)?;
+    let quest_hall_msg_mr2 = get_msg(pak, "Message/Quest/QuestData_Hall2_MR.msg", logger)?;
+    let quest_village_msg = get_msg(pak, "Message/Quest/QuestData_Village.msg", logger)?;
+    let quest_village_msg_mr = get_msg(pak, "Message/Quest/QuestData_Village_MR.msg", logger)?;
+    let quest_tutorial_msg = get_msg(pak, "Message/Quest/QuestData_Tutorial.msg", logger)?;
+    let quest_arena_msg = get_msg(pak, "Message/Quest/QuestData_Arena.msg", logger)?;
+    let quest_dlc_msg = get_msg(pak, "Message/Quest/QuestData_Dlc.msg", logger)?;
+    let npc_mission_msg = get_msg(pak, "Message/Quest/QuestData_NpcMission.msg", logger)?;
+    let npc_mission_msg_mr = get_msg(pak, "Message/Quest/QuestData_NpcMission_MR.msg", logger)?;
+
+    let armor_head_name_msg =
+        get_msg(pak, "data/Define/Player/Armor/Head/A_Head_Name.msg", logger)?;
+    let armor_chest_name_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/Chest/A_Chest_Name.msg",
+        logger,
+    )?;
+    let armor_arm_name_msg = get_msg(pak, "data/Define/Player/Armor/Arm/A_Arm_Name.msg", logger)?;
+    let armor_waist_name_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/Waist/A_Waist_Name.msg",
+        logger,
+    )?;
+    let armor_leg_name_msg = get_msg(pak, "data/Define/Player/Armor/Leg/A_Leg_Name.msg", logger)?;
+    let armor_head_explain_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/Head/A_Head_Explain.msg",
+        logger,
+    )?;
+    let armor_chest_explain_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/Chest/A_Chest_Explain.msg",
+        logger,
+    )?;
+    let armor_arm_explain_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/Arm/A_Arm_Explain.msg",
+        logger,
+    )?;
+    let armor_waist_explain_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/Waist/A_Waist_Explain.msg",
+        logger,
+    )?;
+    let armor_leg_explain_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/Leg/A_Leg_Explain.msg",
+        logger,
+    )?;
+    let armor_series_name_msg = get_msg(
+        pak,
+        "data/Define/Player/Armor/ArmorSeries_Hunter_Name.msg",
+        logger,
+    )?;
+
+    let armor_head_name_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Head/A_Head_Name_MR.msg",
+        logger,
+    )?;
+    let armor_chest_name_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Chest/A_Chest_Name_MR.msg",
+        logger,
+    )?;
+    let armor_arm_name_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Arm/A_Arm_Name_MR.msg",
+        logger,
+    )?;
+    let armor_waist_name_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Waist/A_Waist_Name_MR.msg",
+        logger,
+    )?;
+    let armor_leg_name_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Leg/A_Leg_Name_MR.msg",
+        logger,
+    )?;
+    let armor_head_explain_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Head/A_Head_Explain_MR.msg",
+        logger,
+    )?;
+    let armor_chest_explain_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Chest/A_Chest_Explain_MR.msg",
+        logger,
+    )?;
+    let armor_arm_explain_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Arm/A_Arm_Explain_MR.msg",
+        logger,
+    )?;
+    let armor_waist_explain_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Waist/A_Waist_Explain_MR.msg",
+        logger,
+    )?;
+    let armor_leg_explain_msg_mr = get_msg(
+        pak,
+        "data/Define/Player/Armor/Leg/A_Leg_Explain_MR.msg",
+        logger,
+    )?;
     let armor_series_name_msg_mr = get_msg(
         pak,
         "data/Define/Player/Armor/ArmorSeries_Hunter_Name_MR.msg",
+        logger,
     )?;
 
     let player_skill_detail_msg = get_msg(
         pak,
         "data/Define/Player/Skill/PlEquipSkill/PlayerSkill_Detail.msg",
+        logger,
     )?;
     let player_skill_explain_msg = get_msg(
         pak,
         "data/Define/Player/Skill/PlEquipSkill/PlayerSkill_Explain.msg",
+        logger,
     )?;
     let player_skill_name_msg = get_msg(
         pak,
         "data/Define/Player/Skill/PlEquipSkill/PlayerSkill_Name.msg",
+        logger,
     )?;
     let player_skill_detail_msg_mr = get_msg(
         pak,
         "data/Define/Player/Skill/PlEquipSkill/PlayerSkill_Detail_MR.msg",
+        logger,
     )?;
     let player_skill_explain_msg_mr = get_msg(
         pak,
         "data/Define/Player/Skill/PlEquipSkill/PlayerSkill_Explain_MR.msg",
+        logger,
     )?;
     let player_skill_name_msg_mr = get_msg(
         pak,
         "data/Define/Player/Skill/PlEquipSkill/PlayerSkill_Name_MR.msg",
+        logger,
     )?;
 
     let hyakuryu_skill_name_msg = get_msg(
         pak,
         "data/Define/Player/Skill/PlHyakuryuSkill/HyakuryuSkill_Name.msg",
+        logger,
     )?;
     let hyakuryu_skill_explain_msg = get_msg(
         pak,
         "data/Define/Player/Skill/PlHyakuryuSkill/HyakuryuSkill_Explain.msg",
+        logger,
     )?;
 
     let hyakuryu_skill_name_msg_mr = get_msg(
         pak,
         "data/Define/Player/Skill/PlHyakuryuSkill/HyakuryuSkill_Name_MR.msg",
+        logger,
     )?;
     let hyakuryu_skill_explain_msg_mr = get_msg(
         pak,
         "data/Define/Player/Skill/PlHyakuryuSkill/HyakuryuSkill_Explain_MR.msg",
+        logger,
     )?;
 
     let decorations_name_msg = get_msg(
         pak,
         "data/Define/Player/Equip/Decorations/Decorations_Name.msg",
+        logger,
     )?;
     let decorations_name_msg_mr = get_msg(
         pak,
         "data/Define/Player/Equip/Decorations/Decorations_Name_MR.msg",
+        logger,
     )?;
     let hyakuryu_decos_name_msg = get_msg(
         pak,
         "data/Define/Player/Equip/HyakuryuDeco/HyakuryuDeco_Name_MR.msg",
+        logger,
     )?;
 
-    let items_name_msg = get_msg(pak, "data/System/ContentsIdSystem/Item/Normal/ItemName.msg")?;
+    let items_name_msg = get_msg(
+        pak,
+        "data/System/ContentsIdSystem/Item/Normal/ItemName.msg",
+        logger,
+    )?;
     let items_explain_msg = get_msg(
         pak,
         "data/System/ContentsIdSystem/Item/Normal/ItemExplain.msg",
+        logger,
     )?;
     let items_name_msg_mr = get_msg(
         pak,
         "data/System/ContentsIdSystem/Item/Normal/ItemName_MR.msg",
+        logger,
     )?;
     let items_explain_msg_mr = get_msg(
         pak,
         "data/System/ContentsIdSystem/Item/Normal/ItemExplain_MR.msg",
+        logger,
     )?;
     let material_category_msg = get_msg(
         pak,
         "data/System/ContentsIdSystem/Common/ItemCategoryType_Name.msg",
+        logger,
     )?;
     let material_category_msg_mr = get_msg(
         pak,
         "data/System/ContentsIdSystem/Common/ItemCategoryType_Name_MR.msg",
+        logger,
     )?;
 
-    let great_sword = get_weapon_list(pak, "GreatSword", version_hint)?;
-    let short_sword = get_weapon_list(pak, "ShortSword", version_hint)?;
-    let hammer = get_weapon_list(pak, "Hammer", version_hint)?;
-    let lance = get_weapon_list(pak, "Lance", version_hint)?;
-    let long_sword = get_weapon_list(pak, "LongSword", version_hint)?;
-    let slash_axe = get_weapon_list(pak, "SlashAxe", version_hint)?;
-    let gun_lance = get_weapon_list(pak, "GunLance", version_hint)?;
-    let dual_blades = get_weapon_list(pak, "DualBlades", version_hint)?;
-    let horn = get_weapon_list(pak, "Horn", version_hint)?;
-    let insect_glaive = get_weapon_list(pak, "InsectGlaive", version_hint)?;
-    let charge_axe = get_weapon_list(pak, "ChargeAxe", version_hint)?;
-    let light_bowgun = get_weapon_list(pak, "LightBowgun", version_hint)?;
-    let heavy_bowgun = get_weapon_list(pak, "HeavyBowgun", version_hint)?;
-    let bow = get_weapon_list(pak, "Bow", version_hint)?;
-
-    let horn_melody = get_msg(pak, "data/Define/Player/Weapon/Horn/Horn_UniqueParam.msg")?;
+    let great_sword = get_weapon_list(pak, "GreatSword", version_hint, logger)?;
+    let short_sword = get_weapon_list(pak, "ShortSword", version_hint, logger)?;
+    let hammer = get_weapon_list(pak, "Hammer", version_hint, logger)?;
+    let lance = get_weapon_list(pak, "Lance", version_hint, logger)?;
+    let long_sword = get_weapon_list(pak, "LongSword", version_hint, logger)?;
+    let slash_axe = get_weapon_list(pak, "SlashAxe", version_hint, logger)?;
+    let gun_lance = get_weapon_list(pak, "GunLance", version_hint, logger)?;
+    let dual_blades = get_weapon_list(pak, "DualBlades", version_hint, logger)?;
+    let horn = get_weapon_list(pak, "Horn", version_hint, logger)?;
+    let insect_glaive = get_weapon_list(pak, "InsectGlaive", version_hint, logger)?;
+    let charge_axe = get_weapon_list(pak, "ChargeAxe", version_hint, logger)?;
+    let light_bowgun = get_weapon_list(pak, "LightBowgun", version_hint, logger)?;
+    let heavy_bowgun = get_weapon_list(pak, "HeavyBowgun", version_hint, logger)?;
+    let bow = get_weapon_list(pak, "Bow", version_hint, logger)?;
+
+    let horn_melody = get_msg(
+        pak,
+        "data/Define/Player/Weapon/Horn/Horn_UniqueParam.msg",
+        logger,
+    )?;
     let horn_melody_mr = get_msg(
         pak,
         "data/Define/Player/Weapon/Horn/Horn_UniqueParam_MR.msg",
+        logger,
     )?;
     let weapon_series = get_msg(
         pak,
         "data/Define/Player/Weapon/WeaponSeries_Hunter_Name.msg",
+        logger,
     )?;
     let weapon_series_mr = get_msg(
         pak,
         "data/Define/Player/Weapon/WeaponSeries_Hunter_Name_MR.msg",
+        logger,
     )?;
 
     let maps = prepare_maps(pak)?;
-    let map_name = get_msg(pak, "Message/Common_Msg/Stage_Name.msg")?;
-    let map_name_mr = get_msg(pak, "Message/Common_Msg_MR/Stage_Name_MR.msg")?;
+    let map_name = get_msg(pak, "Message/Common_Msg/Stage_Name.msg", logger)?;
+    let map_name_mr = get_msg(pak, "Message/Common_Msg_MR/Stage_Name_MR.msg", logger)?;
 
     let airou_armor_head_name = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Head_Name.msg",
+        logger,
     )?;
     let airou_armor_head_explain = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Head_Explain.msg",
+        logger,
     )?;
     let airou_armor_chest_name = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Chest_Name.msg",
+        logger,
     )?;
     let airou_armor_chest_explain = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Chest_Explain.msg",
+        logger,
     )?;
     let dog_armor_head_name = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Head_Name.msg",
+        logger,
     )?;
     let dog_armor_head_explain = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Head_Explain.msg",
+        logger,
     )?;
     let dog_armor_chest_name = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Chest_Name.msg",
+        logger,
     )?;
     let dog_armor_chest_explain = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Chest_Explain.msg",
+        logger,
+    )?;
+    let airou_weapon_name = get_msg(
+        pak,
+        "data/Define/Otomo/Equip/Weapon/OtAirouWeapon_Name.msg",
+        logger,
     )?;
-    let airou_weapon_name = get_msg(pak, "data/Define/Otomo/Equip/Weapon/OtAirouWeapon_Name.msg")?;
     let airou_weapon_explain = get_msg(
         pak,
         "data/Define/Otomo/Equip/Weapon/OtAirouWeapon_Explain.msg",
+        logger,
+    )?;
+    let dog_weapon_name = get_msg(
+        pak,
+        "data/Define/Otomo/Equip/Weapon/OtDogWeapon_Name.msg",
+        logger,
     )?;
-    let dog_weapon_name = get_msg(pak, "data/Define/Otomo/Equip/Weapon/OtDogWeapon_Name.msg")?;
     let dog_weapon_explain = get_msg(
         pak,
         "data/Define/Otomo/Equip/Weapon/OtDogWeapon_Explain.msg",
+        logger,
     )?;
     let airou_series_name = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/ArmorSeries_OtAirou_Name.msg",
+        logger,
     )?;
     let dog_series_name = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/ArmorSeries_OtDog_Name.msg",
+        logger,
     )?;
 
     let airou_armor_head_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Head_Name_MR.msg",
+        logger,
     )?;
     let airou_armor_head_explain_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Head_Explain_MR.msg",
+        logger,
     )?;
     let airou_armor_chest_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Chest_Name_MR.msg",
+        logger,
     )?;
     let airou_armor_chest_explain_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtAirouArmor_Chest_Explain_MR.msg",
+        logger,
     )?;
     let dog_armor_head_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Head_Name_MR.msg",
+        logger,
     )?;
     let dog_armor_head_explain_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Head_Explain_MR.msg",
+        logger,
     )?;
     let dog_armor_chest_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Chest_Name_MR.msg",
+        logger,
     )?;
     let dog_armor_chest_explain_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/OtDogArmor_Chest_Explain_MR.msg",
+        logger,
     )?;
     let airou_weapon_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Weapon/OtAirouWeapon_Name_MR.msg",
+        logger,
     )?;
     let airou_weapon_explain_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Weapon/OtAirouWeapon_Explain_MR.msg",
+        logger,
     )?;
     let dog_weapon_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Weapon/OtDogWeapon_Name_MR.msg",
+        logger,
     )?;
     let dog_weapon_explain_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Weapon/OtDogWeapon_Explain_MR.msg",
+        logger,
     )?;
     let airou_series_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/ArmorSeries_OtAirou_Name_MR.msg",
+        logger,
     )?;
     let dog_series_name_mr = get_msg(
         pak,
         "data/Define/Otomo/Equip/Armor/ArmorSeries_OtDog_Name_MR.msg",
+        logger,
     )?;
 
-    let servant_profile = get_msg(pak, "Message/Servant/ServantProfile_MR.msg")?;
+    let servant_profile = get_msg(pak, "Message/Servant/ServantProfile_MR.msg", logger)?;
 
     let mut random_mystery_difficulty: Option<RandomMysteryDifficultyRateListData> =
         get_singleton_opt(pak, version_hint)?;
@@ -966,57 +1128,84 @@ pub fn gen_pedia(
     let switch_action_name = get_msg(
         pak,
         "data/Define/Player/Skill/PlSwitchAction/PlayerSwitchAction_Name.msg",
+        logger,
     )?;
 
     let switch_action_name_mr = get_msg(
         pak,
         "data/Define/Player/Skill/PlSwitchAction/PlayerSwitchAction_Name_MR.msg",
+        logger,
     )?;
 
-    let weapon_control = get_msg(pak, "Message/HunterNote/HN_WeaponControlsMsg.msg")?;
+    let weapon_control = get_msg(pak, "Message/HunterNote/HN_WeaponControlsMsg.msg", logger)?;
 
-    let weapon_control_mr = get_msg(pak, "Message/HunterNote_MR/HN_WeaponControlsMsg_MR.msg")?;
+    let weapon_control_mr = get_msg(
+        pak,
+        "Message/HunterNote_MR/HN_WeaponControlsMsg_MR.msg",
+        logger,
+    )?;
 
     let buff_cage_name = get_msg(
         pak,
         "data/System/ContentsIdSystem/LvBuffCage/Normal/LvBuffCage_Name.msg",
+        logger,
     )?;
 
     let buff_cage_explain = get_msg(
         pak,
         "data/System/ContentsIdSystem/LvBuffCage/Normal/LvBuffCage_Explain.msg",
+        logger,
     )?;
 
-    let dlc_name = get_msg(pak, "Message/DLC/DLC_Name.msg")?;
-    let dlc_name_mr = get_msg(pak, "Message/DLC/DLC_Name_MR.msg")?;
-    let dlc_explain = get_msg(pak, "Message/DLC/DLC_Explain.msg")?;
-    let dlc_explain_mr = get_msg(pak, "Message/DLC/DLC_Explain_MR.msg")?;
+    let dlc_name = get_msg(pak, "Message/DLC/DLC_Name.msg", logger)?;
+    let dlc_name_mr = get_msg(pak, "Message/DLC/DLC_Name_MR.msg", logger)?;
+    let dlc_explain = get_msg(pak, "Message/DLC/DLC_Explain.msg", logger)?;
+    let dlc_explain_mr = get_msg(pak, "Message/DLC/DLC_Explain_MR.msg", logger)?;
 
-    let award_name = get_msg(pak, "Message/GuildCard/GC_Award_Name.msg")?;
-    let award_name_mr = get_msg(pak, "Message/GuildCard/GC_Award_Name_MR.msg")?;
-    let award_explain = get_msg(pak, "Message/GuildCard/GC_Award_Explain.msg")?;
-    let award_explain_mr = get_msg(pak, "Message/GuildCard/GC_Award_Explain_MR.msg")?;
+    let award_name = get_msg(pak, "Message/GuildCard/GC_Award_Name.msg", logger)?;
+    let award_name_mr = get_msg(pak, "Message/GuildCard/GC_Award_Name_MR.msg", logger)?;
+    let award_explain = get_msg(pak, "Message/GuildCard/GC_Award_Explain.msg", logger)?;
+    let award_explain_mr = get_msg(pak, "Message/GuildCard/GC_Award_Explain_MR.msg", logger)?;
 
-    let achievement_name = get_msg(pak, "Message/GuildCard/GC_Achievement_Name.msg")?;
-    let achievement_name_mr = get_msg(pak, "Message/GuildCard/GC_Achievement_Name_MR.msg")?;
-    let achievement_explain = get_msg(pak, "Message/GuildCard/GC_Achievement_Explain.msg")?;
-    let achievement_explain_mr = get_msg(pak, "Message/GuildCard/GC_Achievement_Explain_MR.msg")?;
+    let achievement_name = get_msg(pak, "Message/GuildCard/GC_Achievement_Name.msg", logger)?;
+    let achievement_name_mr = get_msg(pak, "Message/GuildCard/GC_Achievement_Name_MR.msg", logger)?;
+    let achievement_explain = get_msg(pak, "Message/GuildCard/GC_Achievement_Explain.msg", logger)?;
+    let achievement_explain_mr = get_msg(
+        pak,
+        "Message/GuildCard/GC_Achievement_Explain_MR.msg",
+        logger,
+    )?;
 
-    let ec_name = get_msg(pak, "Message/HunterNote/EnvironmentCreature_Name.msg")?;
-    let ec_name_mr = get_msg(pak, "Message/HunterNote_MR/EnvironmentCreature_Name_MR.msg")?;
+    let ec_name = get_msg(
+        pak,
+        "Message/HunterNote/EnvironmentCreature_Name.msg",
+        logger,
+    )?;
+    let ec_name_mr = get_msg(
+        pak,
+        "Message/HunterNote_MR/EnvironmentCreature_Name_MR.msg",
+        logger,
+    )?;
 
     let insect_skill = get_msg(
         pak,
         "data/Define/Player/Weapon/Insect/IG_InsectSkill_Name.msg",
+        logger,
     )?;
     let insect_skill_mr = get_msg(
         pak,
         "data/Define/Player/Weapon/Insect/IG_InsectSkill_Name_MR.msg",
+        logger,
+    )?;
+    let insect_name = get_msg(
+        pak,
+        "data/Define/Player/Weapon/Insect/IG_Insect_Name.msg",
+        logger,
     )?;
-    let insect_name = get_msg(pak, "data/Define/Player/Weapon/Insect/IG_Insect_Name.msg")?;
     let insect_name_mr = get_msg(
         pak,
         "data/Define/Player/Weapon/Insect/IG_Insect_Name_MR.msg",
+        logger,
     )?;
 
     Ok(Pedia {
@@ -1285,7 +1474,7 @@ pub fn gen_pedia(
 }
 
 fn gen_monster_hitzones(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     output: &impl Sink,
     collider_path_gen: fn(u32, u32) -> String,
     mesh_path_gen: fn(u32, u32) -> String,
@@ -1407,7 +1596,7 @@ fn save_spriter(
 }
 
 pub fn gen_resources(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     output: &impl Sink,
     logger: &mut Logger,
 ) -> Result<()> {
@@ -1439,6 +1628,7 @@ pub fn gen_resources(
         logger,
     )?;
 
+    let mut icons = vec![];
     for index in 0..1000 {
         for sub_id in 0..10 {
             let icon_path = if let Some(name) = EM_ICON_MAP.get(&(index, sub_id)) {
@@ -1446,37 +1636,29 @@ pub fn gen_resources(
             } else {
                 format!("gui/80_Texture/boss_icon/em{index:03}_{sub_id:02}_IAM.tex")
             };
-            let icon = if let Ok(icon) = pak.find_file(&icon_path) {
-                icon
-            } else {
-                continue;
-            };
-            let icon = Tex::new(Cursor::new(pak.read_file(icon)?))?;
-            icon.save_png(
-                0,
-                0,
-                output.create(&format!("em{index:03}_{sub_id:02}_icon.png"))?,
-            )?;
+            if let Ok(icon) = pak.find_file(&icon_path) {
+                icons.push((icon, format!("em{index:03}_{sub_id:02}_icon.png")));
+            }
         }
     }
 
     for index in 0..1000 {
         for sub_id in 0..10 {
             let icon_path = format!("gui/80_Texture/boss_icon/ems{index:03}_{sub_id:02}_IAM.tex");
-            let icon = if let Ok(icon) = pak.find_file(&icon_path) {
-                icon
-            } else {
-                continue;
-            };
-            let icon = Tex::new(Cursor::new(pak.read_file(icon)?))?;
-            icon.save_png(
-                0,
-                0,
-                output.create(&format!("ems{index:03}_{sub_id:02}_icon.png"))?,
-            )?;
+            if let Ok(icon) = pak.find_file(&icon_path) {
+                icons.push((icon, format!("ems{index:03}_{sub_id:02}_icon.png")));
+            }
         }
     }
 
+    let shared_pak = &*pak;
+    icons
+        .into_par_iter()
+        .try_for_each(|(icon, file_name)| -> Result<()> {
+            let icon = Tex::new(Cursor::new(shared_pak.read_file(icon)?))?;
+            icon.save_png(0, 0, output.create(&file_name)?)
+        })?;
+
     let icon_uvs = pak.find_file("gui/70_UVSequence/boss_icon_mini.uvs")?;
     let icon_uvs = Uvs::new(Cursor::new(pak.read_file(icon_uvs)?))?;
     if icon_uvs.textures.is_empty() || icon_uvs.spriter_groups.is_empty() {
@@ -1765,7 +1947,7 @@ pub fn gen_resources(
 }
 
 fn gen_gui_colors(
-    pak: &mut PakReader<impl Read + Seek>,
+    pak: &PakReader<impl PakSource>,
     mut file: impl Write,
     gui: &str,
     control_name: &str,
@@ -1822,7 +2004,7 @@ fn gen_gui_colors(
     Ok(())
 }
 
-fn gen_item_colors(pak: &mut PakReader<impl Read + Seek>, mut output: impl Write) -> Result<()> {
+fn gen_item_colors(pak: &PakReader<impl PakSource>, mut output: impl Write) -> Result<()> {
     gen_gui_colors(
         pak,
         &mut output,
@@ -1838,7 +2020,7 @@ fn gen_item_colors(pak: &mut PakReader<impl Read + Seek>, mut output: impl Write
     Ok(())
 }
 
-fn gen_rarity_colors(pak: &mut PakReader<impl Read + Seek>, output: impl Write) -> Result<()> {
+fn gen_rarity_colors(pak: &PakReader<impl PakSource>, output: impl Write) -> Result<()> {
     gen_gui_colors(
         pak,
         output,
@@ -2935,6 +3117,7 @@ fn prepare_items<'a>(pedia: &'a Pedia, logger: &mut Logger) -> Result<BTreeMap<I
 static AMMOR_SPHERE_CATEGORY_MSG: Lazy<MsgEntry> = Lazy::new(|| MsgEntry {
     name: "".to_string(),
     guid: Guid { bytes: [0; 16] },
+    index: 0,
     hash: 0,
     attributes: vec![],
     content: vec!["Armor sphere".to_string(); 32],
@@ -3299,6 +3482,77 @@ fn prepare_item_pop(
     Ok(res)
 }
 
+fn prepare_gather_points<'a>(
+    pedia: &'a Pedia,
+    item_pop: &HashMap<(i32, i32), &'a ItemPopLotTableUserDataParam>,
+) -> Vec<GatherPoint<'a>> {
+    let mut result = vec![];
+    for (&map_id, map) in &pedia.maps {
+        for (pop_index, pop) in map.pops.iter().enumerate() {
+            let MapPopKind::Item { behavior, .. } = &pop.kind else {
+                continue;
+            };
+            // Tables with field type -1 are shared by all maps
+            let Some(&lot) = item_pop
+                .get(&(behavior.pop_id, map_id))
+                .or_else(|| item_pop.get(&(behavior.pop_id, -1)))
+            else {
+                continue;
+            };
+            result.push(GatherPoint {
+                map: map_id,
+                pop_index,
+                behavior,
+                lot,
+            });
+        }
+    }
+    result
+}
+
+fn prepare_ec_names(pedia: &Pedia) -> HashMap<i32, &MsgEntry> {
+    let names = pedia.ec_name.get_name_map();
+    let names_mr = pedia.ec_name_mr.get_name_map();
+    (0..)
+        .map_while(|ec_type| Some((ec_type, get_ec_id(ec_type)?)))
+        .filter_map(|(ec_type, id)| {
+            let name = names
+                .get(&format!("EC_NAME_{id}"))
+                .or_else(|| names_mr.get(&format!("EC_NAME_{id}_MR")))?;
+            Some((ec_type, *name))
+        })
+        .collect()
+}
+
+fn prepare_endemic_life<'a>(
+    pedia: &'a Pedia,
+    ec_names: &HashMap<i32, &'a MsgEntry>,
+) -> BTreeMap<i32, EndemicLife<'a>> {
+    let mut result: BTreeMap<i32, EndemicLife> = BTreeMap::new();
+    for (&map, game_map) in &pedia.maps {
+        for (pop_index, pop) in game_map.pops.iter().enumerate() {
+            let MapPopKind::Ec { behavior, effects } = &pop.kind else {
+                continue;
+            };
+            let type_ = behavior.base.type_;
+            result
+                .entry(type_)
+                .or_insert_with(|| EndemicLife {
+                    name: ec_names.get(&type_).copied(),
+                    pops: vec![],
+                })
+                .pops
+                .push(EndemicLifePop {
+                    map,
+                    pop_index,
+                    behavior,
+                    effects,
+                });
+        }
+    }
+    result
+}
+
 fn prepeare_ot_equip<'a>(
     pedia: &'a Pedia,
     logger: &mut Logger,
@@ -3948,7 +4202,22 @@ fn prepare_monsters<'a>(
     Ok(result)
 }
 
-pub fn prepare_servant(pedia: &Pedia) -> Result<HashMap<i32, Servant<'_>>> {
+/// The NpcID of a follower is not in the data, so it is found from villager requests
+/// whose requester has the same English name as the follower.
+pub fn prepare_servant<'a>(
+    pedia: &'a Pedia,
+    npc_missions: &BTreeMap<i32, NpcMission>,
+    logger: &mut Logger,
+) -> Result<HashMap<i32, Servant<'a>>> {
+    lscope!(logger, "servant");
+    let mut requesters: HashMap<&str, BTreeSet<i32>> = HashMap::new();
+    for mission in npc_missions.values() {
+        requesters
+            .entry(mission.requester.content[1].trim())
+            .or_default()
+            .insert(mission.param.client_npc);
+    }
+
     let mut result = HashMap::new();
     for entry in &pedia.servant_profile.entries {
         if let Some(id) = entry.name.strip_prefix("Name_ServantId") {
@@ -3956,10 +4225,26 @@ pub fn prepare_servant(pedia: &Pedia) -> Result<HashMap<i32, Servant<'_>>> {
                 .strip_suffix("_MR")
                 .and_then(|id| id.parse().ok())
                 .with_context(|| format!("Unexpected servant name tag {}", entry.name))?;
-            let servant = Servant { name: entry };
+            let npc_ids = requesters.get(entry.content[1].trim());
+            let npc_id = match npc_ids.map(|ids| ids.iter().collect::<Vec<_>>()).as_deref() {
+                Some(&[&npc_id]) => Some(npc_id),
+                Some(ids) => {
+                    writeln!(logger, "Follower {id} matches multiple NPCs {ids:?}")?;
+                    None
+                }
+                None => {
+                    writeln!(logger, "Follower {id} matches no requester NPC by name")?;
+                    None
+                }
+            };
+            let servant = Servant {
+                name: entry,
+                npc_id,
+            };
             result.insert(id, servant);
         }
     }
+
     Ok(result)
 }
 
@@ -4480,6 +4765,115 @@ fn prepare_insect<'a>(
     Ok(result)
 }
 
+fn prepare_monster_routes<'a>(
+    pedia: &'a Pedia,
+    logger: &'_ mut Logger,
+) -> Result<BTreeMap<i32, Vec<MonsterRoute<'a>>>> {
+    lscope!(logger, "monster_route");
+    let mut result = BTreeMap::new();
+    for (&map_id, map) in &pedia.maps {
+        // Spawn points come from EnemyBossInitSetData, and rest points from EnemyBlockMoveData.
+        // Both refer to the pops in the enemy prefab of the map by unique ID.
+        let mut init_set_pos = HashMap::new();
+        let mut inside_move_pos = HashMap::new();
+        for pop in &map.pops {
+            match &pop.kind {
+                MapPopKind::InitSet { pos } => {
+                    init_set_pos
+                        .insert(pos.base.unique_id, map.map_position(map_id, &pop.position));
+                }
+                MapPopKind::InsideMove { pos } => {
+                    inside_move_pos
+                        .insert(pos.base.unique_id, map.map_position(map_id, &pop.position));
+                }
+                _ => (),
+            }
+        }
+
+        let mut missing = 0;
+        let mut point = |positions: &HashMap<u32, (f32, f32)>, unique_id: u32, zone: i32| {
+            let map_position = positions.get(&unique_id).copied();
+            if map_position.is_none() {
+                missing += 1;
+            }
+            RoutePoint {
+                unique_id,
+                zone,
+                map_position,
+            }
+        };
+
+        let mut routes = vec![];
+        for monster in &pedia.monsters {
+            let init_set = monster
+                .boss_init_set_data
+                .iter()
+                .flat_map(|d| &d.stage_info_list)
+                .find(|s| s.map_type == map_id);
+            let block_move = monster
+                .block_move
+                .iter()
+                .flat_map(|b| &b.stage_info_list)
+                .find(|s| s.map_type == map_id);
+            if init_set.is_none() && block_move.is_none() {
+                continue;
+            }
+
+            let spawn_sets = init_set
+                .into_iter()
+                .flat_map(|s| &s.set_info_list)
+                .map(|set| SpawnSet {
+                    set_name: &set.set_name,
+                    choices: set
+                        .info
+                        .iter()
+                        .filter(|info| info.lot != 0)
+                        .map(|info| SpawnChoice {
+                            point: point(&init_set_pos, info.id as u32, info.block),
+                            lot: info.lot,
+                        })
+                        .collect(),
+                })
+                .collect();
+
+            let mut inside_move_points = |list: &[EnemyBlockMoveDataInsideMoveInfo]| {
+                list.iter()
+                    .map(|p| point(&inside_move_pos, p.unique_id, p.block_no))
+                    .collect::<Vec<_>>()
+            };
+            let (sleep_points, eat_points, escape_points, zones, routes_info) = match block_move {
+                Some(b) => (
+                    inside_move_points(&b.sleep_point_list),
+                    inside_move_points(&b.map_meet_eat_point_list),
+                    inside_move_points(&b.map_escape_point_list),
+                    &b.block_basic_info_list[..],
+                    &b.route_info_list[..],
+                ),
+                None => (vec![], vec![], vec![], &[][..], &[][..]),
+            };
+
+            routes.push(MonsterRoute {
+                em_type: monster.em_type,
+                spawn_sets,
+                sleep_points,
+                eat_points,
+                escape_points,
+                zones,
+                routes: routes_info,
+            });
+        }
+
+        if missing != 0 {
+            writeln!(
+                logger,
+                "{missing} monster route points not found in map {map_id}"
+            )?;
+        }
+        result.insert(map_id, routes);
+    }
+    Ok(result)
+}
+
 pub fn gen_pedia_ex<'a>(pedia: &'a Pedia, logger: &mut Logger) -> Result<PediaEx<'a>> {
     lscope!(logger, "ex");
     let monster_order = pedia
@@ -4516,6 +4910,8 @@ pub fn gen_pedia_ex<'a>(pedia: &'a Pedia, logger: &mut Logger) -> Result<PediaEx
         logger,
     )?;
 
+    let item_pop = prepare_item_pop(pedia)?;
+
     let mut custom_buildup_pieces = hash_map_unique(
         pedia
             .custom_buildup_base
@@ -4541,12 +4937,16 @@ pub fn gen_pedia_ex<'a>(pedia: &'a Pedia, logger: &mut Logger) -> Result<PediaEx
         HashMap::new()
     };
 
+    let npc_missions = prepare_npc_mission(pedia, logger)?;
+    let ec_names = prepare_ec_names(pedia);
+
     Ok(PediaEx {
         monsters: prepare_monsters(pedia, &reward_lot, logger)?,
         sizes: prepare_size_map(&pedia.size_list, logger)?,
         size_dists: prepare_size_dist_map(&pedia.random_scale, logger)?,
         quests: prepare_quests(pedia, &reward_lot, logger)?,
-        npc_missions: prepare_npc_mission(pedia, logger)?,
+        servant: prepare_servant(pedia, &npc_missions, logger)?,
+        npc_missions,
         skills: prepare_skills(pedia, logger)?,
         hyakuryu_skills: prepare_hyakuryu_skills(pedia, logger)?,
         armors: prepare_armors(pedia, logger)?,
@@ -4623,9 +5023,9 @@ pub fn gen_pedia_ex<'a>(pedia: &'a Pedia, logger: &mut Logger) -> Result<PediaEx
         bow: prepare_weapon(&pedia.bow, &mut hyakuryu_weapon_map, &mut chaos, logger)?,
         horn_melody: prepare_horn_melody(pedia),
         monster_order,
-        item_pop: prepare_item_pop(pedia)?,
+        gather_points: prepare_gather_points(pedia, &item_pop),
+        item_pop,
         ot_equip: prepeare_ot_equip(pedia, logger)?,
-        servant: prepare_servant(pedia)?,
 
         armor_custom_buildup,
         weapon_custom_buildup,
@@ -4642,5 +5042,9 @@ pub fn gen_pedia_ex<'a>(pedia: &'a Pedia, logger: &mut Logger) -> Result<PediaEx
         slc: prepare_slc(pedia)?,
         // map_icon_list: prepare_map_icon_list(pedia)?,
         insect: prepare_insect(pedia, logger)?,
+        monster_routes: prepare_monster_routes(pedia, logger)?,
+        fishing_spots: prepare_fishing_spots(pedia),
+        endemic_life: prepare_endemic_life(pedia, &ec_names),
+        ec_names,
     })
 }
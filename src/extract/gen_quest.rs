@@ -1,3 +1,4 @@
+use super::arena::*;
 use super::gen_armor::*;
 use super::gen_common::*;
 use super::gen_hyakuryu_skill::*;
@@ -6,11 +7,13 @@ use super::gen_map::*;
 use super::gen_misc::*;
 use super::gen_monster::*;
 use super::gen_otomo::*;
+use super::gen_servant::*;
 use super::gen_skill::*;
 use super::gen_weapon::*;
 use super::gen_website::*;
 use super::hash_store::*;
 use super::pedia::*;
+use super::rampage::*;
 use super::sink::*;
 use crate::rsz::*;
 use anyhow::Result;
@@ -197,6 +200,14 @@ pub fn gen_quest_list(
                     <span>"go to villager requests"</span>
                     </span></a>
                 </div>
+                <div>
+                    <a href="crown_hunting.html"><span class="icon-text">
+                    <span class="icon">
+                    <i class="fas fa-arrow-right"></i>
+                    </span>
+                    <span>"go to crown hunting"</span>
+                    </span></a>
+                </div>
                 { sections.into_iter().map(|s|s.content) }
                 </main>
                 { right_aside() }
@@ -211,6 +222,160 @@ pub fn gen_quest_list(
     Ok(())
 }
 
+pub fn gen_crown_hunting(
+    hash_store: &HashStore,
+    pedia: &Pedia,
+    pedia_ex: &PediaEx,
+    output: &impl Sink,
+) -> Result<()> {
+    const RANK_LEN: usize = 5;
+
+    let mut sections = vec![];
+
+    for monster in &pedia.monsters {
+        let em_type = monster.em_type;
+        match pedia_ex.sizes.get(&em_type) {
+            Some(size) if !size.no_size_scale => (),
+            _ => continue,
+        }
+
+        let mut entries: Vec<(&Quest, usize, CrownChance)> = vec![];
+        for quest in pedia_ex.quests.values() {
+            let Some(enemy_param) = quest.enemy_param else {
+                continue;
+            };
+            for (i, &boss) in quest.param.boss_em_type.iter().enumerate() {
+                if boss != em_type {
+                    continue;
+                }
+                let (Some(scale_tbl), Some(base_scale)) =
+                    (enemy_param.scale_tbl(i), enemy_param.scale(i))
+                else {
+                    continue;
+                };
+                if let Some(chance) = pedia_ex.crown_chance(em_type, scale_tbl, base_scale) {
+                    entries.push((quest, i, chance))
+                }
+            }
+        }
+
+        let mut king_rank: Vec<_> = entries.iter().filter(|(_, _, c)| c.king != 0.0).collect();
+        king_rank.sort_by(|(_, _, a), (_, _, b)| b.king.total_cmp(&a.king));
+        king_rank.truncate(RANK_LEN);
+
+        let mut big_rank: Vec<_> = entries.iter().filter(|(_, _, c)| c.big != 0.0).collect();
+        big_rank.sort_by(|(_, _, a), (_, _, b)| b.big.total_cmp(&a.big));
+        big_rank.truncate(RANK_LEN);
+
+        let mut small_rank: Vec<_> = entries.iter().filter(|(_, _, c)| c.small != 0.0).collect();
+        small_rank.sort_by(|(_, _, a), (_, _, b)| b.small.total_cmp(&a.small));
+        small_rank.truncate(RANK_LEN);
+
+        if king_rank.is_empty() && big_rank.is_empty() && small_rank.is_empty() {
+            continue;
+        }
+
+        let gen_rank = |rank: Vec<&(&Quest, usize, CrownChance)>| {
+            if rank.is_empty() {
+                return html!(<td>"-"</td>);
+            }
+            html!(<td><ul class="mh-quest-list">{
+                rank.into_iter().map(|&(quest, i, chance)| {
+                    let is_target = quest.param.has_target(em_type);
+                    let mystery = quest.enemy_param.and_then(|p|p.individual_type.get(i).cloned());
+                    html!(<li class="mh-quest-inline">
+                        {gen_crown_chance(&chance)}
+                        {gen_quest_tag(quest, true, is_target, mystery, None)}
+                    </li>)
+                })
+            }</ul></td>)
+        };
+
+        let title = pedia_ex.monsters[&em_type].name.map_or_else(
+            || format!("Monster {em_type:?}"),
+            |name| translate_msg_plain(&name.content[1]),
+        );
+        let id = format!("s-{:03}_{:02}", monster.id, monster.sub_id);
+        sections.push(Section {
+            title,
+            content: html!(
+                <section id={id.as_str()}>
+                <div class="mh-table"><table>
+                    <thead><tr>
+                        <th>"Monster"</th>
+                        <th>"Best for large crown"</th>
+                        <th>"Best for silver large crown"</th>
+                        <th>"Best for small crown"</th>
+                    </tr></thead>
+                    <tbody><tr>
+                        <td>{gen_monster_tag(pedia_ex, em_type, false, false, None, None)}</td>
+                        {gen_rank(king_rank)}
+                        {gen_rank(big_rank)}
+                        {gen_rank(small_rank)}
+                    </tr></tbody>
+                </table></div>
+                </section>
+            ),
+        });
+    }
+
+    let file_name = "crown_hunting.html";
+
+    let doc: DOMTree<String> = html!(
+        <html lang="en">
+            <head itemscope=true>
+                <title>{text!("Crown hunting - MHRice")}</title>
+                { head_common(hash_store, output) }
+            </head>
+            <body>
+                { navbar() }
+                { gen_menu(&sections, &(output.toc_path() + file_name)) }
+                <main>
+                <header><h1>"Crown hunting"</h1></header>
+                <p>"Quests with the highest chance of each crown size, per monster."</p>
+                { sections.into_iter().map(|s|s.content) }
+                </main>
+                { right_aside() }
+            </body>
+        </html>
+    );
+
+    output
+        .create_html(file_name)?
+        .write_all(doc.to_string().as_bytes())?;
+
+    Ok(())
+}
+
+fn format_percentage(p: f64) -> String {
+    format!("{}%", (p * 100.0).round() / 100.0)
+}
+
+pub fn gen_crown_chance(chance: &CrownChance) -> Box<span<String>> {
+    let small = (chance.small != 0.0).then(|| {
+        html!(<span class="mh-crown">
+            <img class="mh-crown-icon" alt="Small crown" src="resources/small_crown.png" />
+            {text!("{}", format_percentage(chance.small))}
+        </span>)
+    });
+
+    let big = (chance.big != 0.0).then(|| {
+        html!(<span class="mh-crown">
+            <img class="mh-crown-icon" alt="Silver large crown" src="resources/large_crown.png" />
+            {text!("{}", format_percentage(chance.big))}
+        </span>)
+    });
+
+    let king = (chance.king != 0.0).then(|| {
+        html!(<span class="mh-crown">
+            <img class="mh-crown-icon" alt="Large crown" src="resources/king_crown.png" />
+            {text!("{}", format_percentage(chance.king))}
+        </span>)
+    });
+
+    html!(<span>{small}{big}{king}</span>)
+}
+
 pub fn gen_quest_monster_data(
     enemy_param: Option<&impl EnemyParam>,
     em_type_for_scale: Option<EmTypes>,
@@ -228,37 +393,8 @@ pub fn gen_quest_monster_data(
         if let (Some(scale_tbl_i), Some(base_scale)) =
             (enemy_param.scale_tbl(index), enemy_param.scale(index))
         {
-            if let (Some(size), Some(size_dist)) = (
-                pedia_ex.sizes.get(&em_type),
-                pedia_ex.size_dists.get(&scale_tbl_i),
-            ) {
-                let mut small_chance = 0;
-                let mut large_chance = 0;
-                for sample in *size_dist {
-                    let scale = sample.scale * (base_scale as f32) / 100.0;
-                    if scale <= size.small_boarder {
-                        small_chance += sample.rate;
-                    }
-                    if scale >= size.king_boarder {
-                        large_chance += sample.rate;
-                    }
-                }
-
-                let small = (small_chance != 0).then(|| {
-                    html!(<span class="mh-crown">
-                    <img class="mh-crown-icon" alt="Small crown" src="resources/small_crown.png" />
-                    {text!("{}%", small_chance)}
-                </span>)
-                });
-
-                let large = (large_chance != 0).then(|| {
-                    html!(<span class="mh-crown">
-                    <img class="mh-crown-icon" alt="Large crown" src="resources/king_crown.png" />
-                    {text!("{}%", large_chance)}
-                </span>)
-                });
-
-                html!(<span>{small}{large}</span>)
+            if let Some(chance) = pedia_ex.crown_chance(em_type, scale_tbl_i, base_scale) {
+                gen_crown_chance(&chance)
             } else {
                 html!(<span>"-"</span>)
             }
@@ -456,6 +592,76 @@ impl HyakuryuQuestData {
     }
 }
 
+fn rampage_task_name(target: QuestTargetType) -> String {
+    let s = match target {
+        QuestTargetType::HuntingMachine => "Install siege weapons",
+        QuestTargetType::DropItem => "Collect drops",
+        QuestTargetType::EmStun => "Stun monsters",
+        QuestTargetType::EmElement => "Apply element blight",
+        QuestTargetType::EmCondition => "Apply status",
+        QuestTargetType::EmCntWeapon => "Repel using weapon",
+        QuestTargetType::EmCntHmBallista => "Repel using ballista",
+        QuestTargetType::EmCntHmCannon => "Repel using cannon",
+        QuestTargetType::EmCntHmGatling => "Repel using gatling",
+        QuestTargetType::EmCntHmTrap => "Repel using bomb trap",
+        QuestTargetType::EmCntHmFlameThrower => "Repel using flamethrower",
+        QuestTargetType::EmCntHmNpc => "Repel by NPC",
+        QuestTargetType::EmCntHmDragnator => "Repel using dragonator",
+        QuestTargetType::ExtraEmRunaway => "Repel Magnamalo",
+        x => return format!("{}", x as u8),
+    };
+    s.to_owned()
+}
+
+fn rampage_json(quest_no: i32) -> String {
+    format!("{quest_no:06}_rampage.json")
+}
+
+fn gen_rampage_timeline(timeline: &RampageTimeline, pedia_ex: &PediaEx) -> Box<section<String>> {
+    let role_tag = |role: RampageRole| match role {
+        RampageRole::Monster => None,
+        RampageRole::Boss => Some(html!(<span class="tag">"Boss"</span>)),
+        RampageRole::Apex => Some(html!(<span class="tag is-danger">"Apex"</span>)),
+    };
+    html!(<section id="s-rampage-wave">
+        <h2>"Rampage waves"</h2>
+        <div class="mh-kvlist">
+        <p class="mh-kv"><span>"Rampage level"</span>
+            <span>{ text!("{:?}", timeline.quest_lv) }</span></p>
+        <p class="mh-kv"><span>"Wave order"</span>
+            <span>{ text!("{}", if timeline.fixed_wave_order { "Fixed" } else { "Random" }) }</span></p>
+        </div>
+        <ol class="mh-rampage-timeline">{
+            timeline.waves.iter().map(|wave| html!(<li class="mh-rampage-wave">
+                <div class="mh-rampage-wave-title">{text!("Wave {}", wave.wave_no)}</div>
+                <ul class="mh-rampage-wave-em">{
+                    wave.monsters.iter().map(|monster| html!(<li>
+                        { gen_monster_tag(pedia_ex, monster.em_type, false,
+                            monster.role == RampageRole::Monster, None, monster.sub_type) }
+                        { (monster.count > 1).then(|| html!(<span>{text!("x{}", monster.count)}</span>)) }
+                        { role_tag(monster.role) }
+                    </li>))
+                }</ul>
+                { wave.extra_em_nando_tbl_no.map(|nando| html!(<div class="mh-rampage-wave-event">
+                    {text!("Magnamalo appears (scale table {})", nando)}
+                </div>)) }
+                { wave.extra_target.map(|target| html!(<div class="mh-rampage-wave-event">
+                    {text!("Task: {}", rampage_task_name(target))}
+                </div>)) }
+                <div class="mh-rampage-wave-table">
+                    {text!("Boss scale table {} | Other scale table {} | Order table {}",
+                        wave.boss_nando_tbl_no, wave.wave_nando_tbl_no, wave.order_table_no)}
+                </div>
+            </li>))
+        }</ol>
+        <div>"Scale and order tables are shown by number, and rewards by clear rank are not listed, "
+            "as these tables are not extracted."</div>
+        <div>
+            <a href={format!("quest/{}", rampage_json(timeline.quest_no))}>"Download as JSON"</a>
+        </div>
+    </section>)
+}
+
 fn gen_quest(
     hash_store: &HashStore,
     quest: &Quest,
@@ -740,9 +946,7 @@ fn gen_quest(
             content: html!(<section id="s-follower"><h2>"Fixed followers"</h2>
             <ul> {
                 servants.servant_info_list.iter().map(|servant| {
-                    let name = pedia_ex.servant.get(&servant.servant_id)
-                        .map_or_else(||html!(<span>{text!("{}", servant.servant_id)}</span>),
-                        |s|gen_multi_lang(s.name));
+                    let name = gen_servant_label(servant.servant_id, pedia_ex);
                     html!(<li> "NPC: " {name} ", " {
                         text!("Weapon: {}", servant.weapon_type.name())
                     } </li>)
@@ -1005,65 +1209,21 @@ fn gen_quest(
                     ||html!(<span>{
                         text!(" (appears on wave {})", h.sub_target5_wave_no)}
                     </span>));
-                let s = match target {
-                    QuestTargetType::HuntingMachine => "Install siege weapons",
-                    QuestTargetType::DropItem => "Collect drops",
-                    QuestTargetType::EmStun => "Stun monsters",
-                    QuestTargetType::EmElement => "Apply element blight",
-                    QuestTargetType::EmCondition => "Apply status",
-                    QuestTargetType::EmCntWeapon => "Repel using weapon",
-                    QuestTargetType::EmCntHmBallista  => "Repel using ballista",
-                    QuestTargetType::EmCntHmCannon  => "Repel using cannon",
-                    QuestTargetType::EmCntHmGatling => "Repel using gatling",
-                    QuestTargetType::EmCntHmTrap => "Repel using bomb trap",
-                    QuestTargetType::EmCntHmFlameThrower => "Repel using flamethrower",
-                    QuestTargetType::EmCntHmNpc => "Repel by NPC",
-                    QuestTargetType::EmCntHmDragnator => "Repel using dragonator",
-                    QuestTargetType::ExtraEmRunaway => "Repel Magnamalo",
-                    x => return html!(<li>{ text!("{}", *x as u8) }{extra_target}</li>)
-                };
-                html!(<li>{ text!("{}", s) }{extra_target}</li>)
+                html!(<li>{ text!("{}", rampage_task_name(*target)) }{extra_target}</li>)
             })
             }</ul></section>),
         });
 
-        sections.push(Section {
-            title: "Rampage waves".to_owned(),
-            content: html!(
-            <section id="s-rampage-wave">
-            <h2>"Rampage waves"</h2><div class="mh-table"><table>
-            <thead><tr>
-                <th>"Boss monster"</th>
-                <th>"Sub type"</th>
-                <th>"Boss scale table"</th>
-                <th>"Other monsters"</th>
-                <th>"Other scale table"</th>
-                <th>"Order table"</th>
-            </tr></thead>
-            <tbody> {
-                h.wave_data.iter()
-                .filter(|wave|wave.boss_em != EmTypes::Em(0))
-                .map(|wave| {
-                    let sub_type = wave.boss_sub_type as u8;
-                    html!(<tr>
-                        <td>{ gen_monster_tag(pedia_ex, wave.boss_em, false, false, None, Some(sub_type)) }</td>
-                        <td>{text!("{}", wave.boss_sub_type)}</td>
-                        <td>{text!("{}", wave.boss_em_nando_tbl_no)}</td>
-                        <td><ul class="mh-rampage-em-list"> {
-                            wave.em_table.iter().filter(|&&em|em != EmTypes::Em(0))
-                            .map(|&em|html!(<li>
-                                { gen_monster_tag(pedia_ex, em, false, true, None, None) }
-                            </li>))
-                        } </ul></td>
-                        <td>{text!("{}", wave.wave_em_nando_tbl_no)}</td>
-                        <td>{text!("{}", wave.order_table_no)}</td>
-                    </tr>)
-                })
-            } </tbody>
-            </table></div>
-
-            </section>),
-        });
+        if let Some(timeline) = rampage_timeline(quest) {
+            serde_json::to_writer(
+                quest_path.create(&rampage_json(timeline.quest_no))?,
+                &timeline,
+            )?;
+            sections.push(Section {
+                title: "Rampage waves".to_owned(),
+                content: gen_rampage_timeline(&timeline, pedia_ex),
+            });
+        }
     }
 
     if quest.param.supply_tbl != 0 {
@@ -1090,16 +1250,57 @@ fn gen_quest(
     }
 
     if let Some(arena) = quest.arena {
+        let lookup = ArenaLookup::new(pedia_ex);
+        let rank_rows = if quest.time_attack_reward.is_empty() {
+            [
+                ("S", arena.rank_time_s, Some(arena.rank_point_rate_s)),
+                ("A", arena.rank_time_a, Some(arena.rank_point_rate_a)),
+                ("B", arena.rank_time_b, None),
+            ]
+            .into_iter()
+            .map(|(rank, time, rate)| {
+                html!(<tr>
+                    <td>{text!("{}", rank)}</td>
+                    <td>{text!("{}s", time)}</td>
+                    <td>{text!("{}", rate.map_or("-".to_owned(), |rate| format!("x{rate}")))}</td>
+                    <td/>
+                </tr>)
+            })
+            .collect::<Vec<_>>()
+        } else {
+            quest
+                .time_attack_reward
+                .iter()
+                .map(|ta| {
+                    let (rank, rate) = match ta.rank.rank {
+                        RewardRank::RankSS => ("SS", None),
+                        RewardRank::RankS => ("S", Some(arena.rank_point_rate_s)),
+                        RewardRank::RankA => ("A", Some(arena.rank_point_rate_a)),
+                        RewardRank::RankB => ("B", None),
+                    };
+                    html!(<tr>
+                        <td>{text!("{}", rank)}</td>
+                        <td>{text!("{}s", ta.rank.clear_time)}</td>
+                        <td>{text!("{}", rate.map_or("-".to_owned(), |rate| format!("x{rate}")))}</td>
+                        <td><ul class="mh-item-list">{
+                            ta.reward.item_id_list.iter().zip(&ta.reward.num_list)
+                            .filter(|(&item, _)| item != ItemId::Null && item != ItemId::None)
+                            .map(|(&item, num)| html!(<li>
+                                {text!("{}x ", num)}
+                                <div class="il">{gen_item_label_from_id(item, pedia_ex)}</div>
+                            </li>))
+                        }</ul></td>
+                    </tr>)
+                })
+                .collect::<Vec<_>>()
+        };
+
         sections.push(Section {
             title: "Arena".to_owned(),
             content: html!(
                 <section id="s-arena">
                 <h2 >"Arena"</h2>
                 <div class="mh-kvlist">
-                <p class="mh-kv"><span>"Rank time (S/A/B)"</span>
-                    <span>{ text!("{}s / {}s / {}s", arena.rank_time_s, arena.rank_time_a, arena.rank_time_b) }</span></p>
-                <p class="mh-kv"><span>"Point modifier for rank (S/A)"</span>
-                    <span>{ text!("x{} / x{}", arena.rank_point_rate_s, arena.rank_point_rate_a) }</span></p>
                 <p class="mh-kv"><span>"Enemy-to-enemy attack"</span>
                     <span>{ text!("{}", arena.em2em_adjust_data) }</span></p>
                 //<p class="mh-kv"><span>"dodge_blocking_damage_rate (s/m)"</span>
@@ -1115,6 +1316,16 @@ fn gen_quest(
                     <span>{ text!("{}", arena.base_gimmik_damage) }</span></p>
                 </div>
 
+                <div class="mh-table"><table>
+                <thead><tr>
+                    <th>"Rank"</th>
+                    <th>"Clear time"</th>
+                    <th>"Point modifier"</th>
+                    <th>"Reward"</th>
+                </tr></thead>
+                <tbody>{rank_rows}</tbody>
+                </table></div>
+
                 { arena.arena_pl.iter().enumerate().map(|(i, pl)|{
                     let weapon_control_ref = |name: &str| {
                         pedia.weapon_control.get_entry(name).or_else(||pedia.weapon_control_mr.get_entry(name))
@@ -1130,34 +1341,37 @@ fn gen_quest(
                         })}</ul>)
                     };
 
+                    let loadout = resolve_arena_loadout(pl, &lookup, pedia_ex);
+
                     let deco_label_li = |id: DecorationsId| {
-                        // TODO: fast lookup table
-                        for (&skill_id, skill) in &pedia_ex.skills {
-                            for deco in &skill.decos {
-                                if deco.data.id == id {
-                                    return html!(<li>
-                                        <a href={format!("skill/{}", skill_page(skill_id))}>
-                                        { gen_deco_label(deco) }
-                                        </a>
-                                    </li>)
-                                }
-                            }
+                        if let Some((skill_id, deco)) = lookup.deco(id) {
+                            html!(<li>
+                                <a href={format!("skill/{}", skill_page(skill_id))}>
+                                { gen_deco_label(deco) }
+                                </a>
+                            </li>)
+                        } else {
+                            html!(<li>{text!("Unknown deco {:?}", id)}</li>)
                         }
-                        html!(<li>{text!("Unknown deco {:?}", id)}</li>)
                     };
 
-                    let armor_label_td = |id: PlArmorId| {
-                        // TODO: fast lookup table
-                        for series in pedia_ex.armors.values() {
-                            for piece in series.pieces.iter().flatten() {
-                                if piece.data.pl_armor_id == id {
-                                    return html!(<td><a href={format!("armor/{:03}.html", series.series.armor_series.0)}>
-                                        {gen_armor_label(Some(piece))}
-                                    </a></td>)
-                                }
-                            }
-                        }
-                        html!(<td>{text!("Unknown armor {:?}", id)}</td>)
+                    let armor_row = |part: &str, armor: &ArenaArmor| {
+                        let label = if let Some((series, piece)) = armor.piece {
+                            html!(<td><a href={format!("armor/{:03}.html", series.series.armor_series.0)}>
+                                {gen_armor_label(Some(piece))}
+                            </a></td>)
+                        } else {
+                            html!(<td>{text!("Unknown armor {:?}", armor.id)}</td>)
+                        };
+                        html!(<tr>
+                            <td>{text!("{}", part)}</td>
+                            {label}
+                            <td>{ text!("Lv{}", armor.lv) }
+                                { armor.defense.map(|defense| text!(" / Defense {}", defense)) }</td>
+                            <td><ul class="mh-armor-skill-list">{
+                                armor.decos.iter().filter(|&&d|d != DecorationsId::None).map(|&d|deco_label_li(d))
+                            }</ul></td>
+                        </tr>)
                     };
 
                     let items_list = |items: &[ItemWork]| {
@@ -1192,6 +1406,7 @@ fn gen_quest(
                     <tr>
                         <td>"Weapon"</td>
                         <td><div>{ gen_weapon_label_from_id(pedia_ex, pl.wep_id) }</div>
+                        { gen_weapon_stat_from_id(pedia_ex, pl.wep_id) }
                         { (pl.insect_id != WeaponId::None && pl.insect_id != WeaponId::Null).then( ||
                             if let Some(insect) = pedia_ex.insect.get(&pl.insect_id) {
                                 html!(<div>{gen_insect_label(insect)}</div>)
@@ -1220,46 +1435,11 @@ fn gen_quest(
                             pl.deco_wep.iter().filter(|&&d|d != DecorationsId::None).map(|&d|deco_label_li(d))
                         }</ul></td>
                     </tr>
-                    <tr>
-                        <td>"Helm"</td>
-                        { armor_label_td(pl.armor_helm) }
-                        <td>{ text!("Lv{}", pl.armor_lv_helm) }</td>
-                        <td><ul class="mh-armor-skill-list">{
-                            pl.deco_helm.iter().filter(|&&d|d != DecorationsId::None).map(|&d|deco_label_li(d))
-                        }</ul></td>
-                    </tr>
-                    <tr>
-                        <td>"Chest"</td>
-                        { armor_label_td(pl.armor_body) }
-                        <td>{ text!("Lv{}", pl.armor_lv_body) }</td>
-                        <td><ul class="mh-armor-skill-list">{
-                            pl.deco_body.iter().filter(|&&d|d != DecorationsId::None).map(|&d|deco_label_li(d))
-                        }</ul></td>
-                    </tr>
-                    <tr>
-                        <td>"Arm"</td>
-                        { armor_label_td(pl.armor_arm) }
-                        <td>{ text!("Lv{}", pl.armor_lv_arm) }</td>
-                        <td><ul class="mh-armor-skill-list">{
-                            pl.deco_arm.iter().filter(|&&d|d != DecorationsId::None).map(|&d|deco_label_li(d))
-                        }</ul></td>
-                    </tr>
-                    <tr>
-                        <td>"Waist"</td>
-                        { armor_label_td(pl.armor_waist) }
-                        <td>{ text!("Lv{}", pl.armor_lv_waist) }</td>
-                        <td><ul class="mh-armor-skill-list">{
-                            pl.deco_waist.iter().filter(|&&d|d != DecorationsId::None).map(|&d|deco_label_li(d))
-                        }</ul></td>
-                    </tr>
-                    <tr>
-                        <td>"Leg"</td>
-                        { armor_label_td(pl.armor_leg) }
-                        <td>{ text!("Lv{}", pl.armor_lv_leg) }</td>
-                        <td><ul class="mh-armor-skill-list">{
-                            pl.deco_leg.iter().filter(|&&d|d != DecorationsId::None).map(|&d|deco_label_li(d))
-                        }</ul></td>
-                    </tr>
+                    { armor_row("Helm", &loadout.armors[0]) }
+                    { armor_row("Chest", &loadout.armors[1]) }
+                    { armor_row("Arm", &loadout.armors[2]) }
+                    { armor_row("Waist", &loadout.armors[3]) }
+                    { armor_row("Leg", &loadout.armors[4]) }
                     <tr>
                         <td>"Petalace"</td>
                         {buff_cage}
@@ -1288,6 +1468,31 @@ fn gen_quest(
                     </tbody>
                     </table></div>
 
+                    <div class="mh-kvlist">
+                    <p class="mh-kv"><span>"Armor defense"</span>
+                        <span>{ text!("{}", loadout.defense) }</span></p>
+                    <p class="mh-kv"><span>"Resistance"</span>
+                        <span>{ text!("Fire {} / Water {} / Thunder {} / Ice {} / Dragon {}",
+                            loadout.resistance[0], loadout.resistance[1], loadout.resistance[2],
+                            loadout.resistance[3], loadout.resistance[4]) }</span></p>
+                    </div>
+
+                    <div class="mh-table"><table>
+                    <thead><tr>
+                        <th>"Skill"</th>
+                        <th>"Total level"</th>
+                    </tr></thead>
+                    <tbody>{ loadout.skills.iter().map(|skill| html!(<tr>
+                        <td>{ gen_skill_label(pedia_ex, skill.id) }</td>
+                        <td>{ match skill.max_lv {
+                            Some(max_lv) if skill.lv > max_lv =>
+                                text!("{} / {} ({} over max)", max_lv, max_lv, skill.lv - max_lv),
+                            Some(max_lv) => text!("{} / {}", skill.lv, max_lv),
+                            None => text!("{}", skill.lv),
+                        } }</td>
+                    </tr>)) }</tbody>
+                    </table></div>
+
                     </section>)
                 }) }
 
@@ -0,0 +1,248 @@
+use super::gen_common::*;
+use super::gen_quest::*;
+use super::gen_website::*;
+use super::hash_store::*;
+use super::pedia::*;
+use super::sink::*;
+use crate::rsz::*;
+use anyhow::Result;
+use std::io::Write;
+use typed_html::{dom::*, elements::*, html, text};
+
+pub fn servant_page(id: i32) -> String {
+    format!("{id:03}.html")
+}
+
+pub fn gen_servant_label(id: i32, pedia_ex: &PediaEx) -> Box<span<String>> {
+    if let Some(servant) = pedia_ex.servant.get(&id) {
+        html!(<span><a href={format!("follower/{}", servant_page(id))}>
+            {gen_multi_lang(servant.name)}
+        </a></span>)
+    } else {
+        html!(<span>{text!("{}", id)}</span>)
+    }
+}
+
+/// Quests the follower joins, with the weapon they use in each
+fn servant_quests<'a>(
+    id: i32,
+    pedia_ex: &'a PediaEx<'a>,
+) -> Vec<(&'a Quest<'a>, PlayerWeaponType)> {
+    pedia_ex
+        .quests
+        .values()
+        .filter_map(|quest| {
+            let info = quest
+                .servant?
+                .servant_info_list
+                .iter()
+                .find(|info| info.servant_id == id)?;
+            Some((quest, info.weapon_type))
+        })
+        .collect()
+}
+
+/// Villager requests the follower is the client of
+fn servant_missions<'a>(servant: &Servant, pedia_ex: &'a PediaEx<'a>) -> Vec<&'a NpcMission<'a>> {
+    let Some(npc_id) = servant.npc_id else {
+        return vec![];
+    };
+    pedia_ex
+        .npc_missions
+        .values()
+        .filter(|mission| mission.param.client_npc == npc_id)
+        .collect()
+}
+
+fn weapon_counts(quests: &[(&Quest, PlayerWeaponType)]) -> Vec<(PlayerWeaponType, usize)> {
+    let mut counts: Vec<(PlayerWeaponType, usize)> = vec![];
+    for &(_, weapon) in quests {
+        if let Some((_, count)) = counts.iter_mut().find(|(w, _)| *w == weapon) {
+            *count += 1;
+        } else {
+            counts.push((weapon, 1));
+        }
+    }
+    counts.sort_by_key(|&(weapon, _)| weapon as i32);
+    counts
+}
+
+pub fn gen_servant_list(
+    hash_store: &HashStore,
+    pedia_ex: &PediaEx,
+    output: &impl Sink,
+) -> Result<()> {
+    let mut servants: Vec<_> = pedia_ex.servant.keys().copied().collect();
+    servants.sort_unstable();
+
+    let doc: DOMTree<String> = html!(
+        <html lang="en">
+            <head itemscope=true>
+                <title>{text!("Followers - MHRice")}</title>
+                { head_common(hash_store, output) }
+            </head>
+            <body>
+                { navbar() }
+                <main>
+                <header><h1>"Followers"</h1></header>
+                <div class="mh-table"><table>
+                <thead><tr>
+                    <th>"Name"</th>
+                    <th>"Weapons"</th>
+                    <th>"Follower quests"</th>
+                </tr></thead>
+                <tbody>{servants.into_iter().map(|id| {
+                    let quests = servant_quests(id, pedia_ex);
+                    html!(<tr>
+                        <td>{gen_servant_label(id, pedia_ex)}</td>
+                        <td>{weapon_counts(&quests).into_iter().map(|(weapon, _)|
+                            html!(<span class="tag">{text!("{}", weapon.name())}</span>))}</td>
+                        <td>{text!("{}", quests.len())}</td>
+                    </tr>)
+                })}</tbody>
+                </table></div>
+
+                <section>
+                <h2>"Talk condition"</h2>
+                <p>"Clearing all of these follower quests is a condition for an NPC conversation."</p>
+                <ul class="mh-quest-list">{
+                    pedia_ex.quests.values()
+                        .filter(|quest| quest.is_mr_all_clear_follower_quest)
+                        .map(|quest| html!(<li>{gen_quest_tag(quest, true, false, None, None)}</li>))
+                }</ul>
+                </section>
+                </main>
+                { right_aside() }
+            </body>
+        </html>
+    );
+
+    output
+        .create_html("follower.html")?
+        .write_all(doc.to_string().as_bytes())?;
+
+    Ok(())
+}
+
+fn gen_servant(
+    hash_store: &HashStore,
+    id: i32,
+    servant: &Servant,
+    pedia_ex: &PediaEx<'_>,
+    config: &WebsiteConfig,
+    path: &impl Sink,
+    toc: &mut Toc,
+) -> Result<()> {
+    let (mut output, mut toc_sink) = path.create_html_with_toc(&servant_page(id), toc)?;
+
+    toc_sink.add(servant.name);
+
+    let quests = servant_quests(id, pedia_ex);
+    let missions = servant_missions(servant, pedia_ex);
+
+    let mut sections = vec![];
+
+    sections.push(Section {
+        title: "Weapons".to_owned(),
+        content: html!(
+            <section id="s-weapon">
+            <h2 >"Weapons"</h2>
+            <div>"Weapon types used in follower quests. "
+                "Equipment and skills of followers are not available in the extracted data."</div>
+            <ul>{weapon_counts(&quests).into_iter().map(|(weapon, count)| html!(<li>
+                {text!("{}: {} quest(s)", weapon.name(), count)}
+            </li>))}</ul>
+            </section>
+        ),
+    });
+
+    sections.push(Section {
+        title: "Follower quests".to_owned(),
+        content: html!(
+            <section id="s-quest">
+            <h2 >"Follower quests"</h2>
+            <div class="mh-table"><table>
+            <thead><tr>
+                <th>"Quest"</th>
+                <th>"Weapon"</th>
+                <th>"Talk condition"</th>
+            </tr></thead>
+            <tbody>{quests.iter().map(|&(quest, weapon)| html!(<tr>
+                <td>{gen_quest_tag(quest, true, false, None, None)}</td>
+                <td>{text!("{}", weapon.name())}</td>
+                <td>{quest.is_mr_all_clear_follower_quest.then(||
+                    html!(<span class="tag">"Counts for all follower quests"</span>))}</td>
+            </tr>))}</tbody>
+            </table></div>
+            </section>
+        ),
+    });
+
+    if !missions.is_empty() {
+        sections.push(Section {
+            title: "Villager requests".to_owned(),
+            content: html!(
+                <section id="s-request">
+                <h2 >"Villager requests"</h2>
+                <div>"Requests whose requester has the same English name as this follower. "
+                    "Followers are not linked to NPC IDs in the extracted data."</div>
+                <ul class="mh-quest-list">{missions.into_iter().map(|mission| html!(<li>
+                    {gen_npc_mission_tag(mission)}
+                </li>))}</ul>
+                </section>
+            ),
+        });
+    }
+
+    let plain_title = format!("Follower {id}");
+    let doc: DOMTree<String> = html!(
+        <html lang="en">
+            <head itemscope=true>
+                <title>{text!("{}", plain_title)}</title>
+                { head_common(hash_store, path) }
+                { title_multi_lang(servant.name) }
+                { open_graph(Some(servant.name), &plain_title,
+                    None, "", None, toc_sink.path(), config) }
+            </head>
+            <body>
+                { navbar() }
+                { gen_menu(&sections, toc_sink.path()) }
+                <main>
+                <header>
+                    <h1>{gen_multi_lang(servant.name)}</h1>
+                </header>
+
+                { sections.into_iter().map(|s|s.content) }
+
+                </main>
+                { right_aside() }
+            </body>
+        </html>
+    );
+
+    output.write_all(doc.to_string().as_bytes())?;
+
+    Ok(())
+}
+
+pub fn gen_servants(
+    hash_store: &HashStore,
+    pedia_ex: &PediaEx<'_>,
+    config: &WebsiteConfig,
+    output: &impl Sink,
+    toc: &mut Toc,
+) -> Result<()> {
+    let servant_path = output.sub_sink("follower")?;
+    for (&id, servant) in &pedia_ex.servant {
+        gen_servant(
+            hash_store,
+            id,
+            servant,
+            pedia_ex,
+            config,
+            &servant_path,
+            toc,
+        )?;
+    }
+    Ok(())
+}
@@ -16,8 +16,8 @@ pub fn skill_page(id: PlEquipSkillId) -> String {
     format!("{}.html", id.to_msg_tag())
 }
 
-pub fn gen_skill_lv_label(pedia_ex: &PediaEx, skill: PlEquipSkillId, lv: i32) -> Box<li<String>> {
-    let name = if let Some(skill_data) = pedia_ex.skills.get(&skill) {
+pub fn gen_skill_label(pedia_ex: &PediaEx, skill: PlEquipSkillId) -> Box<div<String>> {
+    if let Some(skill_data) = pedia_ex.skills.get(&skill) {
         html!(<div class="il"><a href={format!("skill/{}", skill_page(skill))}
             class="mh-icon-text">
             {gen_colored_icon(skill_data.icon_color, "resources/skill", [], false)}
@@ -25,9 +25,12 @@ pub fn gen_skill_lv_label(pedia_ex: &PediaEx, skill: PlEquipSkillId, lv: i32) ->
         </a></div>)
     } else {
         html!(<div class="il">"<UNKNOWN>"</div>)
-    };
+    }
+}
+
+pub fn gen_skill_lv_label(pedia_ex: &PediaEx, skill: PlEquipSkillId, lv: i32) -> Box<li<String>> {
     html!(<li>
-        {name}
+        {gen_skill_label(pedia_ex, skill)}
         {text!(" + {}", lv)}
     </li>)
 }
@@ -101,6 +101,115 @@ pub fn gen_weapon_label_from_id(pedia_ex: &PediaEx, id: WeaponId) -> Box<span<St
     html!(<span>{text!("Unknown weapon {:?}", id)}</span>)
 }
 
+pub fn gen_sharpness_bar(close_range: &CloseRangeWeaponBaseData) -> Box<span<String>> {
+    let highest = close_range
+        .sharpness_val_list
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|&(_, &s)| s != 0)
+        .map_or(0, |(i, _)| i);
+    let mut sharpness_pos = 0;
+    html!(<span class="mh-sharpness-bar">
+        {
+            close_range.sharpness_val_list.iter().enumerate().map(|(i, &s)|{
+                let pos = sharpness_pos as f32 * 0.25;
+                sharpness_pos += s;
+                let width = s as f32 * 0.25;
+                let class = format!("mh-sharpness mh-sharpness-color-{i}");
+                let style = format!("left:{pos}%;width:{width}%;");
+                html!(<span class={class.as_str()} style={style.as_str()} />)
+            })
+        }
+        {
+            close_range.takumi_val_list.iter().enumerate().map(|(i, &s)|{
+                let pos = sharpness_pos as f32 * 0.25;
+                sharpness_pos += s;
+                let width = s as f32 * 0.25;
+                let class = format!("mh-sharpness-half mh-sharpness-color-{}", i + highest);
+                let style = format!("left:{pos}%;width:{width}%;");
+                html!(<span class={class.as_str()} style={style.as_str()} />)
+            })
+        }
+    </span>)
+}
+
+fn gen_element(element_type: PlWeaponElementTypes, element_val: i32) -> Box<span<String>> {
+    let (img, text) = match element_type {
+        PlWeaponElementTypes::None => return html!(<span>"None"</span>),
+        PlWeaponElementTypes::Fire => ("fire", "Fire"),
+        PlWeaponElementTypes::Water => ("water", "Water"),
+        PlWeaponElementTypes::Thunder => ("thunder", "Thunder"),
+        PlWeaponElementTypes::Ice => ("ice", "Ice"),
+        PlWeaponElementTypes::Dragon => ("dragon", "Dragon"),
+        PlWeaponElementTypes::Poison => ("poison", "Poison"),
+        PlWeaponElementTypes::Sleep => ("sleep", "Sleep"),
+        PlWeaponElementTypes::Paralyze => ("para", "Paralyze"),
+        PlWeaponElementTypes::Bomb => ("blast", "Blast"),
+    };
+    let img = format!("resources/{img}.png");
+    html!(<span>
+        <img alt={text} src={img.as_str()} class="mh-small-icon"/>
+        {text!("{} {}", text, element_val)}
+    </span>)
+}
+
+/// Main stats of a weapon in a compact list, for equipment sets listed outside weapon pages
+fn gen_weapon_stat<Param>(weapon: &Weapon<Param>) -> Box<ul<String>>
+where
+    Param: ToBase<MainWeaponBaseData>
+        + MaybeToBase<ElementWeaponBaseData>
+        + MaybeToBase<DualBladesBaseUserDataParam>
+        + MaybeToBase<CloseRangeWeaponBaseData>,
+{
+    let main: &MainWeaponBaseData = weapon.param.to_base();
+    let element: Option<&ElementWeaponBaseData> = weapon.param.maybe_to_base();
+    let second_element: Option<&DualBladesBaseUserDataParam> = weapon.param.maybe_to_base();
+    let close_range: Option<&CloseRangeWeaponBaseData> = weapon.param.maybe_to_base();
+    html!(<ul class="mh-weapon-stat">
+        <li>{text!("Attack {}", main.atk)}</li>
+        <li>{text!("Affinity {}%", main.critical_rate)}
+            {weapon.chaos.map(|chaos|text!(" / {}%", chaos.chaos_critical_num))}</li>
+        { (main.def_bonus != 0).then(|| html!(<li>{text!("Defense {}", main.def_bonus)}</li>)) }
+        { element.filter(|e| e.main_element_type != PlWeaponElementTypes::None).map(|e| html!(<li>
+            { gen_element(e.main_element_type, e.main_element_val) }
+            { second_element.and_then(|second_element|
+                (second_element.sub_element_type != PlWeaponElementTypes::None).then(||
+                gen_element(second_element.sub_element_type, second_element.sub_element_val))
+            ) }
+        </li>)) }
+        <li>"Slot "{gen_slot(&main.slot_num_list, false)}</li>
+        { close_range.map(|close_range| html!(<li>{gen_sharpness_bar(close_range)}</li>)) }
+    </ul>)
+}
+
+pub fn gen_weapon_stat_from_id(pedia_ex: &PediaEx, id: WeaponId) -> Option<Box<ul<String>>> {
+    macro_rules! check_weapon {
+        ($weapon:ident) => {
+            if let Some(w) = pedia_ex.$weapon.weapons.get(&id) {
+                return Some(gen_weapon_stat(w));
+            }
+        };
+    }
+
+    check_weapon!(great_sword);
+    check_weapon!(short_sword);
+    check_weapon!(hammer);
+    check_weapon!(lance);
+    check_weapon!(long_sword);
+    check_weapon!(slash_axe);
+    check_weapon!(gun_lance);
+    check_weapon!(dual_blades);
+    check_weapon!(horn);
+    check_weapon!(insect_glaive);
+    check_weapon!(charge_axe);
+    check_weapon!(light_bowgun);
+    check_weapon!(heavy_bowgun);
+    check_weapon!(bow);
+
+    None
+}
+
 #[allow(unused_variables)]
 fn no<T, Base>(t: &T) -> Option<&Base> {
     None
@@ -284,40 +393,9 @@ where
     });
 
     let sharpness = close_range.map(|close_range| {
-        let highest = close_range
-            .sharpness_val_list
-            .iter()
-            .enumerate()
-            .rev()
-            .find(|&(_, &s)| s != 0)
-            .map_or(0, |(i, _)| i);
-        let mut sharpness_pos = 0;
         html!(
         <p class="mh-kv"><span>"Sharpness"</span>
-        <span>
-        <span class="mh-sharpness-bar">
-            {
-                close_range.sharpness_val_list.iter().enumerate().map(|(i, &s)|{
-                    let pos = sharpness_pos as f32 * 0.25;
-                    sharpness_pos += s;
-                    let width = s as f32 * 0.25;
-                    let class = format!("mh-sharpness mh-sharpness-color-{i}");
-                    let style = format!("left:{pos}%;width:{width}%;");
-                    html!(<span class={class.as_str()} style={style.as_str()} />)
-                })
-            }
-            {
-                close_range.takumi_val_list.iter().enumerate().map(|(i, &s)|{
-                    let pos = sharpness_pos as f32 * 0.25;
-                    sharpness_pos += s;
-                    let width = s as f32 * 0.25;
-                    let class = format!("mh-sharpness-half mh-sharpness-color-{}", i + highest);
-                    let style = format!("left:{pos}%;width:{width}%;");
-                    html!(<span class={class.as_str()} style={style.as_str()} />)
-                })
-            }
-        </span>
-        </span></p>)
+        <span>{gen_sharpness_bar(close_range)}</span></p>)
     });
 
     struct BowBottleMap {
@@ -391,26 +469,6 @@ where
         ),
     });
 
-    let gen_element = |element_type: PlWeaponElementTypes, element_val: i32| {
-        let (img, text) = match element_type {
-            PlWeaponElementTypes::None => return html!(<span>"None"</span>),
-            PlWeaponElementTypes::Fire => ("fire", "Fire"),
-            PlWeaponElementTypes::Water => ("water", "Water"),
-            PlWeaponElementTypes::Thunder => ("thunder", "Thunder"),
-            PlWeaponElementTypes::Ice => ("ice", "Ice"),
-            PlWeaponElementTypes::Dragon => ("dragon", "Dragon"),
-            PlWeaponElementTypes::Poison => ("poison", "Poison"),
-            PlWeaponElementTypes::Sleep => ("sleep", "Sleep"),
-            PlWeaponElementTypes::Paralyze => ("para", "Paralyze"),
-            PlWeaponElementTypes::Bomb => ("blast", "Blast"),
-        };
-        let img = format!("resources/{img}.png");
-        html!(<span>
-            <img alt={text} src={img.as_str()} class="mh-small-icon"/>
-            {text!("{} {}", text, element_val)}
-        </span>)
-    };
-
     sections.push(Section {
         title: "Stat".to_owned(),
         content: html!(<section id="s-stat">
@@ -1,6 +1,7 @@
 use super::gen_armor::*;
 use super::gen_common::*;
 use super::gen_dlc::*;
+use super::gen_ec::*;
 use super::gen_hyakuryu_skill::*;
 use super::gen_item::*;
 use super::gen_map::*;
@@ -8,11 +9,13 @@ use super::gen_misc::*;
 use super::gen_monster::*;
 use super::gen_otomo::*;
 use super::gen_quest::*;
+use super::gen_servant::*;
 use super::gen_skill::*;
 use super::gen_weapon::*;
 use super::hash_store::*;
 use super::pedia::*;
 use super::sink::*;
+use crate::msg::markup::Markup;
 use crate::msg::*;
 use crate::part_color::*;
 use crate::rsz::*;
@@ -138,6 +141,8 @@ pub fn navbar() -> Box<nav<String>> {
                 <div class="navbar-dropdown">
                     <a class="navbar-item" href="quest.html">"Main quests"</a>
                     <a class="navbar-item" href="villager_request.html">"Villager requests"</a>
+                    <a class="navbar-item" href="follower.html">"Followers"</a>
+                    <a class="navbar-item" href="crown_hunting.html">"Crown hunting"</a>
                 </div>
                 </div>
 
@@ -226,6 +231,7 @@ pub fn navbar() -> Box<nav<String>> {
                     <a class="navbar-item" href="misc/meowcenaries.html">"Meowcenaries"</a>
                     <a class="navbar-item" href="misc/scraps.html">"Trade for scraps"</a>
                     <a class="navbar-item" href="dlc.html">"DLC"</a>
+                    <a class="navbar-item" href="ec.html">"Endemic life"</a>
                     <a class="navbar-item" href="misc/award.html">"Awards"</a>
                     <a class="navbar-item" href="misc/achievement.html">"Guild card titles"</a>
                 </div>
@@ -235,125 +241,6 @@ pub fn navbar() -> Box<nav<String>> {
     </div></nav>)
 }
 
-struct Tag<'a> {
-    tag: &'a str,
-    arg: &'a str,
-    seq: Seq<'a>,
-}
-enum Node<'a> {
-    Raw(&'a str),
-    Tagged(Tag<'a>),
-}
-
-struct Seq<'a> {
-    nodes: Vec<Node<'a>>,
-}
-
-fn parse_msg(content: &str) -> (Seq, bool) {
-    let mut msg = content;
-    let mut has_warning = false;
-
-    let mut root = Seq { nodes: vec![] };
-    let mut stack: Vec<Tag> = vec![];
-
-    loop {
-        let next_stop = if let Some(next_stop) = msg.find('<') {
-            next_stop
-        } else {
-            msg.len()
-        };
-
-        let raw = Node::Raw(&msg[0..next_stop]);
-        if let Some(last) = stack.last_mut() {
-            last.seq.nodes.push(raw);
-        } else {
-            root.nodes.push(raw);
-        }
-
-        if msg.len() == next_stop {
-            if !stack.is_empty() {
-                has_warning = true;
-                //eprintln!("Unfinished tag: {}", content);
-            }
-
-            while let Some(stack_tag) = stack.pop() {
-                let tag = Node::Tagged(stack_tag);
-                if let Some(last) = stack.last_mut() {
-                    last.seq.nodes.push(tag);
-                } else {
-                    root.nodes.push(tag);
-                }
-            }
-
-            break;
-        }
-
-        msg = &msg[(next_stop + 1)..];
-
-        let next_stop = if let Some(next_stop) = msg.find('>') {
-            next_stop
-        } else {
-            if !stack.is_empty() {
-                has_warning = true;
-                eprintln!("Parse error: {content}");
-            }
-            break;
-        };
-        let tag = &msg[0..next_stop];
-        msg = &msg[(next_stop + 1)..];
-
-        if let Some(tag) = tag.strip_prefix('/') {
-            let stack_tag = if let Some(stack_tag) = stack.pop() {
-                stack_tag
-            } else {
-                has_warning = true;
-                eprintln!("Parse error: {content}");
-                break;
-            };
-            if stack_tag.tag != tag {
-                has_warning = true;
-                eprintln!("Parse error: {content}");
-            }
-            let tag = Node::Tagged(stack_tag);
-            if let Some(last) = stack.last_mut() {
-                last.seq.nodes.push(tag);
-            } else {
-                root.nodes.push(tag);
-            }
-        } else {
-            let (tag, arg) = if let Some(space) = tag.find(' ') {
-                (&tag[0..space], &tag[(space + 1)..])
-            } else {
-                (tag, "")
-            };
-            let mut tag = Tag {
-                tag,
-                arg,
-                seq: Seq { nodes: vec![] },
-            };
-
-            if tag.tag == "COLS" {
-                tag.tag = "COL";
-                stack.push(tag);
-            } else if matches!(
-                tag.tag,
-                "COLOR" | "COL" | "BSL" | "LEFT" | "FONT" | "TCU" | "size"
-            ) {
-                stack.push(tag);
-            } else {
-                let tag = Node::Tagged(tag);
-                if let Some(last) = stack.last_mut() {
-                    last.seq.nodes.push(tag);
-                } else {
-                    root.nodes.push(tag);
-                }
-            }
-        }
-    }
-
-    (root, has_warning)
-}
-
 pub fn translate_msg<'r, RefF>(
     content: &str,
     language_i: usize,
@@ -362,105 +249,15 @@ pub fn translate_msg<'r, RefF>(
 where
     RefF: Fn(&str) -> Option<&'r MsgEntry> + Clone,
 {
-    let (root, has_warning) = parse_msg(content);
-
-    fn translate_rec<'r, RefF>(
-        node: &Node<'_>,
-        language_i: usize,
-        reference: RefF,
-    ) -> Box<dyn PhrasingContent<String>>
-    where
-        RefF: Fn(&str) -> Option<&'r MsgEntry> + Clone,
-    {
-        match node {
-            Node::Raw(s) => Box::new(TextNode::<String>::new(*s)),
-            Node::Tagged(t) => {
-                let inner = t
-                    .seq
-                    .nodes
-                    .iter()
-                    .map(|n| translate_rec(n, language_i, reference.clone()));
-                match t.tag {
-                    "COLOR" => {
-                        let style = format!("color: #{};", t.arg);
-                        html!(<span style={style}> {inner} </span>)
-                    }
-                    "COL" => {
-                        let color = match t.arg {
-                            "RED" => "red",
-                            "YEL" | "YELLOW" => "orange",
-                            "GRAY" => "gray",
-                            _ => {
-                                eprintln!("Unknown color: {}", t.arg);
-                                "black"
-                            }
-                        };
-                        let style = format!("color: {color};");
-                        html!(<span style={style}> {inner} </span>)
-                    }
-                    "LSNR" => {
-                        // Gender selector
-                        html!(<span class="mh-msg-place-holder"> {text!("{}", t.arg)} </span>)
-                    }
-                    "BSL" => {
-                        // Text direction change?
-                        html!(<span> {inner} </span>)
-                    }
-                    "PL" => {
-                        html!(<span class="mh-msg-place-holder"> "{Player}" </span>)
-                    }
-                    "ПУСТО" => {
-                        html!(<span> "<ПУСТО>" </span>)
-                    }
-                    "REF" => {
-                        if let Some(entry) = reference(t.arg) {
-                            let Some(content) = entry.content.get(language_i) else {
-                                return html! (<span class="mh-msg-place-holder">
-                                    <i class="fas fa-triangle-exclamation"/> "[Unsupported language]"
-                                </span>);
-                            };
-                            let (translated, _inner_has_warning) =
-                                translate_msg(content, language_i, reference.clone());
-                            //has_warning |= _inner_has_warning; // TODO: ehh
-                            translated
-                        } else {
-                            html!(<span class="mh-msg-place-holder">{text!("{{{} {}}}", t.tag, t.arg)}</span>)
-                        }
-                    }
-                    _ => {
-                        eprintln!("Unknown tag: {}", t.tag);
-                        html!(<span>
-                            <span class="mh-msg-place-holder">{text!("<{} {}>", t.tag, t.arg)}</span>
-                            {inner}
-                            <span class="mh-msg-place-holder">{text!("</{}>", t.tag)}</span>
-                        </span>)
-                    }
-                }
-            }
-        }
-    }
-
-    let result = html!(<span> {root.nodes.iter().map(|n|translate_rec(n, language_i, reference.clone()))} </span>);
-    (result, has_warning)
+    let markup = Markup::parse(content);
+    (
+        markup.to_html(language_i, reference),
+        markup.has_parse_error(),
+    )
 }
 
 pub fn translate_msg_plain(content: &str) -> String {
-    let (root, _) = parse_msg(content);
-    fn translate_rec(result: &mut String, node: &Node<'_>) {
-        match node {
-            Node::Raw(s) => *result += *s,
-            Node::Tagged(t) => {
-                for inner in &t.seq.nodes {
-                    translate_rec(result, inner)
-                }
-            }
-        }
-    }
-    let mut result = String::new();
-    for node in &root.nodes {
-        translate_rec(&mut result, node)
-    }
-    result
+    Markup::parse(content).to_plain_text()
 }
 
 pub fn gen_multi_lang(msg: &MsgEntry) -> Box<span<String>> {
@@ -735,8 +532,11 @@ pub fn gen_website(
     gen_part_color_css(hash_store, output)?;
     gen_quests(hash_store, pedia, pedia_ex, config, output, &mut toc)?;
     gen_quest_list(hash_store, &pedia_ex.quests, output)?;
+    gen_crown_hunting(hash_store, pedia, pedia_ex, output)?;
     gen_npc_missions(hash_store, pedia, pedia_ex, config, output, &mut toc)?;
     gen_npc_mission_list(hash_store, pedia_ex, output)?;
+    gen_servants(hash_store, pedia_ex, config, output, &mut toc)?;
+    gen_servant_list(hash_store, pedia_ex, output)?;
     gen_skills(hash_store, pedia_ex, config, output, &mut toc)?;
     gen_skill_list(hash_store, &pedia_ex.skills, output)?;
     gen_hyakuryu_skills(hash_store, pedia_ex, config, output, &mut toc)?;
@@ -749,6 +549,8 @@ pub fn gen_website(
     gen_weapons(hash_store, pedia, pedia_ex, config, output, &mut toc)?;
     gen_maps(hash_store, pedia, pedia_ex, config, output, &mut toc)?;
     gen_map_list(hash_store, pedia, output)?;
+    gen_ecs(hash_store, pedia, pedia_ex, config, output, &mut toc)?;
+    gen_ec_list(hash_store, pedia, pedia_ex, output)?;
     gen_otomo_equips(hash_store, pedia_ex, config, output, &mut toc)?;
     gen_otomo_equip_list(hash_store, pedia_ex, output)?;
     gen_about(hash_store, pedia, output)?;
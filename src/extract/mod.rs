@@ -1,9 +1,12 @@
 #![allow(unused_braces)]
 #![allow(clippy::too_many_arguments)]
 
+mod arena;
+mod fish;
 mod gen_armor;
 mod gen_common;
 mod gen_dlc;
+mod gen_ec;
 mod gen_hyakuryu_skill;
 mod gen_item;
 mod gen_map;
@@ -12,6 +15,7 @@ mod gen_monster;
 mod gen_otomo;
 mod gen_pedia;
 mod gen_quest;
+mod gen_servant;
 mod gen_skill;
 mod gen_weapon;
 mod gen_website;
@@ -19,8 +23,13 @@ pub mod hash_store;
 pub mod logger;
 mod pedia;
 mod prepare_map;
+mod rampage;
 pub mod sink;
+mod spiribird;
+mod stage;
 
 pub use gen_pedia::gen_resources;
 pub use gen_pedia::{gen_pedia, gen_pedia_ex};
-pub use gen_website::{gen_multi_lang, gen_website, WebsiteConfig};
+pub use gen_website::{gen_multi_lang, gen_website, WebsiteConfig, LANGUAGE_MAP};
+pub use spiribird::print_spiribird_route;
+pub use stage::export_stage;
@@ -1,3 +1,4 @@
+use super::fish::FishingSpot;
 use super::prepare_map::*;
 use crate::msg::*;
 use crate::rsz::*;
@@ -556,8 +557,85 @@ pub struct MonsterEx<'a> {
     pub map_data: BTreeMap<i32, MonsterMapData<'a>>,
 }
 
+/// A gathering point on a map, with the yield table resolved for the map
+pub struct GatherPoint<'a> {
+    pub map: i32,
+    /// Index in `GameMap::pops`, which is also the icon ID on the map page
+    pub pop_index: usize,
+    pub behavior: &'a ItemPopBehavior,
+    pub lot: &'a ItemPopLotTableUserDataParam,
+}
+
+impl<'a> GatherPoint<'a> {
+    /// Item, amount and probability in percentage of each draw in a quest rank
+    pub fn yields(&self, rank: QuestRank) -> impl Iterator<Item = (ItemId, u32, u32)> + 'a {
+        let lot = self.lot;
+        let (id, num, probability) = match rank {
+            QuestRank::Low => (&lot.lower_id, &lot.lower_num, &lot.lower_probability),
+            QuestRank::High => (&lot.upper_id, &lot.upper_num, &lot.upper_probability),
+            QuestRank::Master => (&lot.master_id, &lot.master_num, &lot.master_probability),
+        };
+        id.iter()
+            .zip(num)
+            .zip(probability)
+            .filter(|&((&id, _), _)| id != ItemId::None)
+            .map(|((&id, &num), &probability)| (id, num, probability))
+    }
+}
+
+/// One endemic life placed on a map
+pub struct EndemicLifePop<'a> {
+    pub map: i32,
+    /// Index in `GameMap::pops`, which is also the icon ID on the map page
+    pub pop_index: usize,
+    pub behavior: &'a EnvironmentCreatureWrapper,
+    pub effects: &'a EcEffects,
+}
+
+/// An endemic life type with all of its placements across maps
+pub struct EndemicLife<'a> {
+    pub name: Option<&'a MsgEntry>,
+    pub pops: Vec<EndemicLifePop<'a>>,
+}
+
+/// A point in the enemy prefab of a map, referred by its unique ID
+#[derive(Debug, Serialize)]
+pub struct RoutePoint {
+    pub unique_id: u32,
+    pub zone: i32,
+    /// Position on the map image in percentage, if the point is found in the map
+    pub map_position: Option<(f32, f32)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpawnChoice {
+    pub point: RoutePoint,
+    pub lot: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpawnSet<'a> {
+    pub set_name: &'a str,
+    pub choices: Vec<SpawnChoice>,
+}
+
+/// Where a monster starts, moves and rests on a map
+#[derive(Debug, Serialize)]
+pub struct MonsterRoute<'a> {
+    pub em_type: EmTypes,
+    pub spawn_sets: Vec<SpawnSet<'a>>,
+    pub sleep_points: Vec<RoutePoint>,
+    pub eat_points: Vec<RoutePoint>,
+    pub escape_points: Vec<RoutePoint>,
+    pub zones: &'a [EnemyBlockMoveDataBlockBasicInfo],
+    pub routes: &'a [EnemyBlockMoveDataRouteInfo],
+}
+
 pub struct Servant<'a> {
     pub name: &'a MsgEntry,
+    /// snow.NpcDefine.NpcID of the follower as the client of villager requests,
+    /// inferred from the English name of the requester
+    pub npc_id: Option<i32>,
 }
 
 #[derive(Debug)]
@@ -634,6 +712,14 @@ pub struct Insect<'a> {
     pub name: &'a MsgEntry,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CrownChance {
+    // All in percentage
+    pub small: f64,
+    pub big: f64,
+    pub king: f64,
+}
+
 pub struct PediaEx<'a> {
     pub monsters: BTreeMap<EmTypes, MonsterEx<'a>>,
     pub sizes: HashMap<EmTypes, &'a SizeInfo>,
@@ -688,4 +774,53 @@ pub struct PediaEx<'a> {
     pub slc: BTreeMap<SaveLinkContents, Slc<'a>>,
     // pub map_icon_list: HashMap<i32, &'a MapDetailIconListGPopData>,
     pub insect: HashMap<WeaponId, Insect<'a>>,
+    pub monster_routes: BTreeMap</*map*/ i32, Vec<MonsterRoute<'a>>>,
+    pub gather_points: Vec<GatherPoint<'a>>,
+    pub fishing_spots: Vec<FishingSpot<'a>>,
+    pub endemic_life: BTreeMap</*ec type*/ i32, EndemicLife<'a>>,
+    pub ec_names: HashMap</*ec type*/ i32, &'a MsgEntry>,
+}
+
+impl<'a> PediaEx<'a> {
+    /// Exact probability of getting each crown size for a monster,
+    /// given the quest's scale table and base scale (in percentage)
+    pub fn crown_chance(
+        &self,
+        em_type: EmTypes,
+        scale_tbl: i32,
+        base_scale: u8,
+    ) -> Option<CrownChance> {
+        let size = self.sizes.get(&em_type)?;
+        let size_dist = self.size_dists.get(&scale_tbl)?;
+        if size.no_size_scale {
+            return Some(CrownChance::default());
+        }
+
+        let total: u32 = size_dist.iter().map(|sample| sample.rate).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut small = 0;
+        let mut big = 0;
+        let mut king = 0;
+        for sample in *size_dist {
+            let scale = sample.scale * f32::from(base_scale) / 100.0;
+            if scale <= size.small_boarder {
+                small += sample.rate;
+            }
+            if scale >= size.king_boarder {
+                king += sample.rate;
+            } else if scale >= size.big_boarder {
+                big += sample.rate;
+            }
+        }
+
+        let to_percentage = |rate: u32| f64::from(rate) * 100.0 / f64::from(total);
+        Some(CrownChance {
+            small: to_percentage(small),
+            big: to_percentage(big),
+            king: to_percentage(king),
+        })
+    }
 }
@@ -1,5 +1,6 @@
 use super::gen_pedia::pfb_user;
 use super::sink::Sink;
+use crate::gpu::RgbaImage;
 use crate::pak::*;
 use crate::pfb::*;
 use crate::rsz;
@@ -12,7 +13,7 @@ use nalgebra::*;
 use nalgebra_glm::*;
 use serde::*;
 use std::collections::BTreeMap;
-use std::io::{Cursor, Read, Seek};
+use std::io::Cursor;
 use std::rc::*;
 
 struct MapFiles {
@@ -182,6 +183,7 @@ pub enum MapPopKind {
     },
     Ec {
         behavior: rsz::EnvironmentCreatureWrapper,
+        effects: EcEffects,
     },
     Fg {
         behavior: rsz::FieldGimmickWrapper,
@@ -201,6 +203,70 @@ pub enum MapPopKind {
     },
 }
 
+impl MapPopKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapPopKind::Item { .. } => "Item",
+            MapPopKind::WireLongJump { .. } => "WireLongJump",
+            MapPopKind::Camp { .. } => "Camp",
+            MapPopKind::FishingPoint { .. } => "FishingPoint",
+            MapPopKind::Recon { .. } => "Recon",
+            MapPopKind::Ec { .. } => "Ec",
+            MapPopKind::Fg { .. } => "Fg",
+            MapPopKind::Bush { .. } => "Bush",
+            MapPopKind::InsideMove { .. } => "InsideMove",
+            MapPopKind::BlockMove { .. } => "BlockMove",
+            MapPopKind::InitSet { .. } => "InitSet",
+        }
+    }
+}
+
+/// What an endemic life does to the hunter, read from the components next to its behavior
+#[derive(Debug, Serialize, Default)]
+pub struct EcEffects {
+    /// Item obtained when the endemic life is caught
+    pub item: Option<rsz::ItemId>,
+    /// Spiribird. Raises a status up to the petalace limit
+    pub lv_buff: bool,
+    /// Temporary buff when touched
+    pub buff: bool,
+    /// Wirebug buff
+    pub wire_buff: bool,
+    pub trap: bool,
+    /// Can be used for a long wire jump
+    pub long_wire: bool,
+    /// Drops an item when hit
+    pub drop: bool,
+    /// Time window to photograph it
+    pub photo: Option<rsz::EnvironmentCreaturePhoto>,
+}
+
+impl EcEffects {
+    fn from_object(object: &GameObject) -> EcEffects {
+        fn enabled<T: 'static>(object: &GameObject, f: impl Fn(&T) -> bool) -> bool {
+            object.get_component::<T>().is_ok_and(f)
+        }
+        EcEffects {
+            item: object
+                .get_component::<rsz::EnvironmentCreatureItem>()
+                .ok()
+                .filter(|c| c.enabled)
+                .map(|c| c.env_creature_item_id),
+            lv_buff: enabled(object, |c: &rsz::EnvironmentCreatureLvBuff| c.enabled),
+            buff: enabled(object, |c: &rsz::EnvironmentCreatureBuff| c.enabled),
+            wire_buff: enabled(object, |c: &rsz::EnvironmentCreatureWireBuff| c.enabled),
+            trap: enabled(object, |c: &rsz::EnvironmentCreatureTrap| c.enabled),
+            long_wire: enabled(object, |c: &rsz::EnvironmentCreatureLongWire| c.enabled),
+            drop: enabled(object, |c: &rsz::EnvironmentCreatureDrop| c.enabled),
+            photo: object
+                .get_component::<rsz::EnvironmentCreaturePhoto>()
+                .ok()
+                .filter(|c| c.enabled)
+                .cloned(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct MapPop {
     pub position: Vec3,
@@ -210,6 +276,8 @@ pub struct MapPop {
 #[derive(Debug, Serialize)]
 pub struct GameMap {
     pub layer_count: usize,
+    /// Number of zoom levels in the tile pyramid of each layer
+    pub tile_levels: Vec<u32>,
     pub x_offset: f32,
     pub y_offset: f32,
     pub map_scale: f32,
@@ -217,8 +285,31 @@ pub struct GameMap {
     pub ec_data: Option<rsz::EnvironmentCreatureData>,
 }
 
-fn get_map<F: Read + Seek>(
-    pak: &mut PakReader<F>,
+impl GameMap {
+    /// Converts a position in the stage to percentage on the map image
+    pub fn map_position(&self, id: i32, position: &Vec3) -> (f32, f32) {
+        let mut px = position.x;
+        let mut py = position.y;
+        // snow.gui.QuestUIManager.isInNo42SafeZone
+        if id == 15
+            && (-43.0..=136.0).contains(&position.x)
+            && (163.0..=197.0).contains(&position.z)
+            && (56.0..=252.0).contains(&position.y)
+        {
+            // Some magic constant from code and gui resource...
+            let magic_size = 1080.0;
+            px += -200.0 / magic_size * self.map_scale;
+            py += 100.0 / magic_size * self.map_scale;
+        }
+
+        let x = (px + self.x_offset) / self.map_scale * 100.0;
+        let y = (py + self.y_offset) / self.map_scale * 100.0;
+        (x, y)
+    }
+}
+
+fn get_map<F: PakSource>(
+    pak: &PakReader<F>,
     files: &MapFiles,
     map_no: i32,
 ) -> Result<Option<GameMap>> {
@@ -306,7 +397,10 @@ fn get_map<F: Read + Seek>(
         {
             pops.push(MapPop {
                 position,
-                kind: MapPopKind::Ec { behavior },
+                kind: MapPopKind::Ec {
+                    behavior,
+                    effects: EcEffects::from_object(object),
+                },
             });
         } else if let Ok(behavior) = object
             .filter_component(|rsz| rsz::FG_TYPE_MAP.get(&rsz.symbol()).map(|f| f(rsz).unwrap()))
@@ -408,8 +502,22 @@ fn get_map<F: Read + Seek>(
         })
     }
 
+    let tile_levels = files
+        .tex_files
+        .iter()
+        .map(|&name| {
+            // Missing textures are also skipped in gen_map_resource
+            let Ok(tex) = pak.find_file(name) else {
+                return Ok(1);
+            };
+            let tex = Tex::new(Cursor::new(pak.read_file(tex)?))?;
+            Ok(map_tile_levels(tex.width(), tex.height()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
     Ok(Some(GameMap {
         layer_count: files.tex_files.len(),
+        tile_levels,
         x_offset: scale.map_wide_min_pos,
         y_offset: scale.map_height_min_pos,
         map_scale: scale.map_scale,
@@ -418,7 +526,7 @@ fn get_map<F: Read + Seek>(
     }))
 }
 
-pub fn prepare_maps(pak: &mut PakReader<impl Read + Seek>) -> Result<BTreeMap<i32, GameMap>> {
+pub fn prepare_maps(pak: &PakReader<impl PakSource>) -> Result<BTreeMap<i32, GameMap>> {
     MAP_FILES
         .iter()
         .enumerate()
@@ -433,7 +541,66 @@ pub fn prepare_maps(pak: &mut PakReader<impl Read + Seek>) -> Result<BTreeMap<i3
         .collect()
 }
 
-pub fn gen_map_resource(pak: &mut PakReader<impl Read + Seek>, output: &impl Sink) -> Result<()> {
+fn map_files(map_no: i32) -> Option<&'static MapFiles> {
+    MAP_FILES.get(usize::try_from(map_no).ok()?)?.as_ref()
+}
+
+pub fn map_scene_file(map_no: i32) -> Option<&'static str> {
+    Some(map_files(map_no)?.scene_file)
+}
+
+/// Loads a single map. Returns `None` if the map is not in the PAK.
+pub fn load_map(pak: &PakReader<impl PakSource>, map_no: i32) -> Result<Option<GameMap>> {
+    let files = map_files(map_no).with_context(|| format!("Unknown map {map_no}"))?;
+    get_map(pak, files, map_no)
+}
+
+/// Width and height of a map tile in pixels
+pub const MAP_TILE_SIZE: u32 = 256;
+
+/// Number of zoom levels for a map texture of the given size.
+/// Level 0 is a single tile, and each level doubles the resolution
+/// until the texture is shown at its full size.
+pub fn map_tile_levels(width: u32, height: u32) -> u32 {
+    let mut levels = 1;
+    while MAP_TILE_SIZE << (levels - 1) < width.max(height) {
+        levels += 1;
+    }
+    levels
+}
+
+/// Writes the tile pyramid of a map layer as `<level>/<x>_<y>.png`.
+///
+/// The texture is stretched to a square like on the map page,
+/// so level `n` is a grid of 2^n by 2^n tiles.
+fn gen_map_tiles(image: &RgbaImage, output: &impl Sink) -> Result<()> {
+    let levels = map_tile_levels(image.width(), image.height());
+    let top_size = MAP_TILE_SIZE << (levels - 1);
+    let mut level_image = image.resize(top_size, top_size);
+    for level in (0..levels).rev() {
+        if level != levels - 1 {
+            let size = MAP_TILE_SIZE << level;
+            level_image = level_image.resize(size, size);
+        }
+        let level_output = output.sub_sink(&level.to_string())?;
+        let count = 1 << level;
+        for x in 0..count {
+            for y in 0..count {
+                level_image
+                    .sub_image(
+                        x * MAP_TILE_SIZE,
+                        y * MAP_TILE_SIZE,
+                        MAP_TILE_SIZE,
+                        MAP_TILE_SIZE,
+                    )?
+                    .save_png(level_output.create(&format!("{x}_{y}.png"))?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn gen_map_resource(pak: &PakReader<impl PakSource>, output: &impl Sink) -> Result<()> {
     for (i, f) in MAP_FILES.iter().enumerate() {
         if let Some(f) = f {
             for (j, &name) in f.tex_files.iter().enumerate() {
@@ -442,8 +609,8 @@ pub fn gen_map_resource(pak: &mut PakReader<impl Read + Seek>, output: &impl Sin
                 } else {
                     continue;
                 };
-                let output_file = output.create(&format!("map{i:02}_{j}.png"))?;
-                Tex::new(Cursor::new(pak.read_file(tex)?))?.save_png(0, 0, output_file)?
+                let image = Tex::new(Cursor::new(pak.read_file(tex)?))?.to_rgba(0, 0)?;
+                gen_map_tiles(&image, &output.sub_sink(&format!("map{i:02}_{j}"))?)?;
             }
         }
     }
@@ -0,0 +1,147 @@
+//! Wave timeline of rampage quests.
+//!
+//! The monsters of a wave are the boss and the slots of `em_table`,
+//! where a species listed in several slots comes as several monsters.
+//! In quests of the apex category, the boss of the last wave is the apex.
+//!
+//! This only partly covers the wave data: scale (nando) and order tables
+//! are referenced by number, as their contents are not extracted.
+//! Rewards by clear rank are not included either, as the rampage reward tables are not extracted.
+
+use super::pedia::*;
+use crate::rsz::*;
+use serde::*;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum RampageRole {
+    Monster,
+    Boss,
+    Apex,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RampageMonster {
+    pub em_type: EmTypes,
+    pub sub_type: Option<u8>,
+    pub count: usize,
+    pub role: RampageRole,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RampageWave {
+    /// Starts from 1
+    pub wave_no: usize,
+    /// The boss first, then the other monsters in table order
+    pub monsters: Vec<RampageMonster>,
+    pub boss_nando_tbl_no: i32,
+    pub wave_nando_tbl_no: i32,
+    pub order_table_no: i32,
+    /// Scale table of Magnamalo if it appears in this wave
+    pub extra_em_nando_tbl_no: Option<i8>,
+    /// The optional sixth task if it appears in this wave
+    pub extra_target: Option<QuestTargetType>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RampageTimeline {
+    pub quest_no: i32,
+    pub quest_lv: QuestLevel,
+    pub map_no: i32,
+    pub category: HyakuryuQuestCategory,
+    pub is_village: bool,
+    pub fixed_wave_order: bool,
+    pub final_boss_kill: bool,
+    pub base_time: u8,
+    pub start_block_no: u8,
+    pub end_block_no: u8,
+    pub nushi_order_tbl_no: u8,
+    pub hm_unlock_tbl_no: u8,
+    pub sub_target: Vec<QuestTargetType>,
+    pub waves: Vec<RampageWave>,
+}
+
+fn resolve_wave(
+    wave_no: usize,
+    wave: &HyakuryuQuestDataWaveData,
+    data: &HyakuryuQuestData,
+    is_last: bool,
+) -> RampageWave {
+    let mut monsters = vec![RampageMonster {
+        em_type: wave.boss_em,
+        sub_type: Some(wave.boss_sub_type as u8),
+        count: 1,
+        role: if is_last && data.category == HyakuryuQuestCategory::Nushi {
+            RampageRole::Apex
+        } else {
+            RampageRole::Boss
+        },
+    }];
+
+    let mut others: Vec<RampageMonster> = vec![];
+    for &em_type in &wave.em_table {
+        if em_type == EmTypes::Em(0) {
+            continue;
+        }
+        if let Some(monster) = others.iter_mut().find(|m| m.em_type == em_type) {
+            monster.count += 1;
+        } else {
+            others.push(RampageMonster {
+                em_type,
+                sub_type: None,
+                count: 1,
+                role: RampageRole::Monster,
+            })
+        }
+    }
+    monsters.extend(others);
+
+    let extra_target = data.sub_target[5];
+    RampageWave {
+        wave_no,
+        monsters,
+        boss_nando_tbl_no: wave.boss_em_nando_tbl_no,
+        wave_nando_tbl_no: wave.wave_em_nando_tbl_no,
+        order_table_no: wave.order_table_no,
+        extra_em_nando_tbl_no: (usize::from(data.extra_em_wave_no) == wave_no)
+            .then_some(data.extra_em_nando_tbl_no),
+        extra_target: (extra_target != QuestTargetType::None
+            && usize::from(data.sub_target5_wave_no) == wave_no)
+            .then_some(extra_target),
+    }
+}
+
+pub fn rampage_timeline(quest: &Quest) -> Option<RampageTimeline> {
+    let data = quest.hyakuryu?;
+    let last_wave = data
+        .wave_data
+        .iter()
+        .rposition(|wave| wave.boss_em != EmTypes::Em(0));
+    let waves = data
+        .wave_data
+        .iter()
+        .enumerate()
+        .filter(|(_, wave)| wave.boss_em != EmTypes::Em(0))
+        .map(|(i, wave)| resolve_wave(i + 1, wave, data, Some(i) == last_wave))
+        .collect();
+    Some(RampageTimeline {
+        quest_no: data.quest_no,
+        quest_lv: data.quest_lv,
+        map_no: data.map_no,
+        category: data.category,
+        is_village: data.is_village,
+        fixed_wave_order: data.attr.contains(HyakuryuQuestAttr::FIX_WAVE_ORDER),
+        final_boss_kill: data.attr.contains(HyakuryuQuestAttr::FINAL_BOSS_KILL),
+        base_time: data.base_time,
+        start_block_no: data.start_block_no,
+        end_block_no: data.end_block_no,
+        nushi_order_tbl_no: data.nushi_order_tbl_no,
+        hm_unlock_tbl_no: data.hm_unlock_tbl_no,
+        sub_target: data
+            .sub_target
+            .iter()
+            .copied()
+            .filter(|&target| target != QuestTargetType::None)
+            .collect(),
+        waves,
+    })
+}
@@ -0,0 +1,288 @@
+//! Routes to collect spiribirds until the stat limits of a petalace are reached.
+//!
+//! A route starts at a camp and repeatedly flies to the nearest spiribird that
+//! still raises a stat below its limit. Spiribirds made unnecessary by later ones
+//! are then dropped, and the visiting order is shortened with 2-opt.
+//! Distances are straight lines between pop positions, ignoring terrain,
+//! so the result is a good route rather than a proven shortest one.
+
+use super::gen_pedia::get_singleton;
+use super::prepare_map::*;
+use crate::pak::*;
+use crate::rsz::{LvBuffCageId, NormalLvBuffCageBaseUserData, NormalLvBuffCageBaseUserDataParam};
+use anyhow::{Context, Result};
+use nalgebra_glm::*;
+use serde::*;
+
+/// Stats in the order of the petalace arrays
+pub const SPIRIBIRD_STATS: [&str; 4] = ["Health", "Stamina", "Attack", "Defense"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum SpiribirdBuff {
+    /// Index into `SPIRIBIRD_STATS`
+    Stat(usize),
+    /// Rainbow spiribird
+    All,
+}
+
+/// What a spiribird raises, or `None` if the endemic life is not a spiribird
+pub fn spiribird_buff(ec_type: i32, effects: &EcEffects) -> Option<SpiribirdBuff> {
+    if !effects.lv_buff {
+        return None;
+    }
+    // The colored spiribirds, by their icon colors in get_ec_icon_color
+    Some(match ec_type {
+        0x22 => SpiribirdBuff::Stat(2), // red
+        0x23 => SpiribirdBuff::Stat(3), // orange
+        0x24 => SpiribirdBuff::Stat(0), // green
+        0x25 => SpiribirdBuff::Stat(1), // yellow
+        _ => SpiribirdBuff::All,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct RouteStop {
+    /// Index in `GameMap::pops`, which is also the icon ID on the map page
+    pub pop_index: usize,
+    pub buff: SpiribirdBuff,
+    /// Distance from the previous stop
+    pub distance: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpiribirdRoute {
+    /// Pop index of the camp to start from
+    pub start: usize,
+    pub stops: Vec<RouteStop>,
+    pub distance: f32,
+    /// Stats gained at the end of the route, capped by the limits
+    pub gain: [u32; 4],
+    pub limit: [u32; 4],
+    /// Whether all limits are reached
+    pub complete: bool,
+}
+
+struct Bird {
+    pop_index: usize,
+    position: Vec3,
+    buff: SpiribirdBuff,
+}
+
+struct Petalace {
+    add: [u32; 4],
+    all_add: [u32; 4],
+    limit: [u32; 4],
+}
+
+impl Petalace {
+    fn new(data: &NormalLvBuffCageBaseUserDataParam) -> Petalace {
+        let [health, stamina, attack, defense, _] = data.status_buff_limit;
+        Petalace {
+            add: data.status_buff_add_value,
+            all_add: data.status_buff_all_add_value,
+            limit: [health, stamina, attack, defense],
+        }
+    }
+
+    fn gain(&self, birds: &[&Bird]) -> [u32; 4] {
+        let mut gain = [0; 4];
+        for bird in birds {
+            self.apply(&mut gain, bird.buff);
+        }
+        gain
+    }
+
+    fn apply(&self, gain: &mut [u32; 4], buff: SpiribirdBuff) {
+        for (i, g) in gain.iter_mut().enumerate() {
+            let add = match buff {
+                SpiribirdBuff::Stat(stat) if stat == i => self.add[i],
+                SpiribirdBuff::Stat(_) => 0,
+                SpiribirdBuff::All => self.all_add[i],
+            };
+            *g = (*g + add).min(self.limit[i]);
+        }
+    }
+
+    fn is_useful(&self, gain: &[u32; 4], buff: SpiribirdBuff) -> bool {
+        let mut new_gain = *gain;
+        self.apply(&mut new_gain, buff);
+        new_gain != *gain
+    }
+}
+
+fn path_length(start: &Vec3, stops: &[&Bird]) -> f32 {
+    let mut length = 0.0;
+    let mut prev = start;
+    for stop in stops {
+        length += distance(prev, &stop.position);
+        prev = &stop.position;
+    }
+    length
+}
+
+fn plan_from<'a>(start: &Vec3, birds: &'a [Bird], petalace: &Petalace) -> Vec<&'a Bird> {
+    let mut route: Vec<&Bird> = vec![];
+    let mut visited = vec![false; birds.len()];
+    let mut gain = [0; 4];
+    let mut position = *start;
+    loop {
+        let next = birds
+            .iter()
+            .enumerate()
+            .filter(|&(i, bird)| !visited[i] && petalace.is_useful(&gain, bird.buff))
+            .min_by(|(_, a), (_, b)| {
+                distance(&position, &a.position).total_cmp(&distance(&position, &b.position))
+            });
+        let Some((i, bird)) = next else {
+            break;
+        };
+        visited[i] = true;
+        petalace.apply(&mut gain, bird.buff);
+        position = bird.position;
+        route.push(bird);
+    }
+
+    // A rainbow spiribird picked later can make earlier ones unnecessary
+    let final_gain = petalace.gain(&route);
+    let mut i = route.len();
+    while i > 0 {
+        i -= 1;
+        let mut without = route.clone();
+        without.remove(i);
+        if petalace.gain(&without) == final_gain {
+            route = without;
+        }
+    }
+
+    // 2-opt on the open path from the start
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..route.len() {
+            for k in i + 1..route.len() {
+                let before = if i == 0 {
+                    start
+                } else {
+                    &route[i - 1].position
+                };
+                let old = distance(before, &route[i].position)
+                    + route
+                        .get(k + 1)
+                        .map_or(0.0, |next| distance(&route[k].position, &next.position));
+                let new = distance(before, &route[k].position)
+                    + route
+                        .get(k + 1)
+                        .map_or(0.0, |next| distance(&route[i].position, &next.position));
+                if new + 1e-3 < old {
+                    route[i..=k].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    route
+}
+
+/// Plans a route on a map for a petalace, trying every camp as the start.
+/// Returns `None` if the map has no camp or no spiribird.
+pub fn plan_spiribird_route(
+    map: &GameMap,
+    petalace: &NormalLvBuffCageBaseUserDataParam,
+) -> Option<SpiribirdRoute> {
+    let petalace = Petalace::new(petalace);
+    let mut birds = vec![];
+    let mut camps = vec![];
+    for (pop_index, pop) in map.pops.iter().enumerate() {
+        match &pop.kind {
+            MapPopKind::Ec { behavior, effects } => {
+                if let Some(buff) = spiribird_buff(behavior.base.type_, effects) {
+                    birds.push(Bird {
+                        pop_index,
+                        position: pop.position,
+                        buff,
+                    })
+                }
+            }
+            MapPopKind::Camp { .. } => camps.push((pop_index, pop.position)),
+            _ => (),
+        }
+    }
+    if birds.is_empty() {
+        return None;
+    }
+
+    let (start, start_position, route) = camps
+        .iter()
+        .map(|&(camp, position)| (camp, position, plan_from(&position, &birds, &petalace)))
+        .min_by(|(_, a_start, a), (_, b_start, b)| {
+            // Prefer higher gains, then shorter routes
+            let a_gain: u32 = petalace.gain(a).iter().sum();
+            let b_gain: u32 = petalace.gain(b).iter().sum();
+            b_gain
+                .cmp(&a_gain)
+                .then(path_length(a_start, a).total_cmp(&path_length(b_start, b)))
+        })?;
+
+    let gain = petalace.gain(&route);
+    let mut prev = start_position;
+    let stops = route
+        .iter()
+        .map(|bird| {
+            let stop = RouteStop {
+                pop_index: bird.pop_index,
+                buff: bird.buff,
+                distance: distance(&prev, &bird.position),
+            };
+            prev = bird.position;
+            stop
+        })
+        .collect();
+    Some(SpiribirdRoute {
+        start,
+        stops,
+        distance: path_length(&start_position, &route),
+        gain,
+        limit: petalace.limit,
+        complete: gain == petalace.limit,
+    })
+}
+
+pub fn print_spiribird_route<F: PakSource>(
+    pak: &PakReader<F>,
+    map_no: i32,
+    petalace_id: u32,
+) -> Result<()> {
+    let game_map = load_map(pak, map_no)?.context("Map not found in the PAK")?;
+    let buff_cage: NormalLvBuffCageBaseUserData = get_singleton(pak, None)?;
+    let petalace = buff_cage
+        .param
+        .iter()
+        .find(|param| param.id == LvBuffCageId::Normal(petalace_id))
+        .with_context(|| format!("Petalace {petalace_id} not found"))?;
+    let route =
+        plan_spiribird_route(&game_map, petalace).context("No camp or spiribird on the map")?;
+
+    println!("Start from camp #{}", route.start);
+    for (i, stop) in route.stops.iter().enumerate() {
+        let buff = match stop.buff {
+            SpiribirdBuff::Stat(stat) => SPIRIBIRD_STATS[stat],
+            SpiribirdBuff::All => "All",
+        };
+        println!(
+            "{:>3}. #{:<5} {:<8} +{:.0}",
+            i + 1,
+            stop.pop_index,
+            buff,
+            stop.distance
+        );
+    }
+    println!("Total distance: {:.0}", route.distance);
+    for (i, stat) in SPIRIBIRD_STATS.iter().enumerate() {
+        println!("{stat}: {} / {}", route.gain[i], route.limit[i]);
+    }
+    if !route.complete {
+        println!("Not enough spiribirds on the map to reach all limits");
+    }
+    Ok(())
+}
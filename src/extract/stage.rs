@@ -0,0 +1,270 @@
+//! Exports the 3D geometry of a stage together with its pops.
+//!
+//! The geometry comes from the stage scenes: the main scene of the map with its subscenes,
+//! and every other known scene in the same folder (`scene/mXX/normal/*.scn`).
+//! The glTF file has three node groups:
+//!  - "Render": render meshes placed by `via.render.Mesh`
+//!  - "Collision": triangles of the collision meshes (.mcol) placed by `via.physics.Colliders`.
+//!    Primitive shapes in these files are not exported, and navmesh is not decoded.
+//!  - "Pops": markers of gathering points and other pops
+
+use super::logger::*;
+use super::prepare_map::*;
+use crate::gltf::*;
+use crate::mcol::Mcol;
+use crate::mesh::Mesh;
+use crate::pak::*;
+use crate::rsz;
+use crate::scn::*;
+use anyhow::{Context, Result};
+use nalgebra_glm::*;
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Combines the local transforms from the root object down to the current object
+fn world_matrix(transforms: &[&rsz::Transform]) -> Mat4x4 {
+    transforms.iter().fold(Mat4x4::identity(), |matrix, t| {
+        let rotation = quat_to_mat4(&Quat::from(t.rotation));
+        matrix * translation(&t.position.xyz()) * rotation * scaling(&t.scale.xyz())
+    })
+}
+
+fn load_mesh<F: PakSource>(pak: &PakReader<F>, path: &str) -> Result<Option<GltfMesh>> {
+    let index = pak.find_file(path)?;
+    let mesh = Mesh::new(Cursor::new(pak.read_file(index)?))?;
+    let triangles = mesh.triangles()?;
+    if triangles.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(GltfMesh {
+        name: path.to_owned(),
+        positions: mesh.positions()?,
+        triangles,
+    }))
+}
+
+/// Loads the triangles of a collision mesh, and counts the primitive shapes left out
+fn load_mcol<F: PakSource>(
+    pak: &PakReader<F>,
+    path: &str,
+    skipped_shapes: &mut u32,
+) -> Result<Option<GltfMesh>> {
+    let index = pak.find_file(path)?;
+    let mcol = Mcol::new(Cursor::new(pak.read_file(index)?))?;
+    *skipped_shapes += mcol.sphere_count + mcol.capsule_count + mcol.box_count;
+    if mcol.triangles.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(GltfMesh {
+        name: path.to_owned(),
+        positions: mcol.positions,
+        triangles: mcol.triangles.iter().map(|t| t.vertices).collect(),
+    }))
+}
+
+/// Adds a node for each instance, and returns the node indices.
+/// Meshes are shared by all instances of the same file.
+fn add_instances(
+    gltf: &mut Gltf,
+    instances: Vec<(String, String, Mat4x4)>,
+    mut load: impl FnMut(&str) -> Result<Option<GltfMesh>>,
+    logger: &mut Logger,
+) -> Result<Vec<usize>> {
+    lscope!(logger, "meshes");
+    let mut mesh_indices: HashMap<String, Option<usize>> = HashMap::new();
+    let mut nodes = vec![];
+    for (name, path, transform) in instances {
+        let mesh = match mesh_indices.get(&path) {
+            Some(&mesh) => mesh,
+            None => {
+                let mesh = match load(&path) {
+                    Ok(Some(mesh)) => {
+                        gltf.meshes.push(mesh);
+                        Some(gltf.meshes.len() - 1)
+                    }
+                    Ok(None) => None,
+                    Err(e) => {
+                        writeln!(logger, "Skipped {path}: {e:#}")?;
+                        None
+                    }
+                };
+                mesh_indices.insert(path, mesh);
+                mesh
+            }
+        };
+        if let Some(mesh) = mesh {
+            gltf.nodes.push(GltfNode {
+                name,
+                mesh: Some(mesh),
+                transform,
+                extras: None,
+                children: vec![],
+            });
+            nodes.push(gltf.nodes.len() - 1);
+        }
+    }
+    Ok(nodes)
+}
+
+fn add_group(gltf: &mut Gltf, name: &str, children: Vec<usize>) {
+    gltf.nodes.push(GltfNode {
+        name: name.to_owned(),
+        mesh: None,
+        transform: Mat4x4::identity(),
+        extras: None,
+        children,
+    });
+}
+
+/// Collects paths of the subscenes loaded into a scene, and messages of the ones that failed
+fn collect_subscenes(folders: &[Folder], loaded: &mut BTreeSet<String>, failed: &mut Vec<String>) {
+    for folder in folders {
+        if let (Some(path), Some(subscene)) = (&folder.folder.path, &folder.subscene) {
+            match subscene {
+                Ok(subscene) => {
+                    loaded.insert(path.to_lowercase());
+                    collect_subscenes(&subscene.folders, loaded, failed);
+                }
+                Err(e) => failed.push(format!("Skipped subscene {path}: {e:#}")),
+            }
+        }
+        collect_subscenes(&folder.subfolders, loaded, failed);
+    }
+}
+
+/// Loads the main scene of the map, and other known scenes in the same folder
+/// that are not already loaded as its subscenes.
+/// Other scenes can only be found if their paths are loaded into `pak`.
+fn load_stage_scenes<F: PakSource>(
+    pak: &PakReader<F>,
+    scene_file: &str,
+    logger: &mut Logger,
+) -> Result<Vec<Scene>> {
+    lscope!(logger, "scenes");
+    let main = Scene::new(pak, scene_file)?;
+    let mut loaded = BTreeSet::from([scene_file.to_lowercase()]);
+    let mut failed = vec![];
+    collect_subscenes(&main.folders, &mut loaded, &mut failed);
+
+    let folder = scene_file.rsplit_once('/').map_or("", |(folder, _)| folder);
+    let others: Vec<String> = pak
+        .known_paths()
+        .filter(|path| {
+            let lower = path.to_lowercase();
+            lower.ends_with(".scn")
+                && lower
+                    .rsplit_once('/')
+                    .is_some_and(|(dir, _)| dir.eq_ignore_ascii_case(folder))
+        })
+        .map(str::to_owned)
+        .collect();
+
+    let mut scenes = vec![main];
+    for path in others {
+        // Skip scenes already loaded as subscenes of earlier ones
+        if !loaded.insert(path.to_lowercase()) {
+            continue;
+        }
+        match Scene::new(pak, &path) {
+            Ok(scene) => {
+                collect_subscenes(&scene.folders, &mut loaded, &mut failed);
+                scenes.push(scene)
+            }
+            Err(e) => failed.push(format!("Skipped scene {path}: {e:#}")),
+        }
+    }
+    for message in failed {
+        writeln!(logger, "{message}")?;
+    }
+    writeln!(logger, "Loaded {} scenes", scenes.len())?;
+    Ok(scenes)
+}
+
+pub fn export_stage<F: PakSource>(
+    pak: &PakReader<F>,
+    map_no: i32,
+    output: &Path,
+    logger: &mut Logger,
+) -> Result<()> {
+    lscope!(logger, "stage{map_no:02}");
+    let scene_file = map_scene_file(map_no).context("Unknown map")?;
+    let game_map = load_map(pak, map_no)?.context("Map not found in the PAK")?;
+    let scenes = load_stage_scenes(pak, scene_file, logger)?;
+
+    let mut render_instances = vec![];
+    let mut collision_instances = vec![];
+    for scene in &scenes {
+        scene.for_each_object(&mut |object: &GameObject, transforms: &[&rsz::Transform]| {
+            let name = object.object.name.clone().unwrap_or_default();
+            for component in &object.components {
+                if let Some(mesh) = component.downcast_ref::<rsz::ViaMesh>() {
+                    if let (true, Some(path)) = (mesh.enabled, &mesh.mesh_path) {
+                        render_instances.push((
+                            name.clone(),
+                            path.clone(),
+                            world_matrix(transforms),
+                        ));
+                    }
+                } else if let Some(colliders) = component.downcast_ref::<rsz::Colliders>() {
+                    for shape in colliders.v5.iter().map(|collider| &collider.v2) {
+                        if let Some(path) = &shape.v1 {
+                            let transform = world_matrix(transforms) * shape.v2;
+                            collision_instances.push((name.clone(), path.clone(), transform));
+                        }
+                    }
+                }
+            }
+            Ok(true)
+        })?;
+    }
+
+    let mut gltf = Gltf::default();
+
+    let render = add_instances(
+        &mut gltf,
+        render_instances,
+        |path| load_mesh(pak, path),
+        logger,
+    )?;
+    add_group(&mut gltf, "Render", render);
+
+    let mut skipped_shapes = 0;
+    let collision = add_instances(
+        &mut gltf,
+        collision_instances,
+        |path| load_mcol(pak, path, &mut skipped_shapes),
+        logger,
+    )?;
+    add_group(&mut gltf, "Collision", collision);
+    if skipped_shapes != 0 {
+        writeln!(
+            logger,
+            "Skipped {skipped_shapes} primitive shapes in collision meshes"
+        )?;
+    }
+
+    let mut pops = vec![];
+    for (i, pop) in game_map.pops.iter().enumerate() {
+        // Pop positions are stored with the height in z
+        let position = vec3(pop.position.x, pop.position.z, pop.position.y);
+        let (map_x, map_y) = game_map.map_position(map_no, &pop.position);
+        gltf.nodes.push(GltfNode {
+            name: format!("{} {i}", pop.kind.name()),
+            mesh: None,
+            transform: translation(&position),
+            extras: Some(json!({
+                "pop_index": i,
+                "map_position": [map_x, map_y],
+                "data": serde_json::to_value(&pop.kind)?,
+            })),
+            children: vec![],
+        });
+        pops.push(gltf.nodes.len() - 1);
+    }
+    add_group(&mut gltf, "Pops", pops);
+
+    gltf.save(output)
+}
@@ -11,15 +11,14 @@ let g_classes_to_hide = new Set();
 
 let g_cur_map_explain = "default";
 
-let g_map_scale = 100;
 let g_map_layer = 0;
+let g_map_view = null;
+let g_map_hidden = new Set();
 
 let g_filter = "all";
 
 let g_toc = null;
 
-let g_map_pos = { top: 0, left: 0, x: 0, y: 0, container: null };
-
 let g_diagram_current = new Map();
 let g_diagram_template = new Map();
 
@@ -74,7 +73,6 @@ document.addEventListener('DOMContentLoaded', function () {
     change_sort("armor", 1);
     //change_sort("npcmission", 1);
 
-    initFilter("map");
     initFilter("item");
     initFilter("armor");
     initFilter("skill");
@@ -83,6 +81,7 @@ document.addEventListener('DOMContentLoaded', function () {
     autoSearch();
 
     initWeaponTreeMasonry();
+    initMap();
     updateMapIconForFloor();
 });
 
@@ -122,11 +121,12 @@ function addEventListensers() {
     addEventListenerToId("combo-weapon-tree", "change", onChangeWeaponTree)
 
     addEventListenerToClass("mh-map-filter-item", "click", onShowMapExplain);
-    addEventListenerToClass("mh-map-filter-button", "click", changeMapFilter);
+    addEventListenerToClass("mh-map-filter-button", "click", toggleMapCategory);
     addEventListenerToId("button-scale-down", "click", scaleDownMap);
     addEventListenerToId("button-scale-up", "click", scaleUpMap);
+    addEventListenerToId("button-map-reset", "click", resetMap);
     addEventListenerToId("button-map-layer", "click", switchMapLayer);
-    addEventListenerToId("mh-map-container", "mousedown", startDragMap);
+    addEventListenerToId("mh-spiribird-select", "change", onChangeSpiribirdRoute);
     addEventListenerToClass("undraggable", "dragstart", undraggable);
 
     addEventListenerToId("mh-invalid-part-check", "click",
@@ -386,7 +386,18 @@ function onToggleNavbarMenu() {
 }
 
 function onShowMapExplain(e) {
-    const id = removePrefix(e.currentTarget.id, "mh-map-icon-");
+    if (g_map_view !== null && g_map_view.dragged) {
+        return;
+    }
+    showMapExplain(removePrefix(e.currentTarget.id, "mh-map-icon-"));
+    scheduleMapHash();
+}
+
+function showMapExplain(id) {
+    const explain = document.getElementById(`mh-map-explain-${id}`);
+    if (explain === null) {
+        return;
+    }
     if (g_cur_map_explain !== null) {
         document.getElementById(`mh-map-explain-${g_cur_map_explain}`).classList.add("mh-hidden");
         if (g_cur_map_explain !== "default") {
@@ -394,44 +405,239 @@ function onShowMapExplain(e) {
         }
     }
     g_cur_map_explain = id;
-    document.getElementById(`mh-map-explain-${g_cur_map_explain}`).classList.remove("mh-hidden");
-    document.getElementById(`mh-map-icon-${g_cur_map_explain}`).classList.add("mh-map-select");
+    explain.classList.remove("mh-hidden");
+    if (id !== "default") {
+        document.getElementById(`mh-map-icon-${id}`).classList.add("mh-map-select");
+    }
 }
 
-function updateMapScale() {
-    const map = document.getElementById("mh-map");
-    map.style.width = `${g_map_scale}%`;
-    map.style.paddingTop = `${g_map_scale}%`;
+function onChangeSpiribirdRoute(e) {
+    for (const c of ["mh-spiribird-route", "mh-spiribird-summary"]) {
+        for (const element of document.getElementsByClassName(c)) {
+            element.classList.add("mh-hidden");
+        }
+        const selected = document.getElementById(`${c}-${e.currentTarget.value}`);
+        if (selected !== null) {
+            selected.classList.remove("mh-hidden");
+        }
+    }
 }
 
-function scaleUpMap() {
-    if (g_map_scale >= 500) {
-        return
+// The map is a square in its own coordinates from (0, 0) to (1, 1).
+// The view is described by the map point at the center of the container,
+// and the zoom relative to fitting the whole map in the container.
+const MAP_TILE_SIZE = 256;
+const MAP_MAX_ZOOM = 16;
+
+function initMap() {
+    const container = document.getElementById("mh-map-container");
+    if (container === null) {
+        return;
+    }
+    g_map_view = {
+        container,
+        map: document.getElementById("mh-map"),
+        x: 0.5,
+        y: 0.5,
+        zoom: 1,
+        pointers: new Map(),
+        dragged: false,
+        hash_timer: null,
+    };
+
+    loadMapHash();
+
+    container.addEventListener("wheel", onMapWheel, { passive: false });
+    container.addEventListener("pointerdown", onMapPointerDown);
+    container.addEventListener("pointermove", onMapPointerMove);
+    container.addEventListener("pointerup", onMapPointerUp);
+    container.addEventListener("pointercancel", onMapPointerUp);
+    window.addEventListener("resize", renderMap);
+    // Only fires on navigation like back/forward or an edited URL, not on history.replaceState
+    window.addEventListener("hashchange", onMapHashChange);
+
+    renderMap();
+}
+
+function onMapHashChange() {
+    // A pending save would overwrite the new hash with the old view
+    if (g_map_view.hash_timer !== null) {
+        clearTimeout(g_map_view.hash_timer);
+        g_map_view.hash_timer = null;
+    }
+    loadMapHash();
+    updateMapIconForFloor();
+    renderMap();
+}
+
+function mapViewSize() {
+    const container = g_map_view.container;
+    const width = container.clientWidth;
+    const height = container.clientHeight;
+    return { width, height, size: Math.min(width, height) * g_map_view.zoom };
+}
+
+function renderMap() {
+    const view = g_map_view;
+    const { width, height, size } = mapViewSize();
+    const left = width / 2 - view.x * size;
+    const top = height / 2 - view.y * size;
+    view.map.style.width = `${size}px`;
+    view.map.style.height = `${size}px`;
+    view.map.style.transform = `translate(${left}px, ${top}px)`;
+
+    const layer = document.getElementById(`mh-map-layer-${g_map_layer}`);
+    if (layer !== null) {
+        renderMapTiles(layer, size, left, top, width, height);
     }
 
-    g_map_scale += 50;
+    document.getElementById("button-scale-down").disabled = view.zoom <= 1;
+    document.getElementById("button-scale-up").disabled = view.zoom >= MAP_MAX_ZOOM;
+}
+
+function renderMapTiles(layer, size, left, top, width, height) {
+    // The level 0 tile is always shown below as the base image
+    const levels = parseInt(layer.getAttribute("data-levels"));
+    const wanted_level = Math.ceil(Math.log2(size * window.devicePixelRatio / MAP_TILE_SIZE));
+    const level = Math.max(0, Math.min(levels - 1, wanted_level));
+    const count = 1 << level;
+    const tile_size = size / count;
+
+    const wanted = new Set();
+    if (level > 0) {
+        const x0 = Math.max(0, Math.floor(-left / tile_size));
+        const x1 = Math.min(count - 1, Math.floor((width - left) / tile_size));
+        const y0 = Math.max(0, Math.floor(-top / tile_size));
+        const y1 = Math.min(count - 1, Math.floor((height - top) / tile_size));
+        for (let x = x0; x <= x1; ++x) {
+            for (let y = y0; y <= y1; ++y) {
+                wanted.add(`${level}/${x}_${y}`);
+            }
+        }
+    }
 
-    document.getElementById("button-scale-down").disabled = false;
-    if (g_map_scale >= 500) {
-        document.getElementById("button-scale-up").disabled = true;
+    for (const tile of Array.from(layer.getElementsByClassName("mh-map-tile"))) {
+        const key = tile.getAttribute("data-tile");
+        if (wanted.has(key)) {
+            wanted.delete(key);
+        } else {
+            tile.remove();
+        }
     }
 
-    updateMapScale()
+    const tiles = layer.getAttribute("data-tiles");
+    for (const key of wanted) {
+        const [x, y] = key.split("/")[1].split("_").map(n => parseInt(n));
+        const tile = document.createElement("img");
+        tile.alt = "";
+        tile.draggable = false;
+        tile.className = "mh-map-tile undraggable";
+        tile.setAttribute("data-tile", key);
+        tile.style.left = `${x / count * 100}%`;
+        tile.style.top = `${y / count * 100}%`;
+        tile.style.width = `${100 / count}%`;
+        tile.style.height = `${100 / count}%`;
+        tile.src = `${tiles}/${key}.png`;
+        layer.appendChild(tile);
+    }
+}
+
+function clampMapView() {
+    const view = g_map_view;
+    view.zoom = Math.max(1, Math.min(MAP_MAX_ZOOM, view.zoom));
+    view.x = Math.max(0, Math.min(1, view.x));
+    view.y = Math.max(0, Math.min(1, view.y));
+}
+
+// Zooms by factor while keeping the map point under (px, py) of the container still
+function zoomMapAt(factor, px, py) {
+    const view = g_map_view;
+    const before = mapViewSize();
+    const mx = view.x + (px - before.width / 2) / before.size;
+    const my = view.y + (py - before.height / 2) / before.size;
+    view.zoom *= factor;
+    clampMapView();
+    const after = mapViewSize();
+    view.x = mx - (px - after.width / 2) / after.size;
+    view.y = my - (py - after.height / 2) / after.size;
+    clampMapView();
+    renderMap();
+    scheduleMapHash();
+}
+
+function zoomMapAtCenter(factor) {
+    const { width, height } = mapViewSize();
+    zoomMapAt(factor, width / 2, height / 2);
+}
+
+function scaleUpMap() {
+    zoomMapAtCenter(2);
 }
 
 function scaleDownMap() {
-    if (g_map_scale <= 100) {
-        return
+    zoomMapAtCenter(0.5);
+}
+
+function resetMap() {
+    g_map_view.x = 0.5;
+    g_map_view.y = 0.5;
+    g_map_view.zoom = 1;
+    renderMap();
+    scheduleMapHash();
+}
+
+function onMapWheel(e) {
+    e.preventDefault();
+    const rect = g_map_view.container.getBoundingClientRect();
+    zoomMapAt(Math.pow(2, -e.deltaY / 500), e.clientX - rect.left, e.clientY - rect.top);
+}
+
+function onMapPointerDown(e) {
+    const view = g_map_view;
+    if (view.pointers.size === 0) {
+        view.dragged = false;
     }
+    view.pointers.set(e.pointerId, { x: e.clientX, y: e.clientY });
+}
 
-    g_map_scale -= 50
+function onMapPointerMove(e) {
+    const view = g_map_view;
+    const previous = view.pointers.get(e.pointerId);
+    if (previous === undefined) {
+        return;
+    }
+    const current = { x: e.clientX, y: e.clientY };
+    if (!view.dragged && Math.hypot(current.x - previous.x, current.y - previous.y) < 4) {
+        return;
+    }
+    if (!view.dragged) {
+        view.dragged = true;
+        view.container.setPointerCapture(e.pointerId);
+    }
 
-    document.getElementById("button-scale-up").disabled = false;
-    if (g_map_scale <= 100) {
-        document.getElementById("button-scale-down").disabled = true;
+    if (view.pointers.size === 2) {
+        // Pinch: zoom by the change of distance around the midpoint of the fingers
+        const [other] = Array.from(view.pointers).filter(([id]) => id !== e.pointerId).map(([, p]) => p);
+        const before = Math.hypot(previous.x - other.x, previous.y - other.y);
+        const after = Math.hypot(current.x - other.x, current.y - other.y);
+        const rect = view.container.getBoundingClientRect();
+        if (before > 0) {
+            zoomMapAt(after / before, (current.x + other.x) / 2 - rect.left,
+                (current.y + other.y) / 2 - rect.top);
+        }
+    } else if (view.pointers.size === 1) {
+        const { size } = mapViewSize();
+        view.x -= (current.x - previous.x) / size;
+        view.y -= (current.y - previous.y) / size;
+        clampMapView();
+        renderMap();
+        scheduleMapHash();
     }
+    view.pointers.set(e.pointerId, current);
+}
 
-    updateMapScale()
+function onMapPointerUp(e) {
+    g_map_view.pointers.delete(e.pointerId);
 }
 
 function switchMapLayer() {
@@ -445,10 +651,101 @@ function switchMapLayer() {
     prev.classList.add("mh-hidden");
     cur.classList.remove("mh-hidden");
     updateMapIconForFloor();
+    renderMap();
+    scheduleMapHash();
+}
+
+function toggleMapCategory(e) {
+    const category = removePrefix(e.currentTarget.id, "mh-map-filter-button-");
+    if (category === "all") {
+        g_map_hidden.clear();
+    } else if (g_map_hidden.has(category)) {
+        g_map_hidden.delete(category);
+    } else {
+        g_map_hidden.add(category);
+    }
+    updateMapCategories();
+    scheduleMapHash();
+}
+
+function updateMapCategories() {
+    const style = document.getElementById("mh-map-list-style");
+    style.innerHTML = Array.from(g_map_hidden)
+        .map(category => `.mh-map-filter-item[data-filter="${category}"] { display:none !important; }`)
+        .join("\n");
+    for (const button of document.getElementsByClassName("mh-map-filter-button")) {
+        const category = removePrefix(button.id, "mh-map-filter-button-");
+        const active = category === "all" ? g_map_hidden.size === 0 : !g_map_hidden.has(category);
+        if (active) {
+            button.classList.add("is-active");
+        } else {
+            button.classList.remove("is-active");
+        }
+    }
 }
 
-function changeMapFilter(e) {
-    changeFilter(e, 'map');
+// The view is kept in the URL hash like #x=0.5&y=0.5&z=2&layer=1&hide=bush,fg&icon=12
+// Missing parameters reset to the default view, so that the hash can also be reloaded after a change.
+function loadMapHash() {
+    const param = new URLSearchParams(location.hash.slice(1));
+    const view = g_map_view;
+    const number = (name, fallback) => {
+        const value = parseFloat(param.get(name));
+        return Number.isFinite(value) ? value : fallback;
+    };
+
+    const layer = number("layer", 0);
+    if (layer !== g_map_layer && document.getElementById(`mh-map-layer-${layer}`) !== null) {
+        document.getElementById(`mh-map-layer-${g_map_layer}`).classList.add("mh-hidden");
+        document.getElementById(`mh-map-layer-${layer}`).classList.remove("mh-hidden");
+        g_map_layer = layer;
+    }
+
+    const hide = param.get("hide");
+    g_map_hidden = new Set(hide ? hide.split(",") : []);
+    updateMapCategories();
+
+    view.zoom = number("z", 1);
+    const icon = param.get("icon");
+    const icon_element = icon === null ? null : document.getElementById(`mh-map-icon-${icon}`);
+    if (icon_element !== null) {
+        showMapExplain(icon);
+        // Center on the icon unless the position is also given
+        view.x = parseFloat(icon_element.style.left) / 100;
+        view.y = parseFloat(icon_element.style.top) / 100;
+    } else {
+        showMapExplain("default");
+    }
+    view.x = number("x", view.x);
+    view.y = number("y", view.y);
+    clampMapView();
+}
+
+function scheduleMapHash() {
+    // Avoid flooding the history API while dragging
+    if (g_map_view.hash_timer !== null) {
+        clearTimeout(g_map_view.hash_timer);
+    }
+    g_map_view.hash_timer = setTimeout(saveMapHash, 200);
+}
+
+function saveMapHash() {
+    const view = g_map_view;
+    view.hash_timer = null;
+    const param = new URLSearchParams();
+    param.set("x", view.x.toFixed(4));
+    param.set("y", view.y.toFixed(4));
+    param.set("z", view.zoom.toFixed(2));
+    if (g_map_layer !== 0) {
+        param.set("layer", g_map_layer);
+    }
+    if (g_map_hidden.size !== 0) {
+        param.set("hide", Array.from(g_map_hidden).join(","));
+    }
+    if (g_cur_map_explain !== "default") {
+        param.set("icon", g_cur_map_explain);
+    }
+    history.replaceState(null, null, location.pathname + "#" + param.toString());
 }
 
 function changeItemFilter(e) {
@@ -667,37 +964,6 @@ function autoSearch() {
     loadTocAndDoSearch();
 }
 
-function startDragMap(e) {
-    const container = e.currentTarget;
-    g_map_pos = {
-        // The current scroll
-        left: container.scrollLeft,
-        top: container.scrollTop,
-        // Get the current mouse position
-        x: e.clientX,
-        y: e.clientY,
-        container
-    };
-
-    document.addEventListener('mousemove', dragMap);
-    document.addEventListener('mouseup', stopDragMap);
-}
-
-function dragMap(e) {
-    // How far the mouse has been moved
-    const dx = e.clientX - g_map_pos.x;
-    const dy = e.clientY - g_map_pos.y;
-
-    // Scroll the element
-    g_map_pos.container.scrollTop = g_map_pos.top - dy;
-    g_map_pos.container.scrollLeft = g_map_pos.left - dx;
-}
-
-function stopDragMap() {
-    document.removeEventListener('mousemove', dragMap);
-    document.removeEventListener('mouseup', stopDragMap);
-}
-
 function removePrefix(s, prefix) {
     if (!s.startsWith(prefix)) {
         console.error(`String "${s}" doesn't have prefix "${prefix}"`);
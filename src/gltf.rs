@@ -0,0 +1,172 @@
+//! Minimal writer for binary glTF (GLB) files with untextured triangle meshes.
+
+use anyhow::{Context, Result};
+use nalgebra_glm::*;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+const GLB_MAGIC: u32 = 0x46546C67; // "glTF"
+const CHUNK_JSON: u32 = 0x4E4F534A;
+const CHUNK_BIN: u32 = 0x004E4942;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+pub struct GltfMesh {
+    pub name: String,
+    pub positions: Vec<Vec3>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+pub struct GltfNode {
+    pub name: String,
+    /// Index into `Gltf::meshes`. Nodes without mesh are markers.
+    pub mesh: Option<usize>,
+    pub transform: Mat4x4,
+    pub extras: Option<Value>,
+    /// Indices into `Gltf::nodes`. Nodes that are nobody's child are placed in the scene.
+    pub children: Vec<usize>,
+}
+
+#[derive(Default)]
+pub struct Gltf {
+    pub meshes: Vec<GltfMesh>,
+    pub nodes: Vec<GltfNode>,
+}
+
+impl Gltf {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut bin: Vec<u8> = vec![];
+        let mut buffer_views = vec![];
+        let mut accessors = vec![];
+        let mut meshes = vec![];
+
+        for mesh in &self.meshes {
+            let min = mesh
+                .positions
+                .iter()
+                .fold(vec3(f32::MAX, f32::MAX, f32::MAX), |a, p| min2(&a, p));
+            let max = mesh
+                .positions
+                .iter()
+                .fold(vec3(f32::MIN, f32::MIN, f32::MIN), |a, p| max2(&a, p));
+
+            let position_offset = bin.len();
+            for p in &mesh.positions {
+                for v in p.iter() {
+                    bin.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": position_offset,
+                "byteLength": bin.len() - position_offset,
+                "target": ARRAY_BUFFER,
+            }));
+            accessors.push(json!({
+                "bufferView": buffer_views.len() - 1,
+                "componentType": FLOAT,
+                "count": mesh.positions.len(),
+                "type": "VEC3",
+                "min": [min.x, min.y, min.z],
+                "max": [max.x, max.y, max.z],
+            }));
+
+            let index_offset = bin.len();
+            for i in mesh.triangles.iter().flatten() {
+                bin.extend_from_slice(&i.to_le_bytes());
+            }
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": index_offset,
+                "byteLength": bin.len() - index_offset,
+                "target": ELEMENT_ARRAY_BUFFER,
+            }));
+            accessors.push(json!({
+                "bufferView": buffer_views.len() - 1,
+                "componentType": UNSIGNED_INT,
+                "count": mesh.triangles.len() * 3,
+                "type": "SCALAR",
+            }));
+
+            meshes.push(json!({
+                "name": mesh.name,
+                "primitives": [{
+                    "attributes": { "POSITION": accessors.len() - 2 },
+                    "indices": accessors.len() - 1,
+                }],
+            }));
+        }
+
+        let nodes: Vec<Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let mut value = json!({
+                    "name": node.name,
+                    // column-major, same as nalgebra
+                    "matrix": node.transform.as_slice(),
+                });
+                if let Some(mesh) = node.mesh {
+                    value["mesh"] = json!(mesh);
+                }
+                if let Some(extras) = &node.extras {
+                    value["extras"] = extras.clone();
+                }
+                if !node.children.is_empty() {
+                    value["children"] = json!(node.children);
+                }
+                value
+            })
+            .collect();
+
+        let mut is_child = vec![false; nodes.len()];
+        for &child in self.nodes.iter().flat_map(|node| &node.children) {
+            *is_child.get_mut(child).context("Child node out of bound")? = true;
+        }
+        let roots: Vec<usize> = (0..nodes.len()).filter(|&i| !is_child[i]).collect();
+
+        let mut document = json!({
+            "asset": { "version": "2.0", "generator": "mhrice" },
+            "scene": 0,
+            "scenes": [{ "nodes": roots }],
+            "nodes": nodes,
+        });
+        if !meshes.is_empty() {
+            document["meshes"] = json!(meshes);
+            document["accessors"] = json!(accessors);
+            document["bufferViews"] = json!(buffer_views);
+            document["buffers"] = json!([{ "byteLength": bin.len() }]);
+        }
+
+        let mut json = serde_json::to_vec(&document)?;
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+
+        let has_bin = !meshes.is_empty();
+        let total_len = 12 + 8 + json.len() + if has_bin { 8 + bin.len() } else { 0 };
+        let total_len = u32::try_from(total_len).context("glTF file too large")?;
+
+        let mut file = File::create(path)?;
+        file.write_all(&GLB_MAGIC.to_le_bytes())?;
+        file.write_all(&2u32.to_le_bytes())?;
+        file.write_all(&total_len.to_le_bytes())?;
+        file.write_all(&(json.len() as u32).to_le_bytes())?;
+        file.write_all(&CHUNK_JSON.to_le_bytes())?;
+        file.write_all(&json)?;
+        if has_bin {
+            file.write_all(&(bin.len() as u32).to_le_bytes())?;
+            file.write_all(&CHUNK_BIN.to_le_bytes())?;
+            file.write_all(&bin)?;
+        }
+        Ok(())
+    }
+}
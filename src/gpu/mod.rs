@@ -155,6 +155,49 @@ impl RgbaImage {
         })
     }
 
+    /// Resamples the image with bilinear filtering.
+    /// Halving the size averages each 2x2 block of pixels.
+    pub fn resize(&self, width: u32, height: u32) -> RgbaImage {
+        if width == self.width && height == self.height {
+            return RgbaImage {
+                width,
+                height,
+                data: self.data.clone(),
+            };
+        }
+
+        let sample = |x: u32, y: u32, c: usize| -> f32 {
+            let pos = usize::try_from(x + y * self.width).unwrap() * 4 + c;
+            f32::from(self.data[pos])
+        };
+        let mut data = vec![0; usize::try_from(width * height * 4).unwrap()];
+        for y in 0..height {
+            let sy = ((y as f32 + 0.5) * self.height as f32 / height as f32 - 0.5)
+                .clamp(0.0, (self.height - 1) as f32);
+            let y0 = sy.floor() as u32;
+            let y1 = (y0 + 1).min(self.height - 1);
+            let fy = sy - y0 as f32;
+            for x in 0..width {
+                let sx = ((x as f32 + 0.5) * self.width as f32 / width as f32 - 0.5)
+                    .clamp(0.0, (self.width - 1) as f32);
+                let x0 = sx.floor() as u32;
+                let x1 = (x0 + 1).min(self.width - 1);
+                let fx = sx - x0 as f32;
+                let dst = usize::try_from(x + y * width).unwrap() * 4;
+                for c in 0..4 {
+                    let top = sample(x0, y0, c) * (1.0 - fx) + sample(x1, y0, c) * fx;
+                    let bottom = sample(x0, y1, c) * (1.0 - fx) + sample(x1, y1, c) * fx;
+                    data[dst + c] = (top * (1.0 - fy) + bottom * fy).round() as u8;
+                }
+            }
+        }
+        RgbaImage {
+            width,
+            height,
+            data,
+        }
+    }
+
     pub fn gen_double_mask(mut self) -> (RgbaImage, RgbaImage) {
         let width = self.width;
         let height = self.height;
@@ -2,6 +2,7 @@
 
 use anyhow::{anyhow, bail, Context, Result};
 use clap::*;
+use memmap2::Mmap;
 use minidump::*;
 use rayon::prelude::*;
 use std::collections::BTreeMap;
@@ -9,16 +10,17 @@ use std::convert::TryFrom;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
 use std::path::*;
-use std::sync::Mutex;
 
 mod align;
 mod bitfield;
 mod collada;
 mod extract;
 mod file_ext;
+mod gltf;
 mod gpu;
 mod gui;
 mod hash;
+mod mcol;
 mod mesh;
 mod msg;
 mod pak;
@@ -32,6 +34,8 @@ mod tdb;
 mod tex;
 mod user;
 mod uvs;
+mod vfs;
+mod xref;
 
 use extract::hash_store::*;
 use extract::logger::*;
@@ -39,6 +43,7 @@ use extract::sink::*;
 use file_ext::*;
 use gui::*;
 use mesh::*;
+use msg::translation::*;
 use msg::*;
 use pak::*;
 use pfb::*;
@@ -47,6 +52,7 @@ use scn::*;
 use tex::*;
 use user::*;
 use uvs::*;
+use xref::*;
 
 pub mod built_info {
     // The file has been placed there by the build script.
@@ -72,6 +78,11 @@ pub struct TdbOptions {
     #[clap(short, long)]
     pub cs: Option<String>,
 
+    /// Optional output to a C header with packed structs, enums, vtables and method prototypes.
+    /// The C# options below also apply to it.
+    #[clap(long)]
+    pub c_header: Option<String>,
+
     /// C#: Remove runtime addresses.
     #[clap(long)]
     pub no_runtime: bool,
@@ -130,6 +141,9 @@ enum Mhrice {
         /// Print all gathered CRC instead of mismatched ones
         #[clap(short, long)]
         crc: bool,
+        /// File name lists to name files in error messages
+        #[clap(short, long)]
+        list: Vec<String>,
     },
 
     /// Generate JSON file of game information from the PAK file
@@ -168,11 +182,100 @@ enum Mhrice {
         options: TdbOptions,
     },
 
+    /// Generate rsz_struct!/rsz_enum! definitions for types and their dependencies from TDB
+    GenRszCode {
+        /// Path to a TDB file, or a binary that contains one
+        #[clap(short, long, conflicts_with = "dmp")]
+        tdb: Option<String>,
+        /// Path to a full minidump that contains TDB
+        #[clap(short, long)]
+        dmp: Option<String>,
+        /// Full names of the types to generate, e.g. snow.data.AwardUserData
+        #[clap(short = 'n', long = "type")]
+        types: Vec<String>,
+        /// Tag the current CRC of each type with this version, e.g. 150000
+        #[clap(short, long)]
+        version: Option<u32>,
+        /// Output Rust source file
+        #[clap(short, long)]
+        output: String,
+    },
+
+    /// Interactively query types, fields and methods in TDB
+    TdbShell {
+        /// Path to a TDB file, or a binary that contains one
+        #[clap(short, long, conflicts_with = "dmp")]
+        tdb: Option<String>,
+        /// Path to a full minidump that contains TDB
+        #[clap(short, long)]
+        dmp: Option<String>,
+    },
+
+    /// Compare RSZ layouts between two TDB files and propose version-gated rsz_struct! definitions
+    MigrateRsz {
+        /// Path to the TDB file of the old title update
+        #[clap(long)]
+        old: String,
+        /// Path to the TDB file of the new title update
+        #[clap(long)]
+        new: String,
+        /// Version number of the old title update, e.g. 130000
+        #[clap(long)]
+        old_version: u32,
+        /// Version number of the new title update, e.g. 150000
+        #[clap(long)]
+        new_version: u32,
+        /// Full names of the types to compare. Compare all types with changed CRC if not specified
+        #[clap(short = 'n', long = "type")]
+        types: Vec<String>,
+        /// Output Rust source file
+        #[clap(short, long)]
+        output: String,
+    },
+
     /// Print messages from a MSG file
     ReadMsg {
         /// Path to the MSG file
         #[clap(short, long)]
         msg: String,
+        /// Output format
+        #[clap(short, long, value_enum, default_value_t = MsgPrintFormat::Ansi)]
+        format: MsgPrintFormat,
+        /// Only print this language index. Print all languages if not specified
+        #[clap(short, long)]
+        language: Option<usize>,
+    },
+
+    /// Export text of a MSG file to translation files, one per language
+    ExportMsg {
+        /// Path to the MSG file
+        #[clap(short, long)]
+        msg: String,
+        /// Output directory
+        #[clap(short, long)]
+        output: String,
+        /// Translation file format
+        #[clap(short, long, value_enum, default_value_t = TranslationFormat::Po)]
+        format: TranslationFormat,
+        /// Index of the language used as the source text. The default is English
+        #[clap(short, long, default_value_t = 1)]
+        source: usize,
+    },
+
+    /// Import a translation file (PO or XLIFF) into a MSG file for one language
+    ImportMsg {
+        /// Path to the original MSG file
+        #[clap(short, long)]
+        msg: String,
+        /// Path to the translation file. The format is detected from the extension
+        #[clap(short, long)]
+        input: String,
+        /// Index of the language to write the translation to
+        #[clap(short, long)]
+        language: usize,
+        /// Output MSG file
+        #[clap(short, long)]
+        output: String,
     },
 
     /// Scan the PAK file and output messages from all MSG files
@@ -183,6 +286,9 @@ enum Mhrice {
         /// Output directory
         #[clap(short, long)]
         output: String,
+        /// File name lists to name the output files by their paths
+        #[clap(short, long)]
+        list: Vec<String>,
     },
 
     /// Scan the PAK file and find a regex pattern in MSG files
@@ -192,6 +298,12 @@ enum Mhrice {
         pak: Vec<String>,
         /// The regex pattern
         pattern: String,
+        /// Output format for matched messages
+        #[clap(short, long, value_enum, default_value_t = MsgPrintFormat::Ansi)]
+        format: MsgPrintFormat,
+        /// Optional file name lists for naming the matched files
+        #[clap(long)]
+        list: Vec<String>,
     },
 
     /// Scan the PAK file and find a regex pattern in all files
@@ -202,10 +314,39 @@ enum Mhrice {
         /// Search for UTF-16 string
         #[clap(short, long)]
         utf16: bool,
+        /// Optional file name lists for naming the matched files
+        #[clap(long)]
+        list: Vec<String>,
         /// The regex pattern
         pattern: String,
     },
 
+    /// Scan the PAK file and build an index of references between files
+    ///
+    /// This records extern slots and resource paths from USER/SCN/PFB/RCOL files,
+    /// texture paths from GUI files, material names from MESH files,
+    /// and MSG GUIDs found in RSZ data.
+    BuildXref {
+        /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
+        #[clap(short, long)]
+        pak: Vec<String>,
+        /// Output index file
+        #[clap(short, long)]
+        output: String,
+        /// File name lists to record referencing files by path
+        #[clap(short, long)]
+        list: Vec<String>,
+    },
+
+    /// Find files that reference a path, a MSG GUID / entry name, or a material name
+    QueryXref {
+        /// Index file generated by build-xref
+        #[clap(short, long)]
+        index: String,
+        /// Text to search for in the referenced targets. Case insensitive
+        target: String,
+    },
+
     /// Scan the PAK file as well as optionally full minidump samples
     /// and print all potential sub-file names
     SearchPath {
@@ -216,6 +357,68 @@ enum Mhrice {
         /// Path to the full minidump files
         #[clap(short, long)]
         dmp: Vec<String>,
+
+        /// Known file name lists. Paths in them are not exported to new-output
+        #[clap(short, long)]
+        list: Vec<String>,
+
+        /// Optional output of newly discovered paths that exist in the PAK
+        #[clap(short, long)]
+        new_output: Option<String>,
+    },
+
+    /// Load and merge file name lists, and report how many files in the PAK are named
+    PathDict {
+        /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
+        #[clap(short, long)]
+        pak: Vec<String>,
+        /// File name lists, can be the output from search-path command
+        #[clap(short, long)]
+        list: Vec<String>,
+        /// Optional output of the merged list
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Verify every entry in the PAK files and report which entries are overridden by patches
+    VerifyPak {
+        /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
+        #[clap(short, long)]
+        pak: Vec<String>,
+        /// File name lists to name problematic entries
+        #[clap(short, long)]
+        list: Vec<String>,
+    },
+
+    /// List files present in multiple PAK files, and summarize what each PAK adds or overrides
+    PakLayers {
+        /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
+        #[clap(short, long)]
+        pak: Vec<String>,
+        /// File name lists, used for names and grouping by extension
+        #[clap(short, long)]
+        list: Vec<String>,
+        /// Compare the content of overridden files. This reads all of them
+        #[clap(short, long)]
+        diff: bool,
+        /// Optional output of all files present in multiple PAK files
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Mount PAK files as a read-only filesystem. Requires the "fuse" feature on Linux
+    ///
+    /// Named files appear at their full path, and others under _unknown/
+    MountPak {
+        /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
+        #[clap(short, long)]
+        pak: Vec<String>,
+        /// File name lists, can be the output from search-path command
+        #[clap(short, long)]
+        list: Vec<String>,
+        /// Empty directory to mount on
+        #[clap(short, long)]
+        mountpoint: String,
     },
 
     /// Dump all sub-files from the PAK file
@@ -223,9 +426,9 @@ enum Mhrice {
         /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
         #[clap(short, long)]
         pak: Vec<String>,
-        /// File name list, can be the output from search-path command
+        /// File name lists, can be the output from search-path command
         #[clap(short, long)]
-        list: String,
+        list: Vec<String>,
         /// Output directory
         #[clap(short, long)]
         output: String,
@@ -243,6 +446,9 @@ enum Mhrice {
         /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
         #[clap(short, long)]
         pak: Vec<String>,
+        /// File name lists to name files in error messages
+        #[clap(short, long)]
+        list: Vec<String>,
     },
 
     /// Scan the PAK file and verify the format of all GUI files
@@ -328,6 +534,35 @@ enum Mhrice {
         output: String,
     },
 
+    /// Export the render geometry, collision meshes and pops of a map stage to a binary glTF file
+    DumpStage {
+        /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
+        #[clap(short, long)]
+        pak: Vec<String>,
+        /// Map number
+        #[clap(short, long)]
+        map: i32,
+        /// Output GLB file
+        #[clap(short, long)]
+        output: String,
+        /// File name lists to find other scenes in the folder of the map scene
+        #[clap(short, long)]
+        list: Vec<String>,
+    },
+
+    /// Plan a route collecting spiribirds on a map until the petalace stat limits are reached
+    SpiribirdRoute {
+        /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
+        #[clap(short, long)]
+        pak: Vec<String>,
+        /// Map number
+        #[clap(short, long)]
+        map: i32,
+        /// Petalace ID, as in LvC_Normal_XXX
+        #[clap(long)]
+        petalace: u32,
+    },
+
     /// Generate resource files (images etc.) for the website
     GenResources {
         /// Paths to the PAK files, folder containing PAK files, or a .txt file listing all PAK files
@@ -396,6 +631,39 @@ enum Mhrice {
         name: String,
     },
 
+    /// Dump the RSZ field layout of all runtime types in a full minidump to a JSON schema
+    DumpRszSchema {
+        /// Path to the full minidump (DMP file)
+        #[clap(short, long)]
+        dmp: String,
+        /// Optional memory address where TDB is allocated
+        ///
+        /// Specify this to skip search the entire minidump
+        #[clap(short, long)]
+        address: Option<String>,
+        /// Output JSON file
+        #[clap(short, long)]
+        output: String,
+    },
+
+    /// Decode a managed object in a full minidump and print its fields recursively
+    InspectObject {
+        /// Path to the full minidump (DMP file)
+        #[clap(short, long)]
+        dmp: String,
+        /// Address of the object in hex
+        #[clap(long)]
+        object: String,
+        /// Maximum depth of referenced objects to print
+        #[clap(long, default_value_t = 3)]
+        depth: usize,
+        /// Optional memory address where TDB is allocated
+        ///
+        /// Specify this to skip search the entire minidump
+        #[clap(short, long)]
+        address: Option<String>,
+    },
+
     /// Print runtime information of a type
     TypeInfo {
         /// Path to the full minidump (DMP file)
@@ -427,7 +695,7 @@ enum Mhrice {
     },
 }
 
-fn open_pak_files(mut pak: Vec<String>) -> Result<Vec<File>> {
+fn open_pak_files(mut pak: Vec<String>) -> Result<Vec<Mmap>> {
     if pak.len() == 1 && Path::new(&pak[0]).is_dir() {
         eprintln!("Listing all PAK files in the folder...");
         let dir = pak.pop().unwrap();
@@ -463,11 +731,17 @@ fn open_pak_files(mut pak: Vec<String>) -> Result<Vec<File>> {
         }
     }
 
-    pak.into_iter().map(|path| Ok(File::open(path)?)).collect()
+    pak.into_iter()
+        .map(|path| {
+            let file = File::open(&path).with_context(|| format!("Cannot open {path}"))?;
+            // Safety: PAK files must not be modified while mapped
+            Ok(unsafe { Mmap::map(&file)? })
+        })
+        .collect()
 }
 
 fn dump(pak: Vec<String>, name: String, output: String) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    let pak = PakReader::new(open_pak_files(pak)?)?;
     let index = pak.find_file(&name).context("Cannot find subfile")?;
     println!("Index {index:?}");
     let content = pak.read_file(index)?;
@@ -476,7 +750,7 @@ fn dump(pak: Vec<String>, name: String, output: String) -> Result<()> {
 }
 
 fn dump_index(pak: Vec<String>, version: usize, index: usize, output: String) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    let pak = PakReader::new(open_pak_files(pak)?)?;
     let content = pak.read_file_at(version, index)?;
     std::fs::write(output, content)?;
     Ok(())
@@ -509,40 +783,51 @@ fn visit_tree(nodes: &mut [TreeNode], current: usize, depth: i32) {
     nodes[current].visited = true;
 }*/
 
-fn scan_rsz(pak: Vec<String>, print_all: bool) -> Result<()> {
+fn scan_rsz(pak: Vec<String>, print_all: bool, list: Vec<String>) -> Result<()> {
     let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+
+    let file_mismatches = pak
+        .all_file_indexs()
+        .into_par_iter()
+        .map(|index| {
+            let mut crc_mismatches = BTreeMap::new();
+            let name = pak.display_name(index);
+            let content = pak
+                .read_file(index)
+                .context(format!("Failed to open file at {name}"))?;
+            if content.len() < 4 {
+                return Ok(crc_mismatches);
+            }
 
-    let mut crc_mismatches = BTreeMap::new();
-
-    for index in pak.all_file_indexs() {
-        let content = pak
-            .read_file(index)
-            .context(format!("Failed to open file at {index:?}"))?;
-        if content.len() < 4 {
-            continue;
-        }
+            if &content[0..3] == b"USR" {
+                User::new(Cursor::new(&content))
+                    .context(format!("Failed to open USER at {name}"))?
+                    .rsz
+                    .verify_crc(&mut crc_mismatches, print_all);
+            } else if &content[0..3] == b"PFB" {
+                Pfb::new(Cursor::new(&content))
+                    .context(format!("Failed to open PFB at {name}"))?
+                    .rsz
+                    .verify_crc(&mut crc_mismatches, print_all);
+            } else if &content[0..3] == b"SCN" {
+                Scn::new(Cursor::new(&content))
+                    .context(format!("Failed to open SCN at {name}"))?
+                    .rsz
+                    .verify_crc(&mut crc_mismatches, print_all);
+            } else if &content[0..4] == b"RCOL" {
+                Rcol::new(Cursor::new(&content), false)
+                    .context(format!("Failed to open RCOL at {name}"))?
+                    .rsz
+                    .verify_crc(&mut crc_mismatches, print_all);
+            }
+            Ok(crc_mismatches)
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-        if &content[0..3] == b"USR" {
-            User::new(Cursor::new(&content))
-                .context(format!("Failed to open USER at {index:?}"))?
-                .rsz
-                .verify_crc(&mut crc_mismatches, print_all);
-        } else if &content[0..3] == b"PFB" {
-            Pfb::new(Cursor::new(&content))
-                .context(format!("Failed to open PFB at {index:?}"))?
-                .rsz
-                .verify_crc(&mut crc_mismatches, print_all);
-        } else if &content[0..3] == b"SCN" {
-            Scn::new(Cursor::new(&content))
-                .context(format!("Failed to open SCN at {index:?}"))?
-                .rsz
-                .verify_crc(&mut crc_mismatches, print_all);
-        } else if &content[0..4] == b"RCOL" {
-            Rcol::new(Cursor::new(&content), false)
-                .context(format!("Failed to open RCOL at {index:?}"))?
-                .rsz
-                .verify_crc(&mut crc_mismatches, print_all);
-        }
+    let mut crc_mismatches = BTreeMap::new();
+    for mismatches in file_mismatches {
+        crc_mismatches.extend(mismatches);
     }
 
     for (symbol, crc) in crc_mismatches {
@@ -553,10 +838,10 @@ fn scan_rsz(pak: Vec<String>, print_all: bool) -> Result<()> {
 }
 
 fn gen_json(pak: Vec<String>, sha: bool) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    let pak = PakReader::new(open_pak_files(pak)?)?;
     let mut logger_root = LoggerRoot::new();
     let logger = &mut logger_root.logger();
-    let pedia = extract::gen_pedia(&mut pak, sha, logger)?;
+    let pedia = extract::gen_pedia(&pak, sha, logger)?;
     let json = serde_json::to_string_pretty(&pedia)?;
     println!("{json}");
     Ok(())
@@ -568,17 +853,17 @@ fn gen_website_to_sink(
     config: extract::WebsiteConfig,
     sha: bool,
 ) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    let pak = PakReader::new(open_pak_files(pak)?)?;
     let mut logger_root = LoggerRoot::new();
     {
         let logger = &mut logger_root.logger();
-        let pedia = extract::gen_pedia(&mut pak, sha, logger)?;
+        let pedia = extract::gen_pedia(&pak, sha, logger)?;
         let pedia_ex = extract::gen_pedia_ex(&pedia, logger)?;
         sink.create("mhrice.json")?
             .write_all(serde_json::to_string_pretty(&pedia)?.as_bytes())?;
         let mut hash_store = HashStore::new();
         extract::gen_website(&mut hash_store, &pedia, &pedia_ex, &config, &sink)?;
-        extract::gen_resources(&mut pak, &sink.sub_sink("resources")?, logger)?;
+        extract::gen_resources(&pak, &sink.sub_sink("resources")?, logger)?;
     }
 
     let mut log = sink.create("log.html")?;
@@ -640,7 +925,16 @@ impl<F: Seek> Seek for OffsetFile<F> {
     }
 }
 
-fn read_tdb(tdb: String, options: TdbOptions) -> Result<()> {
+impl TdbOptions {
+    fn has_output(&self) -> bool {
+        self.json.is_some()
+            || self.json_split.is_some()
+            || self.cs.is_some()
+            || self.c_header.is_some()
+    }
+}
+
+fn load_tdb(tdb: String) -> Result<tdb::Tdb> {
     let mut file = BufReader::new(File::open(tdb)?);
     let offset = loop {
         let mut magic = vec![0; TDB_ANCHOR.len()];
@@ -652,8 +946,15 @@ fn read_tdb(tdb: String, options: TdbOptions) -> Result<()> {
         }
     };
 
-    tdb::print(OffsetFile::new(file, offset)?, 0, options)?;
-    Ok(())
+    tdb::Tdb::new(OffsetFile::new(file, offset)?, 0)
+}
+
+fn read_tdb(tdb: String, options: TdbOptions) -> Result<()> {
+    if !options.has_output() {
+        eprintln!("Please specify at least one of --json, --json-split, --cs, --c-header");
+        return Ok(());
+    }
+    tdb::print(&load_tdb(tdb)?, options)
 }
 
 struct MinidumpReader<'a> {
@@ -705,24 +1006,15 @@ impl<'a> Seek for MinidumpReader<'a> {
     }
 }
 
-fn read_dmp_tdb(dmp: String, address: Option<String>, options: TdbOptions) -> Result<()> {
-    let dmp = Minidump::read_path(dmp).map_err(|e| anyhow!(e))?;
-    let memory = dmp
-        .get_stream::<MinidumpMemory64List>()
-        .map_err(|e| anyhow!(e))
-        .context("No full dump memory found")?;
-
+fn find_dmp_tdb(memory: &MinidumpMemory64List, address: Option<String>) -> Result<tdb::Tdb> {
     if let Some(address) = address {
         let base = if let Some(hex) = address.strip_prefix("0x") {
             u64::from_str_radix(hex, 16)?
         } else {
             address.parse()?
         };
-        let file = MinidumpReader::new(&memory);
-
-        tdb::print(file, base, options)?;
-
-        return Ok(());
+        let file = MinidumpReader::new(memory);
+        return tdb::Tdb::new(file, base);
     }
 
     for block in memory.iter() {
@@ -733,15 +1025,110 @@ fn read_dmp_tdb(dmp: String, address: Option<String>, options: TdbOptions) -> Re
         {
             let base = block.base_address + u64::try_from(pos)?;
             eprintln!("Found at address 0x{base:016X}");
-            let file = MinidumpReader::new(&memory);
+            let file = MinidumpReader::new(memory);
+            return tdb::Tdb::new(file, base);
+        }
+    }
 
-            tdb::print(file, base, options)?;
+    bail!("TDB not found")
+}
 
-            break;
-        }
+fn load_dmp_tdb(dmp: String, address: Option<String>) -> Result<tdb::Tdb> {
+    let dmp = Minidump::read_path(dmp).map_err(|e| anyhow!(e))?;
+    let memory = dmp
+        .get_stream::<MinidumpMemory64List>()
+        .map_err(|e| anyhow!(e))
+        .context("No full dump memory found")?;
+    find_dmp_tdb(&memory, address)
+}
+
+fn read_dmp_tdb(dmp: String, address: Option<String>, options: TdbOptions) -> Result<()> {
+    if !options.has_output() {
+        eprintln!("Please specify at least one of --json, --json-split, --cs, --c-header");
+        return Ok(());
     }
+    tdb::print(&load_dmp_tdb(dmp, address)?, options)
+}
 
-    Ok(())
+fn gen_rsz_code(
+    tdb: Option<String>,
+    dmp: Option<String>,
+    types: Vec<String>,
+    version: Option<u32>,
+    output: String,
+) -> Result<()> {
+    let tdb = match (tdb, dmp) {
+        (Some(tdb), _) => load_tdb(tdb)?,
+        (None, Some(dmp)) => load_dmp_tdb(dmp, None)?,
+        (None, None) => bail!("Please specify either --tdb or --dmp"),
+    };
+    tdb.write_rsz_code(
+        std::io::BufWriter::new(File::create(output)?),
+        &types,
+        version,
+    )
+}
+
+fn tdb_shell(tdb: Option<String>, dmp: Option<String>) -> Result<()> {
+    let tdb = match (tdb, dmp) {
+        (Some(tdb), _) => load_tdb(tdb)?,
+        (None, Some(dmp)) => load_dmp_tdb(dmp, None)?,
+        (None, None) => bail!("Please specify either --tdb or --dmp"),
+    };
+    tdb.shell(std::io::stdin().lock(), std::io::stdout().lock())
+}
+
+fn migrate_rsz(
+    old: String,
+    new: String,
+    old_version: u32,
+    new_version: u32,
+    types: Vec<String>,
+    output: String,
+) -> Result<()> {
+    let old = load_tdb(old)?;
+    let new = load_tdb(new)?;
+    new.write_rsz_migration(
+        &old,
+        std::io::BufWriter::new(File::create(output)?),
+        &types,
+        old_version,
+        new_version,
+    )
+}
+
+fn dump_rsz_schema(dmp: String, address: Option<String>, output: String) -> Result<()> {
+    let dmp = Minidump::read_path(dmp).map_err(|e| anyhow!(e))?;
+    let memory = dmp
+        .get_stream::<MinidumpMemory64List>()
+        .map_err(|e| anyhow!(e))
+        .context("No full dump memory found")?;
+    let tdb = find_dmp_tdb(&memory, address)?;
+    tdb.write_rsz_schema(
+        MinidumpReader::new(&memory),
+        std::io::BufWriter::new(File::create(output)?),
+    )
+}
+
+fn inspect_object(
+    dmp: String,
+    object: String,
+    depth: usize,
+    address: Option<String>,
+) -> Result<()> {
+    let object = u64::from_str_radix(object.trim_start_matches("0x"), 16)?;
+    let dmp = Minidump::read_path(dmp).map_err(|e| anyhow!(e))?;
+    let memory = dmp
+        .get_stream::<MinidumpMemory64List>()
+        .map_err(|e| anyhow!(e))
+        .context("No full dump memory found")?;
+    let tdb = find_dmp_tdb(&memory, address)?;
+    tdb.inspect_object(
+        MinidumpReader::new(&memory),
+        object,
+        depth,
+        std::io::stdout().lock(),
+    )
 }
 
 fn type_info(dmp: String, hash: String, crc: String) -> Result<()> {
@@ -799,34 +1186,134 @@ fn type_info(dmp: String, hash: String, crc: String) -> Result<()> {
     Ok(())
 }
 
-fn read_msg(msg: String) -> Result<()> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum MsgPrintFormat {
+    Json,
+    Markdown,
+    Ansi,
+}
+
+fn print_msg_entry(
+    msg: &Msg,
+    entry: &MsgEntry,
+    format: MsgPrintFormat,
+    language: impl Fn(usize) -> bool,
+) {
+    let reference = |name: &str| msg.get_entry(name);
+    match format {
+        MsgPrintFormat::Markdown => println!("### {}\n", entry.name),
+        _ => println!("{} ({})", entry.name, String::from(entry.guid)),
+    }
+    for (i, content) in entry.content.iter().enumerate() {
+        if content.is_empty() || !language(i) {
+            continue;
+        }
+        let markup = markup::Markup::parse(content);
+        let code = extract::LANGUAGE_MAP
+            .get(i)
+            .copied()
+            .flatten()
+            .map_or_else(|| i.to_string(), |(_, code)| code.to_owned());
+        match format {
+            MsgPrintFormat::Markdown => {
+                println!("- **{code}**: {}", markup.to_markdown(i, reference))
+            }
+            _ => println!("    [{code}] {}", markup.to_ansi(i, reference)),
+        }
+    }
+    println!();
+}
+
+fn read_msg(msg: String, format: MsgPrintFormat, language: Option<usize>) -> Result<()> {
     let msg = Msg::new(File::open(msg)?)?;
-    println!("{}", serde_json::to_string_pretty(&msg)?);
+    if format == MsgPrintFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&msg)?);
+        return Ok(());
+    }
+    for entry in &msg.entries {
+        print_msg_entry(&msg, entry, format, |i| language.map_or(true, |l| l == i));
+    }
     Ok(())
 }
 
-fn scan_msg(pak: Vec<String>, output: String) -> Result<()> {
+fn export_msg(msg: String, output: String, format: TranslationFormat, source: usize) -> Result<()> {
+    let msg_path = PathBuf::from(msg);
+    let msg = Msg::new(File::open(&msg_path)?)?;
+    let stem = msg_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Bad path")?;
+    let stem = stem.split('.').next().unwrap_or(stem);
+    let language_count = msg.language_count;
+    let language_code = |i: usize| {
+        extract::LANGUAGE_MAP
+            .get(i)
+            .copied()
+            .flatten()
+            .map(|(_, code)| code)
+    };
+
+    std::fs::create_dir_all(&output)?;
+    for language in 0..language_count {
+        let target = TranslationTarget {
+            source,
+            target: language,
+            source_code: language_code(source),
+            target_code: language_code(language),
+        };
+        let file_name = if let Some(code) = target.target_code {
+            format!("{stem}.{language:02}.{code}.{}", format.extension())
+        } else {
+            format!("{stem}.{language:02}.{}", format.extension())
+        };
+        let file = File::create(PathBuf::from(&output).join(file_name))?;
+        write_translation(&msg, format, &target, std::io::BufWriter::new(file))?;
+    }
+    Ok(())
+}
+
+fn import_msg(msg: String, input: String, language: usize, output: String) -> Result<()> {
+    let mut msg = Msg::new(File::open(msg)?)?;
+    let format = TranslationFormat::from_path(&input)?;
+    let translation = read_translation(format, &std::fs::read_to_string(&input)?)?;
+    let count = apply_translation(&mut msg, language, &translation)?;
+    eprintln!("Updated {count} entries");
+    std::fs::write(output, msg.to_bytes()?)?;
+    Ok(())
+}
+
+fn scan_msg(pak: Vec<String>, output: String, list: Vec<String>) -> Result<()> {
     let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
     std::fs::create_dir_all(&output)?;
     for i in pak.all_file_indexs() {
         let file = pak.read_file(i)?;
         if file.len() < 8 || file[4..8] != b"GMSG"[..] {
             continue;
         }
-        let msg = Msg::new(Cursor::new(&file)).context(format!("at {i:?}"))?;
+        let name = pak.display_name(i);
+        let msg = Msg::new(Cursor::new(&file)).context(format!("at {name}"))?;
         for e in &msg.entries {
             extract::gen_multi_lang(e);
         }
-        std::fs::write(
-            PathBuf::from(&output).join(format!("{}.txt", i.short_string())),
-            serde_json::to_string_pretty(&msg)?,
-        )?;
+        // Named files keep their folders under the output directory
+        let path = PathBuf::from(&output).join(format!("{name}.txt"));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&msg)?)?;
     }
     Ok(())
 }
 
-fn grep_msg(pak: Vec<String>, pattern: String) -> Result<()> {
+fn grep_msg(
+    pak: Vec<String>,
+    pattern: String,
+    format: MsgPrintFormat,
+    list: Vec<String>,
+) -> Result<()> {
     let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
     use regex::*;
     let regex = RegexBuilder::new(&pattern).build()?;
     for i in pak.all_file_indexs() {
@@ -835,11 +1322,21 @@ fn grep_msg(pak: Vec<String>, pattern: String) -> Result<()> {
             continue;
         }
         let msg = Msg::new(Cursor::new(&file)).context(format!("at {i:?}"))?;
+        let mut found = false;
         for entry in &msg.entries {
-            for text in &entry.content {
-                if regex.is_match(text) {
-                    println!("Found @ {i:?}");
-                }
+            if !entry.content.iter().any(|text| regex.is_match(text)) {
+                continue;
+            }
+            if !found {
+                println!("Found @ {}", pak.display_name(i));
+                found = true;
+            }
+            if format == MsgPrintFormat::Json {
+                println!("{}", serde_json::to_string_pretty(entry)?);
+            } else {
+                print_msg_entry(&msg, entry, format, |language| {
+                    regex.is_match(&entry.content[language])
+                });
             }
         }
     }
@@ -847,59 +1344,58 @@ fn grep_msg(pak: Vec<String>, pattern: String) -> Result<()> {
 }
 
 fn scan_mesh(pak: Vec<String>) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
-    for i in pak.all_file_indexs() {
+    let pak = PakReader::new(open_pak_files(pak)?)?;
+    pak.all_file_indexs().into_par_iter().try_for_each(|i| {
         let file = pak.read_file(i)?;
         if file.len() < 4 || file[0..4] != b"MESH"[..] {
-            continue;
+            return Ok(());
         }
         let _ = Mesh::new(Cursor::new(&file)).context(format!("at {i:?}"))?;
-    }
-    Ok(())
+        Ok(())
+    })
 }
 
-fn scan_tex(pak: Vec<String>) -> Result<()> {
+fn scan_tex(pak: Vec<String>, list: Vec<String>) -> Result<()> {
     let mut pak = PakReader::new(open_pak_files(pak)?)?;
-    for i in pak.all_file_indexs() {
+    load_path_lists(&mut pak, &list)?;
+    pak.all_file_indexs().into_par_iter().try_for_each(|i| {
         let file = pak.read_file(i)?;
         if file.len() < 4 || file[0..4] != b"TEX\0"[..] {
-            continue;
+            return Ok(());
         }
-        let _ = Tex::new(Cursor::new(&file)).context(format!("at {i:?}"))?;
-    }
-
-    Ok(())
+        let _ = Tex::new(Cursor::new(&file)).context(format!("at {}", pak.display_name(i)))?;
+        Ok(())
+    })
 }
 
 fn scan_gui(pak: Vec<String>) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
-    for i in pak.all_file_indexs() {
+    let pak = PakReader::new(open_pak_files(pak)?)?;
+    pak.all_file_indexs().into_par_iter().try_for_each(|i| {
         let file = pak.read_file(i)?;
         if file.len() < 8 || file[4..8] != b"GUIR"[..] {
-            continue;
+            return Ok(());
         }
         let _ = Gui::new(Cursor::new(&file)).context(format!("at {i:?}"))?;
-    }
-
-    Ok(())
+        Ok(())
+    })
 }
 
 fn scan_uvs(pak: Vec<String>) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
-    for i in pak.all_file_indexs() {
+    let pak = PakReader::new(open_pak_files(pak)?)?;
+    pak.all_file_indexs().into_par_iter().try_for_each(|i| {
         let file = pak.read_file(i)?;
         if file.len() < 4 || file[0..4] != b".SVU"[..] {
-            continue;
+            return Ok(());
         }
         let _ = Uvs::new(Cursor::new(&file)).context(format!("at {i:?}"))?;
-    }
-
-    Ok(())
+        Ok(())
+    })
 }
 
-fn grep(pak: Vec<String>, utf16: bool, mut pattern: String) -> Result<()> {
+fn grep(pak: Vec<String>, utf16: bool, list: Vec<String>, mut pattern: String) -> Result<()> {
     use regex::bytes::*;
     let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
     if utf16 {
         pattern = pattern
             .encode_utf16()
@@ -911,18 +1407,199 @@ fn grep(pak: Vec<String>, utf16: bool, mut pattern: String) -> Result<()> {
     }
     println!("Searching for patterns \"{}\"", &pattern);
     let re = RegexBuilder::new(&pattern).unicode(false).build()?;
-    for i in pak.all_file_indexs() {
+    pak.all_file_indexs().into_par_iter().try_for_each(|i| {
         let file = pak.read_file(i)?;
         if re.is_match(&file) {
-            println!("Matched @ {i:?}");
+            println!("Matched @ {}", pak.display_name(i));
+        }
+        Ok(())
+    })
+}
+
+fn build_xref(pak: Vec<String>, output: String, list: Vec<String>) -> Result<()> {
+    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+    let index = XrefIndex::build(&pak)?;
+    index.save(std::io::BufWriter::new(File::create(output)?))?;
+    eprintln!("Recorded {} references", index.entries.len());
+    Ok(())
+}
+
+fn query_xref(index: String, target: String) -> Result<()> {
+    let index = XrefIndex::load(BufReader::new(File::open(index)?))?;
+    for entry in index.query(&target) {
+        if entry.note.is_empty() {
+            println!("{} {} -> {}", entry.file, entry.format, entry.target);
+        } else {
+            println!(
+                "{} {} -> {} ({})",
+                entry.file, entry.format, entry.target, entry.note
+            );
         }
     }
     Ok(())
 }
 
-fn search_path(pak: Vec<String>, dmp: Vec<String>) -> Result<()> {
-    let pak = Mutex::new(PakReader::new(open_pak_files(pak)?)?);
-    let indexs = pak.lock().unwrap().all_file_indexs();
+fn load_path_lists<F: PakSource>(pak: &mut PakReader<F>, list: &[String]) -> Result<()> {
+    for list in list {
+        let count = pak
+            .load_path_list(BufReader::new(File::open(list)?))
+            .context(format!("Failed to load {list}"))?;
+        eprintln!("Named {count} files from {list}");
+    }
+    Ok(())
+}
+
+fn verify_pak(pak: Vec<String>, list: Vec<String>) -> Result<()> {
+    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+
+    let issues = pak.verify()?;
+    for issue in &issues {
+        println!("{}: {}", pak.display_name(issue.index), issue.message);
+    }
+    println!("{} problems found", issues.len());
+
+    let mut overrides: BTreeMap<(usize, usize), usize> = BTreeMap::new();
+    for (overridden, winner) in pak.overridden_entries() {
+        *overrides
+            .entry((winner.version(), overridden.version()))
+            .or_default() += 1;
+    }
+    for ((patch, base), count) in overrides {
+        println!("PAK {patch:02} overrides {count} entries in PAK {base:02}");
+    }
+
+    if !issues.is_empty() {
+        bail!("Verification failed")
+    }
+    Ok(())
+}
+
+fn pak_layers(
+    pak: Vec<String>,
+    list: Vec<String>,
+    diff: bool,
+    output: Option<String>,
+) -> Result<()> {
+    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+
+    let mut output = output
+        .map(|output| -> Result<_> { Ok(std::io::BufWriter::new(File::create(output)?)) })
+        .transpose()?;
+
+    // (version, is override, extension) -> count
+    let mut summary: BTreeMap<(usize, bool, String), usize> = BTreeMap::new();
+    for layer in pak.layers() {
+        // Only the winner is registered by name, but all entries of a layer share its path
+        let extension = pak
+            .file_name(layer.winner())
+            .and_then(|name| name.rsplit('/').next()?.split_once('.'))
+            .map_or("(unnamed)", |(_, extension)| extension)
+            .to_owned();
+        for (i, &index) in layer.entries.iter().enumerate() {
+            *summary
+                .entry((index.version(), i != 0, extension.clone()))
+                .or_default() += 1;
+        }
+
+        if layer.entries.len() < 2 {
+            continue;
+        }
+        if let Some(output) = &mut output {
+            let differs = if diff {
+                if pak.layer_differs(&layer)? {
+                    "differ"
+                } else {
+                    "same"
+                }
+            } else {
+                "-"
+            };
+            let entries: Vec<String> = layer.entries.iter().map(|e| e.short_string()).collect();
+            writeln!(
+                output,
+                "{:016X}\t{}\t{}\t{differs}",
+                layer.hash,
+                pak.display_name(layer.winner()),
+                entries.join(",")
+            )?;
+        }
+    }
+
+    let mut current = None;
+    for ((version, is_override, extension), count) in summary {
+        if current != Some(version) {
+            current = Some(version);
+            println!("PAK {version:02}:");
+        }
+        let kind = if is_override { "override" } else { "new" };
+        println!("    {kind:8} {count:8} {extension}");
+    }
+
+    Ok(())
+}
+
+fn mount_pak(pak: Vec<String>, list: Vec<String>, mountpoint: String) -> Result<()> {
+    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+    let vfs = vfs::PakVfs::new(pak)?;
+    eprintln!("{} nodes in the filesystem", vfs.node_count());
+
+    #[cfg(all(unix, feature = "fuse"))]
+    {
+        eprintln!("Mounting on {mountpoint}. Unmount it to exit");
+        vfs::fuse::mount(vfs, &mountpoint)
+    }
+
+    #[cfg(not(all(unix, feature = "fuse")))]
+    {
+        let _ = mountpoint;
+        bail!("Built without FUSE support. Rebuild with --features fuse on Linux")
+    }
+}
+
+fn path_dict(pak: Vec<String>, list: Vec<String>, output: Option<String>) -> Result<()> {
+    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+
+    let mut named = 0;
+    let mut total = 0;
+    for (i, coverage) in pak.path_coverage().into_iter().enumerate() {
+        println!(
+            "PAK {i:02}: {} / {} named ({:.2}%)",
+            coverage.named,
+            coverage.total,
+            coverage.named as f64 / coverage.total.max(1) as f64 * 100.0
+        );
+        named += coverage.named;
+        total += coverage.total;
+    }
+    println!(
+        "Total: {named} / {total} named ({:.2}%)",
+        named as f64 / total.max(1) as f64 * 100.0
+    );
+
+    if let Some(output) = output {
+        let mut output = std::io::BufWriter::new(File::create(output)?);
+        for path in pak.known_paths() {
+            writeln!(output, "{path}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn search_path(
+    pak: Vec<String>,
+    dmp: Vec<String>,
+    list: Vec<String>,
+    new_output: Option<String>,
+) -> Result<()> {
+    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+    let indexs = pak.all_file_indexs();
     let counter = std::sync::atomic::AtomicU32::new(0);
 
     let mut paths: Vec<(String, Vec<I18nPakFileIndex>)> = vec![];
@@ -973,7 +1650,7 @@ fn search_path(pak: Vec<String>, dmp: Vec<String>) -> Result<()> {
                 for pos in (begin..end).step_by(2) {
                     path.push(char::from(memory[pos]));
                 }
-                let index = pak.lock().unwrap().find_file_i18n(&path)?;
+                let index = pak.find_file_i18n(&path)?;
                 paths.push((path, index));
             }
         }
@@ -1034,7 +1711,7 @@ fn search_path(pak: Vec<String>, dmp: Vec<String>) -> Result<()> {
         indexs
             .into_par_iter()
             .map(|index| {
-                let file = pak.lock().unwrap().read_file(index)?;
+                let file = pak.read_file(index)?;
                 search_memory(&file)
             })
             .flat_map_iter(|paths: Result<_>| paths.unwrap()),
@@ -1043,46 +1720,42 @@ fn search_path(pak: Vec<String>, dmp: Vec<String>) -> Result<()> {
     paths.sort_by(|(p, _), (q, _)| p.cmp(q));
     paths.dedup_by(|(p, _), (q, _)| p == q);
 
-    for (path, index) in paths {
+    for (path, index) in &paths {
         println!("{path} $ {index:?}");
     }
 
+    if let Some(new_output) = new_output {
+        let mut new_output = std::io::BufWriter::new(File::create(new_output)?);
+        let mut count = 0;
+        for (path, index) in &paths {
+            if !index.is_empty() && !pak.is_known_path(path) {
+                writeln!(new_output, "{path}")?;
+                count += 1;
+            }
+        }
+        eprintln!("Found {count} new paths");
+    }
+
     Ok(())
 }
 
-fn dump_tree(pak: Vec<String>, list: String, output: String) -> Result<()> {
+fn dump_tree(pak: Vec<String>, list: Vec<String>, output: String) -> Result<()> {
     let mut pak = PakReader::new(open_pak_files(pak)?)?;
-    let list = File::open(list)?;
-    let mut unvisited: std::collections::HashSet<_> = pak.all_file_indexs().into_iter().collect();
-    for line in BufReader::new(list).lines() {
-        let line = line?;
-        let mut origin_path = line.split(" $ ").next().context("Empty line")?;
-        if let Some(new_path) = origin_path.strip_prefix('@') {
-            origin_path = new_path;
-        }
-
-        let streaming_path = "streaming/".to_owned() + origin_path;
-        let paths = [origin_path, &streaming_path];
-
-        for path in paths {
-            for i18n_index in pak.find_file_i18n(path)? {
-                let index = i18n_index.index;
-                let path_i18n = if i18n_index.language.is_empty() {
-                    path.to_owned()
-                } else {
-                    format!("{}.{}", path, i18n_index.language)
-                };
-
-                let mut path = PathBuf::from(&output);
-                for component in path_i18n.split('/') {
-                    path.push(component);
-                }
+    load_path_lists(&mut pak, &list)?;
+    let mut unvisited = vec![];
+    for index in pak.all_file_indexs() {
+        let Some(name) = pak.file_name(index) else {
+            unvisited.push(index);
+            continue;
+        };
 
-                std::fs::create_dir_all(path.parent().context("no parent")?)?;
-                std::fs::write(path, pak.read_file(index)?)?;
-                unvisited.remove(&index);
-            }
+        let mut path = PathBuf::from(&output);
+        for component in name.split('/') {
+            path.push(component);
         }
+
+        std::fs::create_dir_all(path.parent().context("no parent")?)?;
+        std::fs::write(path, pak.read_file(index)?)?;
     }
 
     for index in unvisited {
@@ -1209,8 +1882,21 @@ fn dump_meat(_mesh: String, _rcol: String, _output: String) -> Result<()> {
     unimplemented!()
 }
 
-fn gen_meat(pak: Vec<String>, index: u32, output: impl Write) -> Result<()> {
+fn dump_stage(pak: Vec<String>, map: i32, output: String, list: Vec<String>) -> Result<()> {
+    let mut logger_root = LoggerRoot::new();
+    let logger = &mut logger_root.logger();
     let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    load_path_lists(&mut pak, &list)?;
+    extract::export_stage(&pak, map, Path::new(&output), logger)
+}
+
+fn spiribird_route(pak: Vec<String>, map: i32, petalace: u32) -> Result<()> {
+    let pak = PakReader::new(open_pak_files(pak)?)?;
+    extract::print_spiribird_route(&pak, map, petalace)
+}
+
+fn gen_meat(pak: Vec<String>, index: u32, output: impl Write) -> Result<()> {
+    let pak = PakReader::new(open_pak_files(pak)?)?;
 
     let mesh_path = format!("enemy/em{index:03}/00/mod/em{index:03}_00.mesh");
     let rcol_path = format!("enemy/em{index:03}/00/collision/em{index:03}_00_colliders.rcol");
@@ -1231,10 +1917,10 @@ fn gen_meat(pak: Vec<String>, index: u32, output: impl Write) -> Result<()> {
 fn gen_resources(pak: Vec<String>, output: String) -> Result<()> {
     let mut logger_root = LoggerRoot::new();
     let logger = &mut logger_root.logger();
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
+    let pak = PakReader::new(open_pak_files(pak)?)?;
 
     let sink = DiskSink::init(Path::new(&output))?;
-    extract::gen_resources(&mut pak, &sink, logger)?;
+    extract::gen_resources(&pak, &sink, logger)?;
 
     Ok(())
 }
@@ -1352,15 +2038,15 @@ fn scene_print_scene(scene: &Scene, level: usize) {
 }
 
 fn scene(pak: Vec<String>, name: String) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
-    let scene = Scene::new(&mut pak, &name)?;
+    let pak = PakReader::new(open_pak_files(pak)?)?;
+    let scene = Scene::new(&pak, &name)?;
     scene_print_scene(&scene, 0);
     Ok(())
 }
 
 fn map(pak: Vec<String>, name: String, scale: String, tex: String, output: String) -> Result<()> {
-    let mut pak = PakReader::new(open_pak_files(pak)?)?;
-    let scene = Scene::new(&mut pak, &name)?;
+    let pak = PakReader::new(open_pak_files(pak)?)?;
+    let scene = Scene::new(&pak, &name)?;
     let scale: rsz::GuiMapScaleDefineData = User::new(File::open(scale)?)?
         .rsz
         .deserialize_single(None)?;
@@ -1399,7 +2085,7 @@ fn main() -> Result<()> {
             index,
             output,
         } => dump_index(pak, version, index, output),
-        Mhrice::ScanRsz { pak, crc } => scan_rsz(pak, crc),
+        Mhrice::ScanRsz { pak, crc, list } => scan_rsz(pak, crc, list),
         Mhrice::GenJson { pak, sha } => gen_json(pak, sha),
         Mhrice::GenWebsite {
             pak,
@@ -1408,18 +2094,76 @@ fn main() -> Result<()> {
             sha,
         } => gen_website(pak, output, origin, sha),
         Mhrice::ReadTdb { tdb, options } => read_tdb(tdb, options),
-        Mhrice::ReadMsg { msg } => read_msg(msg),
-        Mhrice::ScanMsg { pak, output } => scan_msg(pak, output),
-        Mhrice::GrepMsg { pak, pattern } => grep_msg(pak, pattern),
+        Mhrice::GenRszCode {
+            tdb,
+            dmp,
+            types,
+            version,
+            output,
+        } => gen_rsz_code(tdb, dmp, types, version, output),
+        Mhrice::TdbShell { tdb, dmp } => tdb_shell(tdb, dmp),
+        Mhrice::MigrateRsz {
+            old,
+            new,
+            old_version,
+            new_version,
+            types,
+            output,
+        } => migrate_rsz(old, new, old_version, new_version, types, output),
+        Mhrice::ReadMsg {
+            msg,
+            format,
+            language,
+        } => read_msg(msg, format, language),
+        Mhrice::ExportMsg {
+            msg,
+            output,
+            format,
+            source,
+        } => export_msg(msg, output, format, source),
+        Mhrice::ImportMsg {
+            msg,
+            input,
+            language,
+            output,
+        } => import_msg(msg, input, language, output),
+        Mhrice::ScanMsg { pak, output, list } => scan_msg(pak, output, list),
+        Mhrice::GrepMsg {
+            pak,
+            pattern,
+            format,
+            list,
+        } => grep_msg(pak, pattern, format, list),
         Mhrice::Grep {
             pak,
             utf16,
             pattern,
-        } => grep(pak, utf16, pattern),
-        Mhrice::SearchPath { pak, dmp } => search_path(pak, dmp),
+            list,
+        } => grep(pak, utf16, list, pattern),
+        Mhrice::BuildXref { pak, output, list } => build_xref(pak, output, list),
+        Mhrice::QueryXref { index, target } => query_xref(index, target),
+        Mhrice::SearchPath {
+            pak,
+            dmp,
+            list,
+            new_output,
+        } => search_path(pak, dmp, list, new_output),
+        Mhrice::VerifyPak { pak, list } => verify_pak(pak, list),
+        Mhrice::MountPak {
+            pak,
+            list,
+            mountpoint,
+        } => mount_pak(pak, list, mountpoint),
+        Mhrice::PakLayers {
+            pak,
+            list,
+            diff,
+            output,
+        } => pak_layers(pak, list, diff, output),
+        Mhrice::PathDict { pak, list, output } => path_dict(pak, list, output),
         Mhrice::DumpTree { pak, list, output } => dump_tree(pak, list, output),
         Mhrice::ScanMesh { pak } => scan_mesh(pak),
-        Mhrice::ScanTex { pak } => scan_tex(pak),
+        Mhrice::ScanTex { pak, list } => scan_tex(pak, list),
         Mhrice::ScanGui { pak } => scan_gui(pak),
         Mhrice::ScanUvs { pak } => scan_uvs(pak),
         Mhrice::DumpMesh { mesh, output } => dump_mesh(mesh, output),
@@ -1435,6 +2179,13 @@ fn main() -> Result<()> {
         Mhrice::GenMeat { pak, index, output } => {
             gen_meat(pak, index, std::fs::File::create(output)?)
         }
+        Mhrice::DumpStage {
+            pak,
+            map,
+            output,
+            list,
+        } => dump_stage(pak, map, output, list),
+        Mhrice::SpiribirdRoute { pak, map, petalace } => spiribird_route(pak, map, petalace),
         Mhrice::GenResources { pak, output } => gen_resources(pak, output),
         Mhrice::Hash { input, utf16 } => {
             hash(input, utf16);
@@ -1449,6 +2200,17 @@ fn main() -> Result<()> {
         Mhrice::DumpScn { scn } => dump_scn(scn),
         Mhrice::DumpPfb { pfb } => dump_pfb(pfb),
         Mhrice::Scene { pak, name } => scene(pak, name),
+        Mhrice::DumpRszSchema {
+            dmp,
+            address,
+            output,
+        } => dump_rsz_schema(dmp, address, output),
+        Mhrice::InspectObject {
+            dmp,
+            object,
+            depth,
+            address,
+        } => inspect_object(dmp, object, depth, address),
         Mhrice::TypeInfo { dmp, hash, crc } => type_info(dmp, hash, crc),
         Mhrice::Map {
             pak,
@@ -0,0 +1,109 @@
+//! Reader for collision mesh files (.mcol), referenced by `via.physics.MeshShape`.
+//!
+//! An MCOL file wraps a BVH block that holds the collision geometry.
+//! Only the triangle soup is decoded. Spheres, capsules and boxes in the same block,
+//! as well as the tree nodes, are counted but not read.
+
+use crate::file_ext::*;
+use anyhow::{bail, Result};
+use nalgebra_glm::*;
+use std::io::{Read, Seek, SeekFrom};
+
+pub struct McolTriangle {
+    pub vertices: [u32; 3],
+    pub layer: i32,
+    pub mask: u32,
+}
+
+pub struct Mcol {
+    pub positions: Vec<Vec3>,
+    pub triangles: Vec<McolTriangle>,
+    pub sphere_count: u32,
+    pub capsule_count: u32,
+    pub box_count: u32,
+}
+
+impl Mcol {
+    pub fn new<F: Read + Seek>(mut file: F) -> Result<Mcol> {
+        let file_len = file.seek(SeekFrom::End(0))?;
+        file.seek(SeekFrom::Start(0))?;
+
+        if &file.read_magic()? != b"MCOL" {
+            bail!("Wrong magic for MCOL");
+        }
+        let _ = file.read_u32()?; // version?
+        let bvh_len = file.read_u64()?;
+        let bvh_offset = file.read_u64()?;
+        if bvh_offset
+            .checked_add(bvh_len)
+            .is_none_or(|end| end > file_len)
+        {
+            bail!("BVH block out of bound");
+        }
+
+        // All offsets in the BVH block are relative to its start
+        file.seek(SeekFrom::Start(bvh_offset))?;
+        let _tree_offset = file.read_u64()?;
+        let triangle_offset = file.read_u64()?;
+        let _sphere_offset = file.read_u64()?;
+        let _capsule_offset = file.read_u64()?;
+        let _box_offset = file.read_u64()?;
+        let vertex_offset = file.read_u64()?;
+        let _string_table_offset = file.read_u64()?;
+        let triangle_count = file.read_u32()?;
+        let sphere_count = file.read_u32()?;
+        let capsule_count = file.read_u32()?;
+        let box_count = file.read_u32()?;
+        let vertex_count = file.read_u32()?;
+        let _ = file.read_u32()?; // tree node count?
+
+        // Each triangle takes 0x28 bytes, each vertex 0x10 bytes
+        let triangle_end = triangle_offset + u64::from(triangle_count) * 0x28;
+        let vertex_end = vertex_offset + u64::from(vertex_count) * 0x10;
+        if triangle_end > bvh_len || vertex_end > bvh_len {
+            bail!("BVH section out of bound");
+        }
+
+        file.seek(SeekFrom::Start(bvh_offset + vertex_offset))?;
+        let positions = (0..vertex_count)
+            .map(|_| Ok(file.read_f32vec4()?.xyz()))
+            .collect::<Result<Vec<_>>>()?;
+
+        file.seek(SeekFrom::Start(bvh_offset + triangle_offset))?;
+        let triangles = (0..triangle_count)
+            .map(|i| {
+                let layer = file.read_i32()?;
+                let mask = file.read_u32()?;
+                let _ = file.read_u32()?; // attribute?
+                let _ = file.read_u32()?;
+                let mut vertices = [0; 3];
+                for vertex in &mut vertices {
+                    *vertex = file.read_u32()?;
+                    if *vertex >= vertex_count {
+                        bail!("Vertex index out of bound in triangle {i}");
+                    }
+                }
+                for _ in 0..3 {
+                    // Neighbor triangle across each edge, -1 on open edges
+                    let neighbor = file.read_i32()?;
+                    if neighbor < -1 || i64::from(neighbor) >= i64::from(triangle_count) {
+                        bail!("Neighbor index out of bound in triangle {i}");
+                    }
+                }
+                Ok(McolTriangle {
+                    vertices,
+                    layer,
+                    mask,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Mcol {
+            positions,
+            triangles,
+            sphere_count,
+            capsule_count,
+            box_count,
+        })
+    }
+}
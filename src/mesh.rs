@@ -691,13 +691,53 @@ impl Mesh {
         })
     }
 
-    pub fn dump(&self, output: String) -> Result<()> {
-        let mut output = std::fs::File::create(output)?;
+    fn vertex_count(&self) -> u32 {
+        (self.vertex_layouts[1].offset - self.vertex_layouts[0].offset)
+            / self.vertex_layouts[0].width as u32
+    }
+
+    /// Vertex positions of the main model
+    pub fn positions(&self) -> Result<Vec<Vec3>> {
         let position = self
             .vertex_layouts
             .iter()
             .find(|layout| layout.usage == 0)
             .context("No position data")?;
+        if position.width != 12 {
+            bail!("Unexpected width for position {}", position.width);
+        }
+
+        let mut buffer = &self.vertex_buffer[position.offset as usize..];
+        (0..self.vertex_count())
+            .map(|_| {
+                let x = buffer.read_f32()?;
+                let y = buffer.read_f32()?;
+                let z = buffer.read_f32()?;
+                Ok(vec3(x, y, z))
+            })
+            .collect()
+    }
+
+    /// Triangles of the first LOD of the main model, as indices into `positions`
+    pub fn triangles(&self) -> Result<Vec<[u32; 3]>> {
+        let lod = self.main_model_lods.first().context("No LOD")?;
+        let mut triangles = vec![];
+        for group in &lod.model_groups {
+            for model in &group.models {
+                let mut index_buffer = &self.index_buffer[model.index_buffer_start as usize * 2..];
+                for _ in 0..model.index_count / 3 {
+                    let a = index_buffer.read_u16()? as u32 + model.vertex_buffer_start;
+                    let b = index_buffer.read_u16()? as u32 + model.vertex_buffer_start;
+                    let c = index_buffer.read_u16()? as u32 + model.vertex_buffer_start;
+                    triangles.push([a, b, c]);
+                }
+            }
+        }
+        Ok(triangles)
+    }
+
+    pub fn dump(&self, output: String) -> Result<()> {
+        let mut output = std::fs::File::create(output)?;
 
         let normal = self
             .vertex_layouts
@@ -711,19 +751,10 @@ impl Mesh {
             .find(|layout| layout.usage == 2)
             .context("No texcoord data")?;
 
-        let vertex_count = (self.vertex_layouts[1].offset - self.vertex_layouts[0].offset)
-            / self.vertex_layouts[0].width as u32;
+        let vertex_count = self.vertex_count();
 
-        if position.width != 12 {
-            bail!("Unexpected width for position {}", position.width);
-        }
-
-        let mut buffer = &self.vertex_buffer[position.offset as usize..];
-        for _ in 0..vertex_count {
-            let x = buffer.read_f32()?;
-            let y = buffer.read_f32()?;
-            let z = buffer.read_f32()?;
-            writeln!(output, "v {x} {y} {z}")?;
+        for p in self.positions()? {
+            writeln!(output, "v {} {} {}", p.x, p.y, p.z)?;
         }
 
         let mut buffer = &self.vertex_buffer[normal.offset as usize..];
@@ -762,24 +793,14 @@ impl Mesh {
             writeln!(output, "vt {} {}", u, 1.0 - v.to_f32())?;
         }
 
-        let lod = &self.main_model_lods[0];
-
-        for group in &lod.model_groups {
-            for model in &group.models {
-                let mut index_buffer = &self.index_buffer[model.index_buffer_start as usize * 2..];
-                for _ in 0..model.index_count / 3 {
-                    let a = index_buffer.read_u16()? as u32 + model.vertex_buffer_start;
-                    let b = index_buffer.read_u16()? as u32 + model.vertex_buffer_start;
-                    let c = index_buffer.read_u16()? as u32 + model.vertex_buffer_start;
-                    writeln!(
-                        output,
-                        "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}",
-                        a + 1,
-                        b + 1,
-                        c + 1
-                    )?;
-                }
-            }
+        for [a, b, c] in self.triangles()? {
+            writeln!(
+                output,
+                "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}",
+                a + 1,
+                b + 1,
+                c + 1
+            )?;
         }
 
         Ok(())
@@ -0,0 +1,493 @@
+//! Parser and renderers for the tags embedded in MSG text,
+//! such as `<COL RED>...</COL>`, `<REF name>`, `<ICON name>` and `<PL>`.
+
+use super::MsgEntry;
+use std::fmt::Write;
+use typed_html::{dom::*, elements::*, html, text};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color<'a> {
+    /// `<COLOR rrggbb>`
+    Rgb(&'a str),
+    Red,
+    Yellow,
+    Gray,
+    Unknown(&'a str),
+}
+
+impl<'a> Color<'a> {
+    fn from_name(name: &'a str) -> Self {
+        match name {
+            "RED" => Color::Red,
+            "YEL" | "YELLOW" => Color::Yellow,
+            "GRAY" => Color::Gray,
+            _ => Color::Unknown(name),
+        }
+    }
+
+    fn css(&self) -> String {
+        match self {
+            Color::Rgb(rgb) => format!("#{rgb}"),
+            Color::Red => "red".to_owned(),
+            Color::Yellow => "orange".to_owned(),
+            Color::Gray => "gray".to_owned(),
+            Color::Unknown(_) => "black".to_owned(),
+        }
+    }
+
+    fn ansi(&self) -> String {
+        match self {
+            Color::Rgb(rgb) => {
+                let value = u32::from_str_radix(rgb, 16).unwrap_or(0xFFFFFF);
+                format!(
+                    "\x1b[38;2;{};{};{}m",
+                    (value >> 16) & 0xFF,
+                    (value >> 8) & 0xFF,
+                    value & 0xFF
+                )
+            }
+            Color::Red => "\x1b[31m".to_owned(),
+            Color::Yellow => "\x1b[33m".to_owned(),
+            Color::Gray => "\x1b[90m".to_owned(),
+            Color::Unknown(_) => "\x1b[39m".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node<'a> {
+    Text(&'a str),
+    /// `<COLOR>`, `<COL>` and `<COLS>`
+    Color {
+        color: Color<'a>,
+        children: Vec<Node<'a>>,
+    },
+    /// `<BSL>`. Text direction change?
+    Bsl(Vec<Node<'a>>),
+    /// `<REF name>`, the content of another MSG entry
+    Ref(&'a str),
+    /// `<PL>`, the player name
+    Player,
+    /// `<ICON name>`, an inline icon such as a button
+    Icon(&'a str),
+    /// `<LSNR ...>`, gender selector
+    Selector(&'a str),
+    /// `<ПУСТО>`, appears verbatim in some Russian text
+    Blank,
+    /// Any other tag. Only `LEFT`, `FONT`, `TCU` and `size` can have children
+    Unknown {
+        tag: &'a str,
+        arg: &'a str,
+        children: Vec<Node<'a>>,
+    },
+}
+
+/// Tags that come in pairs. Everything else is a single tag without closing.
+const CONTAINER_TAGS: &[&str] = &["COLOR", "COL", "COLS", "BSL", "LEFT", "FONT", "TCU", "size"];
+
+/// References nested deeper than this are not expanded
+const MAX_REF_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupWarning<'a> {
+    /// A tag is left open at the end, or `<` has no matching `>`
+    Unterminated,
+    /// A closing tag that doesn't match the open tag, or there is no open tag
+    Unbalanced(&'a str),
+    UnknownColor(&'a str),
+    UnknownTag(&'a str),
+}
+
+impl MarkupWarning<'_> {
+    /// Whether the text is malformed, as opposed to using tags we don't know
+    pub fn is_parse_error(&self) -> bool {
+        matches!(
+            self,
+            MarkupWarning::Unterminated | MarkupWarning::Unbalanced(_)
+        )
+    }
+}
+
+impl std::fmt::Display for MarkupWarning<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkupWarning::Unterminated => write!(f, "Unterminated tag"),
+            MarkupWarning::Unbalanced(tag) => write!(f, "Unbalanced closing tag </{tag}>"),
+            MarkupWarning::UnknownColor(name) => write!(f, "Unknown color {name}"),
+            MarkupWarning::UnknownTag(tag) => write!(f, "Unknown tag <{tag}>"),
+        }
+    }
+}
+
+struct OpenTag<'a> {
+    tag: &'a str,
+    arg: &'a str,
+    children: Vec<Node<'a>>,
+}
+
+impl<'a> OpenTag<'a> {
+    fn into_node(self, warnings: &mut Vec<MarkupWarning<'a>>) -> Node<'a> {
+        let OpenTag { tag, arg, children } = self;
+        match tag {
+            "COLOR" => Node::Color {
+                color: Color::Rgb(arg),
+                children,
+            },
+            "COL" | "COLS" => {
+                let color = Color::from_name(arg);
+                if let Color::Unknown(name) = color {
+                    warnings.push(MarkupWarning::UnknownColor(name));
+                }
+                Node::Color { color, children }
+            }
+            "BSL" => Node::Bsl(children),
+            "REF" => Node::Ref(arg),
+            "PL" => Node::Player,
+            "ICON" => Node::Icon(arg),
+            "LSNR" => Node::Selector(arg),
+            "ПУСТО" => Node::Blank,
+            _ => {
+                warnings.push(MarkupWarning::UnknownTag(tag));
+                Node::Unknown { tag, arg, children }
+            }
+        }
+    }
+
+    fn closed_by(&self, tag: &str) -> bool {
+        self.tag == tag || (self.tag == "COLS" && tag == "COL")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Markup<'a> {
+    pub nodes: Vec<Node<'a>>,
+    /// Problems found while parsing, in the order they appear
+    pub warnings: Vec<MarkupWarning<'a>>,
+}
+
+impl<'a> Markup<'a> {
+    pub fn parse(content: &'a str) -> Markup<'a> {
+        let mut msg = content;
+        let mut warnings = vec![];
+
+        let mut root: Vec<Node<'a>> = vec![];
+        let mut stack: Vec<OpenTag<'a>> = vec![];
+
+        fn push<'a>(root: &mut Vec<Node<'a>>, stack: &mut [OpenTag<'a>], node: Node<'a>) {
+            if let Some(last) = stack.last_mut() {
+                last.children.push(node);
+            } else {
+                root.push(node);
+            }
+        }
+
+        loop {
+            let next_stop = msg.find('<').unwrap_or(msg.len());
+            if next_stop != 0 {
+                push(&mut root, &mut stack, Node::Text(&msg[0..next_stop]));
+            }
+
+            if msg.len() == next_stop {
+                if !stack.is_empty() {
+                    warnings.push(MarkupWarning::Unterminated);
+                }
+                break;
+            }
+
+            msg = &msg[(next_stop + 1)..];
+
+            let Some(next_stop) = msg.find('>') else {
+                if !stack.is_empty() {
+                    warnings.push(MarkupWarning::Unterminated);
+                }
+                break;
+            };
+            let tag = &msg[0..next_stop];
+            msg = &msg[(next_stop + 1)..];
+
+            if let Some(tag) = tag.strip_prefix('/') {
+                let Some(open) = stack.pop() else {
+                    warnings.push(MarkupWarning::Unbalanced(tag));
+                    break;
+                };
+                if !open.closed_by(tag) {
+                    warnings.push(MarkupWarning::Unbalanced(tag));
+                }
+                let node = open.into_node(&mut warnings);
+                push(&mut root, &mut stack, node);
+            } else {
+                let (tag, arg) = tag.split_once(' ').unwrap_or((tag, ""));
+                let open = OpenTag {
+                    tag,
+                    arg,
+                    children: vec![],
+                };
+                if CONTAINER_TAGS.contains(&tag) {
+                    stack.push(open);
+                } else {
+                    let node = open.into_node(&mut warnings);
+                    push(&mut root, &mut stack, node);
+                }
+            }
+        }
+
+        while let Some(open) = stack.pop() {
+            let node = open.into_node(&mut warnings);
+            push(&mut root, &mut stack, node);
+        }
+
+        Markup {
+            nodes: root,
+            warnings,
+        }
+    }
+
+    pub fn has_parse_error(&self) -> bool {
+        self.warnings.iter().any(MarkupWarning::is_parse_error)
+    }
+
+    /// Only the text, with all tags removed and references left unresolved.
+    pub fn to_plain_text(&self) -> String {
+        fn rec(result: &mut String, nodes: &[Node]) {
+            for node in nodes {
+                match node {
+                    Node::Text(s) => *result += s,
+                    Node::Color { children, .. }
+                    | Node::Bsl(children)
+                    | Node::Unknown { children, .. } => rec(result, children),
+                    Node::Ref(_)
+                    | Node::Player
+                    | Node::Icon(_)
+                    | Node::Selector(_)
+                    | Node::Blank => (),
+                }
+            }
+        }
+        let mut result = String::new();
+        rec(&mut result, &self.nodes);
+        result
+    }
+
+    pub fn to_html<'r, RefF>(&self, language: usize, reference: RefF) -> Box<span<String>>
+    where
+        RefF: Fn(&str) -> Option<&'r MsgEntry> + Clone,
+    {
+        html!(<span> {
+            self.nodes.iter().map(|n| node_to_html(n, language, reference.clone(), &[]))
+        } </span>)
+    }
+
+    pub fn to_markdown<'r, RefF>(&self, language: usize, reference: RefF) -> String
+    where
+        RefF: Fn(&str) -> Option<&'r MsgEntry> + Clone,
+    {
+        let mut result = String::new();
+        render_text(
+            &mut result,
+            &self.nodes,
+            TextStyle::Markdown,
+            language,
+            &reference,
+            &mut vec![],
+            &mut vec![],
+        );
+        result
+    }
+
+    /// Renders for terminals, with colors as ANSI escape sequences.
+    pub fn to_ansi<'r, RefF>(&self, language: usize, reference: RefF) -> String
+    where
+        RefF: Fn(&str) -> Option<&'r MsgEntry> + Clone,
+    {
+        let mut result = String::new();
+        render_text(
+            &mut result,
+            &self.nodes,
+            TextStyle::Ansi,
+            language,
+            &reference,
+            &mut vec![],
+            &mut vec![],
+        );
+        result
+    }
+}
+
+/// Whether a reference can be expanded inside the references in `refs`,
+/// without cycling back to one of them or nesting too deep.
+fn can_expand_ref(name: &str, refs: &[String]) -> bool {
+    refs.len() < MAX_REF_DEPTH && !refs.iter().any(|r| r == name)
+}
+
+/// `refs` are the names of the references being expanded, from the outermost one.
+fn node_to_html<'r, RefF>(
+    node: &Node<'_>,
+    language: usize,
+    reference: RefF,
+    refs: &[String],
+) -> Box<dyn PhrasingContent<String>>
+where
+    RefF: Fn(&str) -> Option<&'r MsgEntry> + Clone,
+{
+    let inner = |children: &[Node<'_>]| {
+        children
+            .iter()
+            .map(|n| node_to_html(n, language, reference.clone(), refs))
+            .collect::<Vec<_>>()
+    };
+    match node {
+        Node::Text(s) => Box::new(TextNode::<String>::new(*s)),
+        Node::Color { color, children } => {
+            let style = format!("color: {};", color.css());
+            html!(<span style={style}> {inner(children)} </span>)
+        }
+        Node::Bsl(children) => html!(<span> {inner(children)} </span>),
+        Node::Selector(arg) => {
+            html!(<span class="mh-msg-place-holder"> {text!("{}", arg)} </span>)
+        }
+        Node::Player => html!(<span class="mh-msg-place-holder"> "{Player}" </span>),
+        Node::Icon(name) => {
+            html!(<span class="mh-msg-place-holder"> {text!("{{ICON {}}}", name)} </span>)
+        }
+        Node::Blank => html!(<span> "<ПУСТО>" </span>),
+        Node::Ref(name) => {
+            let entry = reference(name).filter(|_| can_expand_ref(name, refs));
+            let Some(entry) = entry else {
+                return html!(<span class="mh-msg-place-holder">{text!("{{REF {}}}", name)}</span>);
+            };
+            let Some(content) = entry.content.get(language) else {
+                return html!(<span class="mh-msg-place-holder">
+                    <i class="fas fa-triangle-exclamation"/> "[Unsupported language]"
+                </span>);
+            };
+            let mut refs = refs.to_vec();
+            refs.push(name.to_string());
+            let children: Vec<_> = Markup::parse(content)
+                .nodes
+                .iter()
+                .map(|n| node_to_html(n, language, reference.clone(), &refs))
+                .collect();
+            html!(<span> {children} </span>)
+        }
+        Node::Unknown { tag, arg, children } => {
+            html!(<span>
+                <span class="mh-msg-place-holder">{text!("<{} {}>", tag, arg)}</span>
+                {inner(children)}
+                <span class="mh-msg-place-holder">{text!("</{}>", tag)}</span>
+            </span>)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TextStyle {
+    Markdown,
+    Ansi,
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_DIM: &str = "\x1b[2m";
+
+fn markdown_escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '{' | '}' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// `colors` is the stack of colors enclosing `nodes`, for restoring ANSI color on closing tags.
+/// `refs` are the names of the references being expanded, from the outermost one.
+fn render_text<'r, RefF>(
+    result: &mut String,
+    nodes: &[Node<'_>],
+    style: TextStyle,
+    language: usize,
+    reference: &RefF,
+    colors: &mut Vec<String>,
+    refs: &mut Vec<String>,
+) where
+    RefF: Fn(&str) -> Option<&'r MsgEntry> + Clone,
+{
+    let restore = |result: &mut String, colors: &[String]| {
+        *result += ANSI_RESET;
+        if let Some(color) = colors.last() {
+            *result += color;
+        }
+    };
+    let place_holder = |result: &mut String, colors: &[String], s: &str| match style {
+        TextStyle::Markdown => {
+            let _ = write!(result, "`{s}`");
+        }
+        TextStyle::Ansi => {
+            *result += ANSI_DIM;
+            *result += s;
+            restore(result, colors);
+        }
+    };
+
+    for node in nodes {
+        match node {
+            Node::Text(s) => {
+                let s = s.replace("\r\n", "\n");
+                match style {
+                    TextStyle::Markdown => *result += &markdown_escape(&s).replace('\n', "  \n"),
+                    TextStyle::Ansi => *result += &s,
+                }
+            }
+            Node::Color { color, children } => match style {
+                TextStyle::Markdown => {
+                    // Markdown has no color, and nested emphasis doesn't work
+                    let bold = if colors.is_empty() { "**" } else { "" };
+                    *result += bold;
+                    colors.push(String::new());
+                    render_text(result, children, style, language, reference, colors, refs);
+                    colors.pop();
+                    *result += bold;
+                }
+                TextStyle::Ansi => {
+                    let code = color.ansi();
+                    *result += &code;
+                    colors.push(code);
+                    render_text(result, children, style, language, reference, colors, refs);
+                    colors.pop();
+                    restore(result, colors);
+                }
+            },
+            Node::Bsl(children) | Node::Unknown { children, .. } => {
+                render_text(result, children, style, language, reference, colors, refs)
+            }
+            Node::Selector(arg) => place_holder(result, colors, &format!("{{{arg}}}")),
+            Node::Player => place_holder(result, colors, "{Player}"),
+            Node::Icon(name) => place_holder(result, colors, &format!("{{ICON {name}}}")),
+            Node::Blank => place_holder(result, colors, "<ПУСТО>"),
+            Node::Ref(name) => {
+                let content = reference(name)
+                    .filter(|_| can_expand_ref(name, refs))
+                    .and_then(|entry| entry.content.get(language));
+                match content {
+                    Some(content) => {
+                        refs.push(name.to_string());
+                        render_text(
+                            result,
+                            &Markup::parse(content).nodes,
+                            style,
+                            language,
+                            reference,
+                            colors,
+                            refs,
+                        );
+                        refs.pop();
+                    }
+                    None => place_holder(result, colors, &format!("{{REF {name}}}")),
+                }
+            }
+        }
+    }
+}
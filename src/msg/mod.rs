@@ -0,0 +1,428 @@
+use crate::align::*;
+use crate::file_ext::*;
+use crate::hash::hash_as_utf16;
+use crate::rsz::Guid;
+use anyhow::{bail, Result};
+use serde::*;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{Read, Seek};
+
+pub mod markup;
+pub mod translation;
+
+const KEY: [u8; 16] = [
+    0xCF, 0xCE, 0xFB, 0xF8, 0xEC, 0x0A, 0x33, 0x66, 0x93, 0xA9, 0x1D, 0x93, 0x50, 0x39, 0x5F, 0x09,
+];
+
+#[derive(Debug, Serialize)]
+pub struct MsgAttributeHeader {
+    pub ty: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub enum MsgAttribute {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Unknown(u64),
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MsgEntry {
+    pub name: String,
+    pub guid: Guid,
+    pub index: u32,
+    pub hash: u32,
+    pub attributes: Vec<MsgAttribute>,
+    pub content: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Msg {
+    pub version: u32,
+    /// Number of content slots in each entry, which is kept even if there is no entry
+    pub language_count: usize,
+    pub attribute_headers: Vec<MsgAttributeHeader>,
+    pub entries: Vec<MsgEntry>,
+}
+
+impl Msg {
+    pub fn new<F: Read + Seek>(mut file: F) -> Result<Msg> {
+        let version = file.read_u32()?;
+        if version != 17 && version != 539100710 {
+            bail!("Wrong version {version} for MSG")
+        }
+        if &file.read_magic()? != b"GMSG" {
+            bail!("Wrong magic for MSG")
+        }
+        if file.read_u64()? != 0x10 {
+            bail!("Expected 0x10")
+        }
+        let entry_count = file.read_u32()?;
+        let attribute_count = file.read_u32()?;
+        let language_count = file.read_u32()?;
+        file.seek_align_up(8)?;
+
+        let data_offset = file.read_u64()?;
+        let p_offset = file.read_u64()?;
+        let languages_offset = file.read_u64()?;
+        let attribute_types_offset = file.read_u64()?;
+        let attribute_names_offset = file.read_u64()?;
+
+        let entries = (0..entry_count)
+            .map(|_| file.read_u64())
+            .collect::<Result<Vec<_>>>()?;
+
+        file.seek_noop(p_offset)?;
+        let p = file.read_u64()?;
+        if p != 0 {
+            bail!("Expected 0")
+        }
+
+        file.seek_noop(languages_offset)?;
+        let languages = (0..language_count)
+            .map(|_| file.read_u32())
+            .collect::<Result<Vec<_>>>()?;
+
+        for (i, language) in languages.into_iter().enumerate() {
+            if i != usize::try_from(language)? {
+                bail!("Unexpected language index")
+            }
+        }
+
+        file.seek_assert_align_up(attribute_types_offset, 8)?;
+        let attribute_types = (0..attribute_count)
+            .map(|_| file.read_i32())
+            .collect::<Result<Vec<_>>>()?;
+
+        file.seek_assert_align_up(attribute_names_offset, 8)?;
+        let attribute_names = (0..attribute_count)
+            .map(|_| file.read_u64())
+            .collect::<Result<Vec<_>>>()?;
+
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                file.seek_noop(entry)?;
+                let mut guid = [0; 16];
+                file.read_exact(&mut guid)?;
+                let index = file.read_u32()?;
+                let hash = file.read_u32()?;
+
+                let name = file.read_u64()?;
+                let attributes = file.read_u64()?;
+                let content = (0..language_count)
+                    .map(|_| file.read_u64())
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok((name, Guid { bytes: guid }, index, hash, attributes, content))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .map(|(name, guid, index, hash, attributes, content)| {
+                file.seek_noop(attributes)?;
+                let attributes = (0..attribute_count)
+                    .map(|_| file.read_u64())
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((name, guid, index, hash, attributes, content))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        file.seek_noop(data_offset)?;
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+
+        let mut prev = 0;
+        for (i, byte) in data.iter_mut().enumerate() {
+            let cur = *byte;
+            *byte ^= prev ^ KEY[i & 0xF];
+            prev = cur;
+        }
+
+        let entries = entries
+            .into_iter()
+            .map(|(name, guid, index, hash, attributes, content)| {
+                let name = (&data[usize::try_from(name - data_offset)?..]).read_u16str()?;
+                if hash_as_utf16(&name) != hash {
+                    bail!("Wrong hash")
+                }
+                let attributes = attributes
+                    .into_iter()
+                    .zip(&attribute_types)
+                    .map(|(attr, &ty)| {
+                        Ok(match ty {
+                            0 => MsgAttribute::Int(attr as i64),
+                            1 => MsgAttribute::Float(f64::from_bits(attr)),
+                            2 => MsgAttribute::String(
+                                (&data[usize::try_from(attr - data_offset)?..]).read_u16str()?,
+                            ),
+                            -1 => MsgAttribute::Unknown(attr),
+                            _ => bail!("Unknown attribute {ty}"),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let content = content
+                    .into_iter()
+                    .map(|o| (&data[usize::try_from(o - data_offset)?..]).read_u16str())
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(MsgEntry {
+                    name,
+                    guid,
+                    index,
+                    hash,
+                    attributes,
+                    content,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let attribute_headers = attribute_types
+            .into_iter()
+            .zip(attribute_names)
+            .map(|(ty, name)| {
+                let name = (&data[usize::try_from(name - data_offset)?..]).read_u16str()?;
+                Ok(MsgAttributeHeader { ty, name })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Msg {
+            version,
+            language_count: usize::try_from(language_count)?,
+            attribute_headers,
+            entries,
+        })
+    }
+
+    /// Encodes the MSG back to the file format, re-encrypting the string data.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let entry_count = u32::try_from(self.entries.len())?;
+        let attribute_count = u32::try_from(self.attribute_headers.len())?;
+        let language_count = self.language_count;
+        if self
+            .entries
+            .iter()
+            .any(|entry| entry.content.len() != language_count)
+        {
+            bail!("Inconsistent language count among entries")
+        }
+        if self
+            .entries
+            .iter()
+            .any(|entry| entry.attributes.len() != self.attribute_headers.len())
+        {
+            bail!("Inconsistent attribute count among entries")
+        }
+        let language_count_u32 = u32::try_from(language_count)?;
+
+        let entry_list_offset = 0x48;
+        let p_offset = entry_list_offset + 8 * u64::from(entry_count);
+        let languages_offset = p_offset + 8;
+        let attribute_types_offset = align_up(languages_offset + 4 * language_count as u64, 8);
+        let attribute_names_offset =
+            align_up(attribute_types_offset + 4 * u64::from(attribute_count), 8);
+        let entry_header_offset = attribute_names_offset + 8 * u64::from(attribute_count);
+        let entry_header_size = 0x28 + 8 * language_count as u64;
+        let entry_attributes_offset =
+            entry_header_offset + entry_header_size * u64::from(entry_count);
+        let data_offset =
+            entry_attributes_offset + 8 * u64::from(attribute_count) * u64::from(entry_count);
+
+        let mut data: Vec<u8> = vec![];
+        let mut string_map: HashMap<String, u64> = HashMap::new();
+        let mut add_string = |s: &str| -> u64 {
+            if let Some(&offset) = string_map.get(s) {
+                return offset;
+            }
+            let offset = data_offset + data.len() as u64;
+            data.extend(s.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+            string_map.insert(s.to_owned(), offset);
+            offset
+        };
+
+        let mut file = vec![];
+        file.extend(self.version.to_le_bytes());
+        file.extend(b"GMSG");
+        file.extend(0x10u64.to_le_bytes());
+        file.extend(entry_count.to_le_bytes());
+        file.extend(attribute_count.to_le_bytes());
+        file.extend(language_count_u32.to_le_bytes());
+        file.resize(align_up(file.len(), 8), 0);
+        for offset in [
+            data_offset,
+            p_offset,
+            languages_offset,
+            attribute_types_offset,
+            attribute_names_offset,
+        ] {
+            file.extend(offset.to_le_bytes());
+        }
+
+        for i in 0..u64::from(entry_count) {
+            file.extend((entry_header_offset + entry_header_size * i).to_le_bytes());
+        }
+        file.extend(0u64.to_le_bytes());
+        for language in 0..language_count_u32 {
+            file.extend(language.to_le_bytes());
+        }
+
+        file.resize(usize::try_from(attribute_types_offset)?, 0);
+        for header in &self.attribute_headers {
+            file.extend(header.ty.to_le_bytes());
+        }
+
+        file.resize(usize::try_from(attribute_names_offset)?, 0);
+        for header in &self.attribute_headers {
+            file.extend(add_string(&header.name).to_le_bytes());
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            file.extend(entry.guid.bytes);
+            file.extend(entry.index.to_le_bytes());
+            file.extend(hash_as_utf16(&entry.name).to_le_bytes());
+            file.extend(add_string(&entry.name).to_le_bytes());
+            let attributes = entry_attributes_offset + 8 * u64::from(attribute_count) * i as u64;
+            file.extend(attributes.to_le_bytes());
+            for content in &entry.content {
+                file.extend(add_string(content).to_le_bytes());
+            }
+        }
+
+        for entry in &self.entries {
+            for (attribute, header) in entry.attributes.iter().zip(&self.attribute_headers) {
+                let value = match (attribute, header.ty) {
+                    (MsgAttribute::Int(i), 0) => *i as u64,
+                    (MsgAttribute::Float(f), 1) => f.to_bits(),
+                    (MsgAttribute::String(s), 2) => add_string(s),
+                    (MsgAttribute::Unknown(u), -1) => *u,
+                    _ => bail!(
+                        "Attribute value {:?} doesn't match type {} for entry {}",
+                        attribute,
+                        header.ty,
+                        entry.name
+                    ),
+                };
+                file.extend(value.to_le_bytes());
+            }
+        }
+
+        if file.len() as u64 != data_offset {
+            bail!("Unexpected MSG header size")
+        }
+
+        let mut prev = 0;
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte ^= prev ^ KEY[i & 0xF];
+            prev = *byte;
+        }
+        file.extend(data);
+
+        Ok(file)
+    }
+
+    pub fn get_entry_mut(&mut self, name: &str) -> Option<&mut MsgEntry> {
+        self.entries.iter_mut().find(|entry| entry.name == name)
+    }
+
+    /// Makes sure every entry has a slot for the given language, so that
+    /// a language unused by the original file can be filled in.
+    pub fn ensure_language(&mut self, language: usize) {
+        self.language_count = self.language_count.max(language + 1);
+        for entry in &mut self.entries {
+            if entry.content.len() <= language {
+                entry.content.resize(language + 1, String::new());
+            }
+        }
+    }
+
+    pub fn get_entry(&self, name: &str) -> Option<&MsgEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn get_name_map(&self) -> HashMap<&String, &MsgEntry> {
+        self.entries
+            .iter()
+            .map(|entry| (&entry.name, entry))
+            .collect()
+    }
+
+    pub fn get_guid_map(&self) -> HashMap<Guid, &MsgEntry> {
+        self.entries
+            .iter()
+            .map(|entry| (entry.guid, entry))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn entry(name: &str, index: u32, content: &[&str]) -> MsgEntry {
+        MsgEntry {
+            name: name.to_owned(),
+            guid: Guid {
+                bytes: [index as u8; 16],
+            },
+            index,
+            hash: hash_as_utf16(name),
+            attributes: vec![
+                MsgAttribute::Int(-3),
+                MsgAttribute::Float(0.5),
+                MsgAttribute::String(format!("attr {name}")),
+                MsgAttribute::Unknown(0x1234),
+            ],
+            content: content.iter().map(|&s| s.to_owned()).collect(),
+        }
+    }
+
+    fn sample() -> Msg {
+        Msg {
+            version: 539100710,
+            language_count: 3,
+            attribute_headers: vec![
+                MsgAttributeHeader {
+                    ty: 0,
+                    name: "int".to_owned(),
+                },
+                MsgAttributeHeader {
+                    ty: 1,
+                    name: "float".to_owned(),
+                },
+                MsgAttributeHeader {
+                    ty: 2,
+                    name: "string".to_owned(),
+                },
+                MsgAttributeHeader {
+                    ty: -1,
+                    name: "unknown".to_owned(),
+                },
+            ],
+            entries: vec![
+                entry("Entry_00", 0, &["日本語", "<COL RED>English</COL>", ""]),
+                // Shares strings with the first entry
+                entry("Entry_01", 1, &["日本語", "Second\r\nline", "Français"]),
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let msg = sample();
+        let bytes = msg.to_bytes().unwrap();
+        let parsed = Msg::new(Cursor::new(&bytes)).unwrap();
+        assert_eq!(format!("{parsed:?}"), format!("{msg:?}"));
+        assert_eq!(parsed.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn round_trip_without_entries() {
+        let mut msg = sample();
+        msg.entries.clear();
+        let parsed = Msg::new(Cursor::new(msg.to_bytes().unwrap())).unwrap();
+        assert_eq!(parsed.language_count, 3);
+        assert!(parsed.entries.is_empty());
+    }
+}
@@ -0,0 +1,352 @@
+//! Exchange MSG text with translation tools via gettext PO and XLIFF 1.2.
+//!
+//! Each exported file covers one language. Entries are keyed by their name,
+//! which is unique within a MSG file, and carry the GUID as a note so that
+//! translators can search the game data for it.
+
+use super::*;
+use anyhow::{bail, Context, Result};
+use quick_xml::events::{BytesDecl, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashMap;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TranslationFormat {
+    Po,
+    Xliff,
+}
+
+impl TranslationFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TranslationFormat::Po => "po",
+            TranslationFormat::Xliff => "xlf",
+        }
+    }
+
+    pub fn from_path(path: &str) -> Result<Self> {
+        let lower = path.to_lowercase();
+        if lower.ends_with(".po") {
+            Ok(TranslationFormat::Po)
+        } else if lower.ends_with(".xlf") || lower.ends_with(".xliff") {
+            Ok(TranslationFormat::Xliff)
+        } else {
+            bail!("Unknown translation file type for {path}")
+        }
+    }
+}
+
+pub struct TranslationTarget<'a> {
+    /// Language index used as msgid / source
+    pub source: usize,
+    /// Language index to export
+    pub target: usize,
+    /// e.g. "en". Only used for file headers
+    pub source_code: Option<&'a str>,
+    pub target_code: Option<&'a str>,
+}
+
+fn get_content(entry: &MsgEntry, language: usize) -> &str {
+    entry.content.get(language).map_or("", |s| s.as_str())
+}
+
+fn po_escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => result += "\\\\",
+            '"' => result += "\\\"",
+            '\n' => result += "\\n",
+            '\r' => result += "\\r",
+            '\t' => result += "\\t",
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn po_unescape(s: &str) -> Result<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => result.push('\\'),
+            Some('"') => result.push('"'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            c => bail!("Unknown escape sequence \\{c:?}"),
+        }
+    }
+    Ok(result)
+}
+
+pub fn write_po(msg: &Msg, target: &TranslationTarget, mut output: impl Write) -> Result<()> {
+    writeln!(output, "msgid \"\"")?;
+    writeln!(output, "msgstr \"\"")?;
+    writeln!(output, "\"MIME-Version: 1.0\\n\"")?;
+    writeln!(output, "\"Content-Type: text/plain; charset=UTF-8\\n\"")?;
+    writeln!(output, "\"Content-Transfer-Encoding: 8bit\\n\"")?;
+    if let Some(code) = target.target_code {
+        writeln!(output, "\"Language: {code}\\n\"")?;
+    }
+    writeln!(output, "\"X-Mhrice-Language-Index: {}\\n\"", target.target)?;
+    writeln!(output)?;
+
+    for entry in &msg.entries {
+        let source = get_content(entry, target.source);
+        let translated = get_content(entry, target.target);
+        writeln!(output, "#. {}", String::from(entry.guid))?;
+        writeln!(output, "msgctxt \"{}\"", po_escape(&entry.name))?;
+        writeln!(output, "msgid \"{}\"", po_escape(source))?;
+        writeln!(output, "msgstr \"{}\"", po_escape(translated))?;
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+/// Returns translated text keyed by entry name.
+/// Untranslated (empty) strings and fuzzy translations are omitted.
+pub fn read_po(input: &str) -> Result<HashMap<String, String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Field {
+        None,
+        Context,
+        Id,
+        Str,
+    }
+
+    let mut result = HashMap::new();
+    let mut field = Field::None;
+    let mut context = String::new();
+    let mut translated = String::new();
+    // Fuzzy translations need review, so they are not imported
+    let mut fuzzy = false;
+
+    let mut flush = |context: &mut String, translated: &mut String, fuzzy: &mut bool| {
+        if !context.is_empty() && !translated.is_empty() && !*fuzzy {
+            result.insert(std::mem::take(context), std::mem::take(translated));
+        }
+        context.clear();
+        translated.clear();
+        *fuzzy = false;
+    };
+
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let quoted = |s: &str| -> Result<String> {
+            let s = s.trim();
+            let inner = s
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .with_context(|| format!("Expected quoted string at line {}", line_number + 1))?;
+            po_unescape(inner)
+        };
+
+        if let Some(flags) = line.strip_prefix("#,") {
+            // Flags come before the entry they apply to
+            flush(&mut context, &mut translated, &mut fuzzy);
+            field = Field::None;
+            fuzzy = flags.split(',').any(|flag| flag.trim() == "fuzzy");
+        } else if line.is_empty() || line.starts_with('#') {
+            continue;
+        } else if let Some(rest) = line.strip_prefix("msgctxt ") {
+            if field != Field::None {
+                flush(&mut context, &mut translated, &mut fuzzy);
+            }
+            field = Field::Context;
+            context = quoted(rest)?;
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            if field != Field::Context && field != Field::None {
+                flush(&mut context, &mut translated, &mut fuzzy);
+            }
+            field = Field::Id;
+            quoted(rest)?;
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            field = Field::Str;
+            translated = quoted(rest)?;
+        } else if line.starts_with('"') {
+            let s = quoted(line)?;
+            match field {
+                Field::Context => context += &s,
+                Field::Str => translated += &s,
+                Field::Id | Field::None => (),
+            }
+        } else {
+            bail!("Unrecognized PO line {}: {}", line_number + 1, line)
+        }
+    }
+    flush(&mut context, &mut translated, &mut fuzzy);
+
+    Ok(result)
+}
+
+pub fn write_xliff(msg: &Msg, target: &TranslationTarget, output: impl Write) -> Result<()> {
+    let mut writer = Writer::new_with_indent(output, b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+    let target_language = target
+        .target_code
+        .map_or_else(|| format!("x-mhrice-{}", target.target), str::to_owned);
+    let source_language = target
+        .source_code
+        .map_or_else(|| format!("x-mhrice-{}", target.source), str::to_owned);
+
+    writer
+        .create_element("xliff")
+        .with_attribute(("version", "1.2"))
+        .with_attribute(("xmlns", "urn:oasis:names:tc:xliff:document:1.2"))
+        .write_inner_content(|w| {
+            w.create_element("file")
+                .with_attribute(("original", "msg"))
+                .with_attribute(("datatype", "plaintext"))
+                .with_attribute(("source-language", source_language.as_str()))
+                .with_attribute(("target-language", target_language.as_str()))
+                .write_inner_content(|w| {
+                    w.create_element("body").write_inner_content(|w| {
+                        for entry in &msg.entries {
+                            let guid = String::from(entry.guid);
+                            w.create_element("trans-unit")
+                                .with_attribute(("id", entry.name.as_str()))
+                                .with_attribute(("resname", guid.as_str()))
+                                .write_inner_content(|w| {
+                                    w.create_element("source").write_text_content(
+                                        BytesText::new(get_content(entry, target.source)),
+                                    )?;
+                                    w.create_element("target").write_text_content(
+                                        BytesText::new(get_content(entry, target.target)),
+                                    )?;
+                                    Ok::<(), quick_xml::Error>(())
+                                })?;
+                        }
+                        Ok::<(), quick_xml::Error>(())
+                    })?;
+                    Ok::<(), quick_xml::Error>(())
+                })?;
+            Ok::<(), quick_xml::Error>(())
+        })?;
+    Ok(())
+}
+
+/// Returns translated text keyed by entry name. Untranslated (empty) strings are omitted.
+pub fn read_xliff(input: &str) -> Result<HashMap<String, String>> {
+    let mut reader = Reader::from_str(input);
+    let mut result = HashMap::new();
+
+    let mut unit: Option<String> = None;
+    let mut in_target = false;
+    let mut translated = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"trans-unit" => {
+                    let id = e
+                        .try_get_attribute("id")?
+                        .context("trans-unit without id")?
+                        .unescape_value()?
+                        .into_owned();
+                    unit = Some(id);
+                }
+                b"target" => {
+                    in_target = true;
+                    translated.clear();
+                }
+                _ => (),
+            },
+            Event::End(e) => match e.name().as_ref() {
+                b"trans-unit" => unit = None,
+                b"target" => {
+                    in_target = false;
+                    let id = unit.clone().context("target outside of trans-unit")?;
+                    if !translated.is_empty() {
+                        result.insert(id, std::mem::take(&mut translated));
+                    }
+                }
+                _ => (),
+            },
+            Event::Text(t) if in_target => translated += &t.unescape()?,
+            Event::CData(t) if in_target => translated += std::str::from_utf8(&t)?,
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(result)
+}
+
+pub fn write_translation(
+    msg: &Msg,
+    format: TranslationFormat,
+    target: &TranslationTarget,
+    output: impl Write,
+) -> Result<()> {
+    match format {
+        TranslationFormat::Po => write_po(msg, target, output),
+        TranslationFormat::Xliff => write_xliff(msg, target, output),
+    }
+}
+
+pub fn read_translation(format: TranslationFormat, input: &str) -> Result<HashMap<String, String>> {
+    match format {
+        TranslationFormat::Po => read_po(input),
+        TranslationFormat::Xliff => read_xliff(input),
+    }
+}
+
+/// Replaces the text of the given language with the translation.
+/// Returns the number of entries updated.
+pub fn apply_translation(
+    msg: &mut Msg,
+    language: usize,
+    translation: &HashMap<String, String>,
+) -> Result<usize> {
+    msg.ensure_language(language);
+    let mut count = 0;
+    for (name, text) in translation {
+        let entry = msg
+            .get_entry_mut(name)
+            .with_context(|| format!("Entry {name} not found in MSG"))?;
+        entry.content[language] = text.clone();
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn po_skips_fuzzy() {
+        let po = r#"msgid ""
+msgstr ""
+"Language: fr\n"
+
+#. guid
+msgctxt "A"
+msgid "a"
+msgstr "done"
+
+#, fuzzy
+msgctxt "B"
+msgid "b"
+msgstr "needs review"
+
+#, c-format
+msgctxt "C"
+msgid "c"
+msgstr "multi"
+"line"
+"#;
+        let result = read_po(po).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result["A"], "done");
+        assert_eq!(result["C"], "multiline");
+    }
+}
@@ -5,12 +5,15 @@ use crate::suffix::SUFFIX_MAP;
 use anyhow::{bail, Context, Result};
 use base64::prelude::*;
 use compress::flate;
+use memmap2::Mmap;
 use num_bigint::BigUint;
 use once_cell::sync::Lazy;
+use rayon::prelude::*;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::{TryFrom, TryInto};
-use std::io::{Read, Seek, SeekFrom};
+use std::fs::File;
+use std::io::{BufRead, Read, Seek, SeekFrom};
 
 static PAK_MAIN_KEY_MOD: Lazy<Option<Vec<u8>>> = Lazy::new(|| None);
 
@@ -48,10 +51,44 @@ impl PakFileIndex {
     pub fn short_string(&self) -> String {
         format!("{:02}-{:06}", self.version, self.index)
     }
+
+    /// Which PAK file this is in
+    pub fn version(&self) -> usize {
+        self.version
+    }
+}
+
+/// A problem found in a PAK entry by `PakReader::verify`
+#[derive(Debug)]
+pub struct PakIssue {
+    pub index: PakFileIndex,
+    pub message: String,
+}
+
+/// All entries with the same path hash across PAK files
+#[derive(Debug, Clone)]
+pub struct PakLayer {
+    pub hash: u64,
+    /// Sorted by PAK order. The last one is what `find_file` returns.
+    pub entries: Vec<PakFileIndex>,
+}
+
+impl PakLayer {
+    pub fn winner(&self) -> PakFileIndex {
+        *self.entries.last().unwrap()
+    }
+}
+
+/// Named vs unnamed entries in a PAK file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathCoverage {
+    pub named: usize,
+    pub total: usize,
 }
 
 #[derive(Debug)]
 struct PakEntry {
+    hash: u64,
     offset: u64,
     len_compressed: u64,
     len: u64,
@@ -67,19 +104,114 @@ struct PakFile<F> {
     entries: Vec<PakEntry>,
 }
 
+/// Storage of a PAK file that can be read at any offset through a shared reference,
+/// so that entries can be read from multiple threads.
+pub trait PakSource: Sync {
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<()>;
+    fn source_len(&self) -> std::io::Result<u64>;
+}
+
+fn read_slice_at(data: &[u8], buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    let src = usize::try_from(offset)
+        .ok()
+        .and_then(|begin| data.get(begin..begin.checked_add(buf.len())?))
+        .ok_or(std::io::ErrorKind::UnexpectedEof)?;
+    buf.copy_from_slice(src);
+    Ok(())
+}
+
+impl PakSource for Mmap {
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+        read_slice_at(self, buf, offset)
+    }
+    fn source_len(&self) -> std::io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+impl PakSource for Vec<u8> {
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+        read_slice_at(self, buf, offset)
+    }
+    fn source_len(&self) -> std::io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+}
+
+impl PakSource for File {
+    #[cfg(unix)]
+    fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+        std::os::unix::fs::FileExt::read_exact_at(self, buf, offset)
+    }
+    #[cfg(windows)]
+    fn read_exact_at(&self, mut buf: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+        while !buf.is_empty() {
+            match std::os::windows::fs::FileExt::seek_read(self, buf, offset)? {
+                0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                n => {
+                    buf = &mut buf[n..];
+                    offset += n as u64;
+                }
+            }
+        }
+        Ok(())
+    }
+    fn source_len(&self) -> std::io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+}
+
+/// Read + Seek over a PakSource, for parsing the header
+struct SourceReader<'a, F> {
+    source: &'a F,
+    pos: u64,
+}
+
+impl<'a, F: PakSource> Read for SourceReader<'a, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.source.source_len()?;
+        let available = len.saturating_sub(self.pos).min(buf.len() as u64) as usize;
+        self.source.read_exact_at(&mut buf[..available], self.pos)?;
+        self.pos += available as u64;
+        Ok(available)
+    }
+}
+
+impl<'a, F: PakSource> Seek for SourceReader<'a, F> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(d) => self.source.source_len()?.checked_add_signed(d),
+        };
+        self.pos = new_pos.ok_or(std::io::ErrorKind::InvalidInput)?;
+        Ok(self.pos)
+    }
+}
+
 #[derive(Debug)]
 pub struct PakReader<F> {
     files: Vec<PakFile<F>>,
     hash_map: HashMap<u64, PakFileIndex>,
+    /// All paths added to the dictionary, whether they exist in the PAK or not
+    known_paths: BTreeSet<String>,
+    /// Names resolved from known_paths, including "streaming/" prefix and language suffix
+    names: HashMap<PakFileIndex, String>,
+    /// Paths as stored in the PAK, e.g. "natives/STM/...user.2"
+    full_paths: HashMap<PakFileIndex, String>,
 }
 
-impl<F: Read + Seek> PakReader<F> {
+impl<F: PakSource> PakReader<F> {
     pub fn new(raw_files: Vec<F>) -> Result<PakReader<F>> {
         let mut hash_map = HashMap::new();
         let files = raw_files
             .into_iter()
             .enumerate()
-            .map(|(version, mut file)| {
+            .map(|(version, source)| {
+                let mut file = SourceReader {
+                    source: &source,
+                    pos: 0,
+                };
                 let magic = file.read_magic()?;
                 if &magic != b"KPKA" {
                     bail!("Wrong magic for PAK file");
@@ -134,6 +266,7 @@ impl<F: Read + Seek> PakReader<F> {
                         let flag = entry.read_u8()?;
                         let encryption = entry.read_u8()?;
                         Ok(PakEntry {
+                            hash,
                             offset,
                             len_compressed,
                             len,
@@ -144,20 +277,38 @@ impl<F: Read + Seek> PakReader<F> {
                     })
                     .collect::<Result<Vec<PakEntry>>>()?;
 
-                Ok(PakFile { file, entries })
+                Ok(PakFile {
+                    file: source,
+                    entries,
+                })
             })
             .collect::<Result<Vec<PakFile<F>>>>()?;
 
-        Ok(PakReader { files, hash_map })
+        Ok(PakReader {
+            files,
+            hash_map,
+            known_paths: BTreeSet::new(),
+            names: HashMap::new(),
+            full_paths: HashMap::new(),
+        })
     }
 
-    fn find_file_internal(&mut self, full_path: String) -> Option<PakFileIndex> {
+    fn find_file_internal(&self, full_path: String) -> Option<PakFileIndex> {
         let hash: u64 = u64::from(hash_as_utf16(&full_path.to_lowercase()))
             | (u64::from(hash_as_utf16(&full_path.to_uppercase())) << 32);
         self.hash_map.get(&hash).cloned()
     }
 
-    pub fn find_file_i18n(&mut self, mut path: &str) -> Result<Vec<I18nPakFileIndex>> {
+    pub fn find_file_i18n(&self, path: &str) -> Result<Vec<I18nPakFileIndex>> {
+        Ok(self
+            .find_file_i18n_full(path)?
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect())
+    }
+
+    /// Like find_file_i18n, but also returns the full path in the PAK of each file
+    fn find_file_i18n_full(&self, mut path: &str) -> Result<Vec<(I18nPakFileIndex, String)>> {
         if path.starts_with('@') {
             path = &path[1..];
         }
@@ -183,8 +334,8 @@ impl<F: Read + Seek> PakReader<F> {
                 for full_path in &full_paths {
                     let dot = if language.is_empty() { "" } else { "." };
                     let with_language = format!("{full_path}{dot}{language}");
-                    if let Some(index) = self.find_file_internal(with_language) {
-                        result.push(I18nPakFileIndex { language, index });
+                    if let Some(index) = self.find_file_internal(with_language.clone()) {
+                        result.push((I18nPakFileIndex { language, index }, with_language));
                         break;
                     }
                 }
@@ -196,7 +347,7 @@ impl<F: Read + Seek> PakReader<F> {
         Ok(vec![])
     }
 
-    pub fn find_file(&mut self, path: &str) -> Result<PakFileIndex> {
+    pub fn find_file(&self, path: &str) -> Result<PakFileIndex> {
         Ok(self
             .find_file_i18n(path)?
             .first()
@@ -204,8 +355,8 @@ impl<F: Read + Seek> PakReader<F> {
             .index)
     }
 
-    pub fn read_file(&mut self, file_index: PakFileIndex) -> Result<Vec<u8>> {
-        let PakFile { file, entries } = &mut self.files[file_index.version];
+    pub fn read_file(&self, file_index: PakFileIndex) -> Result<Vec<u8>> {
+        let PakFile { file, entries } = &self.files[file_index.version];
         let PakEntry {
             offset,
             len_compressed,
@@ -215,9 +366,8 @@ impl<F: Read + Seek> PakReader<F> {
             ..
         } = entries[file_index.index];
 
-        file.seek(SeekFrom::Start(offset))?;
         let mut data = vec![0; len_compressed.try_into()?];
-        file.read_exact(&mut data)?;
+        file.read_exact_at(&mut data, offset)?;
 
         match encryption {
             0 => {}
@@ -272,7 +422,7 @@ impl<F: Read + Seek> PakReader<F> {
         }
     }
 
-    pub fn read_file_at(&mut self, version: usize, index: usize) -> Result<Vec<u8>> {
+    pub fn read_file_at(&self, version: usize, index: usize) -> Result<Vec<u8>> {
         if version > self.files.len() {
             bail!("Version out of bound")
         }
@@ -282,19 +432,254 @@ impl<F: Read + Seek> PakReader<F> {
         self.read_file(PakFileIndex { version, index })
     }
 
+    /// Adds a path to the dictionary, and names all files it resolves to.
+    /// Returns the number of files that get a new name.
+    pub fn add_path(&mut self, path: &str) -> Result<usize> {
+        let path = path.strip_prefix('@').unwrap_or(path);
+        if !self.known_paths.insert(path.to_owned()) {
+            return Ok(0);
+        }
+        let Some(dot) = path.rfind('.') else {
+            return Ok(0);
+        };
+        if !SUFFIX_MAP.contains_key(&path[dot + 1..]) {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        let streaming_path = "streaming/".to_owned() + path;
+        for path in [path, &streaming_path] {
+            for (i18n_index, full_path) in self.find_file_i18n_full(path)? {
+                self.full_paths.entry(i18n_index.index).or_insert(full_path);
+                let name = if i18n_index.language.is_empty() {
+                    path.to_owned()
+                } else {
+                    format!("{}.{}", path, i18n_index.language)
+                };
+                if let std::collections::hash_map::Entry::Vacant(entry) =
+                    self.names.entry(i18n_index.index)
+                {
+                    entry.insert(name);
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Loads a path list, one path per line.
+    /// Output of SearchPath ("path $ ...") is also accepted.
+    /// Returns the number of files that get a new name.
+    pub fn load_path_list(&mut self, list: impl BufRead) -> Result<usize> {
+        let mut count = 0;
+        for line in list.lines() {
+            let line = line?;
+            let path = line.split(" $ ").next().unwrap_or("").trim();
+            if path.is_empty() {
+                continue;
+            }
+            count += self
+                .add_path(path)
+                .with_context(|| format!("Bad path {path}"))?;
+        }
+        Ok(count)
+    }
+
+    pub fn is_known_path(&self, path: &str) -> bool {
+        self.known_paths
+            .contains(path.strip_prefix('@').unwrap_or(path))
+    }
+
+    pub fn known_paths(&self) -> impl Iterator<Item = &str> {
+        self.known_paths.iter().map(|s| s.as_str())
+    }
+
+    pub fn file_name(&self, index: PakFileIndex) -> Option<&str> {
+        self.names.get(&index).map(|s| s.as_str())
+    }
+
+    /// The path as stored in the PAK, if known
+    pub fn full_path(&self, index: PakFileIndex) -> Option<&str> {
+        self.full_paths.get(&index).map(|s| s.as_str())
+    }
+
+    /// Path hash of the entry
+    pub fn file_hash(&self, index: PakFileIndex) -> u64 {
+        self.files[index.version].entries[index.index].hash
+    }
+
+    /// Size of the entry after decompression
+    pub fn file_len(&self, index: PakFileIndex) -> u64 {
+        self.files[index.version].entries[index.index].len
+    }
+
+    /// The name if known, or the index otherwise
+    pub fn display_name(&self, index: PakFileIndex) -> String {
+        self.file_name(index)
+            .map_or_else(|| index.short_string(), str::to_owned)
+    }
+
+    /// Named vs unnamed entries, for each PAK file.
+    /// Entries overridden by later PAK files are not counted.
+    pub fn path_coverage(&self) -> Vec<PathCoverage> {
+        let mut coverage = vec![PathCoverage::default(); self.files.len()];
+        for index in self.hash_map.values() {
+            let c = &mut coverage[index.version];
+            c.total += 1;
+            if self.names.contains_key(index) {
+                c.named += 1;
+            }
+        }
+        coverage
+    }
+
     pub fn all_file_indexs(&self) -> Vec<PakFileIndex> {
         let mut v: Vec<_> = self.hash_map.values().cloned().collect();
         v.sort();
         v
     }
 
-    pub fn sha256(&mut self) -> Result<Vec<String>> {
+    /// Checks every entry: data range, overlapping with other entries,
+    /// supported encryption and format, and the size after decompression.
+    pub fn verify(&self) -> Result<Vec<PakIssue>> {
+        let mut issues = vec![];
+        for version in 0..self.files.len() {
+            let PakFile { file, entries } = &self.files[version];
+            let file_len = file.source_len()?;
+            let mut issue = |index, message| {
+                issues.push(PakIssue {
+                    index: PakFileIndex { version, index },
+                    message,
+                })
+            };
+
+            let mut by_offset: Vec<usize> = (0..entries.len()).collect();
+            by_offset.sort_by_key(|&i| entries[i].offset);
+            // The entry reaching furthest so far, as (end, index)
+            let mut furthest: Option<(u64, usize)> = None;
+            for &i in &by_offset {
+                let entry = &entries[i];
+                if entry.len_compressed == 0 {
+                    continue;
+                }
+                let end = entry.offset.checked_add(entry.len_compressed);
+                if end.is_none_or(|end| end > file_len) {
+                    issue(
+                        i,
+                        format!(
+                            "Data range 0x{:X}+0x{:X} exceeds file size 0x{file_len:X}",
+                            entry.offset, entry.len_compressed
+                        ),
+                    );
+                    continue;
+                }
+                let end = entry.offset + entry.len_compressed;
+                if let Some((furthest_end, p)) = furthest {
+                    if furthest_end > entry.offset {
+                        issue(i, format!("Data overlaps with entry {p}"));
+                    }
+                }
+                if furthest.is_none_or(|(furthest_end, _)| end > furthest_end) {
+                    furthest = Some((end, i));
+                }
+            }
+
+            for (i, entry) in entries.iter().enumerate() {
+                if entry.encryption > 1 {
+                    issue(i, format!("Unsupported encryption {}", entry.encryption));
+                }
+                if !matches!(entry.format, 0 | 1 | 0x11 | 2) {
+                    issue(i, format!("Unsupported format {}", entry.format));
+                }
+            }
+        }
+
+        let reported: BTreeSet<PakFileIndex> = issues.iter().map(|i| i.index).collect();
+        for version in 0..self.files.len() {
+            let count = self.files[version].entries.len();
+            issues.par_extend((0..count).into_par_iter().filter_map(|index| {
+                let file_index = PakFileIndex { version, index };
+                if reported.contains(&file_index) {
+                    return None;
+                }
+                let e = self.read_file(file_index).err()?;
+                Some(PakIssue {
+                    index: file_index,
+                    message: format!("{e:#}"),
+                })
+            }));
+            eprintln!("Verified PAK {version:02}: {count} entries");
+        }
+
+        issues.sort_by_key(|i| i.index);
+        Ok(issues)
+    }
+
+    /// Groups entries of all PAK files by path hash, sorted by hash
+    pub fn layers(&self) -> Vec<PakLayer> {
+        let mut layers: BTreeMap<u64, Vec<PakFileIndex>> = BTreeMap::new();
+        for (version, file) in self.files.iter().enumerate() {
+            for (index, entry) in file.entries.iter().enumerate() {
+                layers
+                    .entry(entry.hash)
+                    .or_default()
+                    .push(PakFileIndex { version, index });
+            }
+        }
+        layers
+            .into_iter()
+            .map(|(hash, entries)| PakLayer { hash, entries })
+            .collect()
+    }
+
+    /// Entries that exist in an earlier PAK but are overridden by a later one,
+    /// as (overridden, winner)
+    pub fn overridden_entries(&self) -> Vec<(PakFileIndex, PakFileIndex)> {
+        self.layers()
+            .into_iter()
+            .flat_map(|layer| {
+                let winner = layer.winner();
+                layer
+                    .entries
+                    .into_iter()
+                    .filter(move |&e| e != winner)
+                    .map(move |e| (e, winner))
+            })
+            .collect()
+    }
+
+    /// Whether any overridden entry in the layer has different content from the winner
+    pub fn layer_differs(&self, layer: &PakLayer) -> Result<bool> {
+        let winner = layer.winner();
+        let len = |reader: &Self, i: PakFileIndex| reader.files[i.version].entries[i.index].len;
+        if layer
+            .entries
+            .iter()
+            .any(|&e| len(self, e) != len(self, winner))
+        {
+            return Ok(true);
+        }
+        let winner_data = self.read_file(winner)?;
+        for &entry in &layer.entries[..layer.entries.len() - 1] {
+            if self.read_file(entry)? != winner_data {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn sha256(&self) -> Result<Vec<String>> {
         self.files
-            .iter_mut()
+            .iter()
             .map(|file| {
                 let mut hasher = Sha256::new();
-                file.file.rewind()?;
-                std::io::copy(&mut file.file, &mut hasher)?;
+                std::io::copy(
+                    &mut SourceReader {
+                        source: &file.file,
+                        pos: 0,
+                    },
+                    &mut hasher,
+                )?;
                 let hash = hasher.finalize();
                 Ok(format!("{hash:x}"))
             })
@@ -1413,7 +1413,7 @@ rsz_struct! {
 // snow.stage.StageTimeChange.StageTime
 rsz_struct! {
     #[rsz()]
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Serialize, Clone)]
     pub struct StageTime {
         pub hour: u32,
         pub minute: u32,
@@ -1423,7 +1423,7 @@ rsz_struct! {
 
 rsz_struct! {
     #[rsz("snow.envCreature.EnvironmentCreaturePhoto")]
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Serialize, Clone)]
     pub struct EnvironmentCreaturePhoto {
         pub enabled: bool,
         pub start_time: StageTime,
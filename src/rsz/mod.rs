@@ -533,7 +533,7 @@ pub enum ExternUser<T> {
 impl<T: FromUser> ExternUser<T> {
     pub fn load<'a>(
         &'a mut self,
-        pak: &'_ mut crate::pak::PakReader<impl Read + Seek>,
+        pak: &'_ crate::pak::PakReader<impl crate::pak::PakSource>,
         version_hint: Option<u32>,
     ) -> Result<&'a mut T> {
         match self {
@@ -1078,7 +1078,7 @@ rsz_struct! {
 // snow.player.PlayerWeaponType
 rsz_enum! {
     #[rsz(i32)]
-    #[derive(Debug, Serialize, Clone, Copy)]
+    #[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
     pub enum PlayerWeaponType {
         GreatSword = 0,
         SlashAxe = 1,
@@ -152,8 +152,8 @@ rsz_struct! {
     #[derive(Debug, Serialize)]
     pub struct ViaMesh {
         render_output_id: u32,
-        enabled: bool,
-        mesh_path: Option<String>,
+        pub enabled: bool,
+        pub mesh_path: Option<String>,
         mdf2_path: Option<String>,
         static_mesh: bool, // x3d0
         small_object_culling_factor: f32, // x35c
@@ -761,6 +761,7 @@ impl_tobase!(
 );
 impl_base!(LightBowgunBaseUserDataParam, BulletWeaponBaseUserDataParam);
 impl_maybetobase_none!(LightBowgunBaseUserDataParam, ElementWeaponBaseData);
+impl_maybetobase_none!(LightBowgunBaseUserDataParam, CloseRangeWeaponBaseData);
 impl_maybetobase_none!(LightBowgunBaseUserDataParam, DualBladesBaseUserDataParam);
 
 rsz_struct! {
@@ -797,6 +798,7 @@ impl_tobase!(
 );
 impl_base!(HeavyBowgunBaseUserDataParam, BulletWeaponBaseUserDataParam);
 impl_maybetobase_none!(HeavyBowgunBaseUserDataParam, ElementWeaponBaseData);
+impl_maybetobase_none!(HeavyBowgunBaseUserDataParam, CloseRangeWeaponBaseData);
 impl_maybetobase_none!(HeavyBowgunBaseUserDataParam, DualBladesBaseUserDataParam);
 
 rsz_struct! {
@@ -837,6 +839,7 @@ impl_tobase!(
 );
 impl_base!(BowBaseUserDataParam, ElementWeaponBaseData);
 impl_maybetobase_none!(BowBaseUserDataParam, DualBladesBaseUserDataParam);
+impl_maybetobase_none!(BowBaseUserDataParam, CloseRangeWeaponBaseData);
 
 rsz_struct! {
     #[rsz("snow.equip.BowBaseUserData",
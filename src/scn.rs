@@ -378,7 +378,7 @@ pub struct Scene {
 }
 
 impl Scene {
-    pub fn new<F: Read + Seek>(pak: &mut PakReader<F>, path: &str) -> Result<Scene> {
+    pub fn new<F: PakSource>(pak: &PakReader<F>, path: &str) -> Result<Scene> {
         let index = pak.find_file(path)?;
         let content = pak.read_file(index)?;
         let scn = Scn::new(Cursor::new(content))?;
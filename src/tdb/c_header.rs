@@ -0,0 +1,492 @@
+//! C header output of TDB, for importing types into IDA, Binary Ninja or Ghidra.
+//!
+//! The layout rules follow misc/ghidra_importTdb.py: value types are embedded
+//! with their `len`, reference types are pointers, and objects are
+//! `runtime_len` bytes with fields shifted by the object header.
+
+use super::*;
+
+const C_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "this",
+    "class",
+    "template",
+    "new",
+    "delete",
+    "namespace",
+    "operator",
+    "private",
+    "public",
+    "protected",
+    "virtual",
+];
+
+fn c_identifier(name: &str) -> String {
+    let mut s: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.insert(0, '_');
+    }
+    if C_KEYWORDS.contains(&s.as_str()) {
+        s.push('_');
+    }
+    s
+}
+
+fn primitive_c_type(system_type: u64) -> Option<&'static str> {
+    Some(match system_type {
+        13 => "uint8_t",
+        14 => "int8_t",
+        15 => "uint16_t", // char
+        16 => "int16_t",
+        17 => "uint16_t",
+        18 => "int32_t",
+        19 => "uint32_t",
+        20 => "int64_t",
+        21 => "uint64_t",
+        22 => "float",
+        23 => "double",
+        24 => "intptr_t",
+        25 => "uintptr_t",
+        26 => "bool",
+        31 => "void",
+        _ => return None,
+    })
+}
+
+struct CHeaderGen<'a> {
+    tdb: &'a Tdb,
+    options: &'a crate::TdbOptions,
+    names: Vec<String>,
+}
+
+impl<'a> CHeaderGen<'a> {
+    fn new(tdb: &'a Tdb, options: &'a crate::TdbOptions) -> Self {
+        // Sanitized names can collide, e.g. for generic instantiations
+        let mut used = HashSet::new();
+        let mut names: Vec<String> = tdb
+            .types
+            .iter()
+            .map(|t| c_identifier(&t.full_name))
+            .collect();
+        for (name, t) in names.iter_mut().zip(&tdb.types) {
+            if !used.insert(name.clone()) {
+                *name = format!("{name}_{:08X}", t.hash);
+            }
+        }
+        CHeaderGen {
+            tdb,
+            options,
+            names,
+        }
+    }
+
+    /// Whether the type is defined as a struct in the header
+    fn has_struct(&self, ti: usize) -> bool {
+        let t = &self.tdb.types[ti];
+        if primitive_c_type(t.system_type).is_some() || self.tdb.is_enum(ti) {
+            return false;
+        }
+        if t.full_name.contains('!')
+            || matches!(t.generics, Some(Generics::Template { .. }))
+            || t.flags.contains(TypeFlag::INTERFACE)
+        {
+            return false;
+        }
+        if self.options.no_system && t.full_name.starts_with("System.") {
+            return false;
+        }
+        if self.options.no_compound && (t.ti_dearray.is_some() || t.full_name.contains('<')) {
+            return false;
+        }
+        true
+    }
+
+    fn size(&self, ti: usize) -> usize {
+        let t = &self.tdb.types[ti];
+        if self.tdb.is_value_type(ti) {
+            t.len
+        } else {
+            t.runtime_len
+        }
+    }
+
+    /// C type used when the type appears in a field or a parameter
+    fn c_type(&self, ti: usize) -> String {
+        let t = &self.tdb.types[ti];
+        if let Some(primitive) = primitive_c_type(t.system_type) {
+            return primitive.to_owned();
+        }
+        if self.tdb.is_enum(ti) {
+            if let Some(underlying) = self.tdb.enum_underlying(ti) {
+                return self.c_type(underlying);
+            }
+        }
+        match (self.tdb.is_value_type(ti), self.has_struct(ti)) {
+            (true, true) => format!("struct {}", self.names[ti]),
+            (false, true) => format!("struct {}*", self.names[ti]),
+            _ => "void*".to_owned(),
+        }
+    }
+
+    /// Instance fields including inherited ones, as (offset, type, size, name).
+    /// Base fields shadowed by a derived field, or by the object header,
+    /// are prefixed with the name of the base type.
+    fn layout(&self, ti: usize) -> Vec<(usize, String, usize, String)> {
+        let header_len = if self.tdb.is_value_type(ti) {
+            0
+        } else {
+            self.tdb.types[ti]
+                .runtime_len
+                .saturating_sub(self.tdb.types[ti].len)
+        };
+
+        let mut fields = vec![];
+        let mut used = HashSet::new();
+        if header_len >= 16 {
+            let vtable = if self.tdb.types[ti].vtable.is_empty() {
+                "void*".to_owned()
+            } else {
+                format!("struct {}_vtable*", self.names[ti])
+            };
+            fields.push((0, vtable, 8, "_vtable".to_owned()));
+            fields.push((8, "void*".to_owned(), 8, "_lock".to_owned()));
+            used.insert("_vtable".to_owned());
+            used.insert("_lock".to_owned());
+        }
+
+        let mut current = Some(ti);
+        while let Some(cur) = current {
+            for field in &self.tdb.types[cur].fields {
+                if field.flags.contains(FieldAttribute::STATIC) {
+                    continue;
+                }
+                let offset = field.position as usize + header_len;
+                let mut name = c_identifier(&field.name);
+                if used.contains(&name) {
+                    name = format!("{}_{name}", self.names[cur]);
+                }
+                while !used.insert(name.clone()) {
+                    name.push('_');
+                }
+                if self.tdb.is_value_type(field.ti) && !self.tdb.is_enum(field.ti) {
+                    let size = self.size(field.ti);
+                    let c_type = self.c_type(field.ti);
+                    if c_type == "void*" {
+                        // Unknown value type: keep the space as raw bytes
+                        fields.push((
+                            offset,
+                            "uint8_t".to_owned(),
+                            size,
+                            format!("{name}[{size}]"),
+                        ))
+                    } else {
+                        fields.push((offset, c_type, size, name))
+                    }
+                } else if self.tdb.is_enum(field.ti) {
+                    let underlying = self.tdb.enum_underlying(field.ti);
+                    let size = underlying.map_or(4, |u| self.size(u));
+                    fields.push((offset, self.c_type(field.ti), size, name))
+                } else {
+                    fields.push((offset, self.c_type(field.ti), 8, name))
+                }
+            }
+            current = self.tdb.types[cur].ti_base;
+        }
+
+        fields.sort_by_key(|f| f.0);
+        fields
+    }
+
+    /// Orders value types before the structs that embed them
+    fn struct_order(&self) -> Vec<usize> {
+        fn visit(header: &CHeaderGen, ti: usize, visited: &mut [bool], order: &mut Vec<usize>) {
+            if visited[ti] {
+                return;
+            }
+            visited[ti] = true;
+            let mut current = Some(ti);
+            while let Some(cur) = current {
+                for field in &header.tdb.types[cur].fields {
+                    if !field.flags.contains(FieldAttribute::STATIC)
+                        && header.tdb.is_value_type(field.ti)
+                        && header.has_struct(field.ti)
+                    {
+                        visit(header, field.ti, visited, order);
+                    }
+                }
+                current = header.tdb.types[cur].ti_base;
+            }
+            order.push(ti);
+        }
+
+        let mut visited = vec![false; self.tdb.types.len()];
+        let mut order = vec![];
+        for ti in 0..self.tdb.types.len() {
+            if self.has_struct(ti) {
+                visit(self, ti, &mut visited, &mut order);
+            }
+        }
+        order
+    }
+
+    /// Writes the literals that fit in `int` as a C enum, and the others as constants
+    /// of the underlying type. Enums without literals are skipped.
+    fn write_enum(&self, output: &mut impl Write, ti: usize) -> Result<()> {
+        let Some(underlying) = self.tdb.enum_underlying(ti) else {
+            return Ok(());
+        };
+        let size = self.size(underlying);
+        let signed = matches!(self.tdb.types[underlying].system_type, 14 | 16 | 18 | 20);
+        let name = &self.names[ti];
+
+        let mut in_range = vec![];
+        let mut out_of_range = vec![];
+        for field in &self.tdb.types[ti].fields {
+            let Some(ValueInfo::Bytes(bytes)) = &field.value else {
+                continue;
+            };
+            if !field.flags.contains(FieldAttribute::LITERAL) || bytes.len() != size {
+                continue;
+            }
+            let mut buf = [0; 8];
+            buf[..size].copy_from_slice(bytes);
+            let value = u64::from_le_bytes(buf);
+            let field_name = format!("{name}_{}", c_identifier(&field.name));
+            if signed {
+                let value = if size < 8 && value >> (size * 8 - 1) != 0 {
+                    (value | (u64::MAX << (size * 8))) as i64
+                } else {
+                    value as i64
+                };
+                if i32::try_from(value).is_ok() {
+                    in_range.push((field_name, format!("{value}")));
+                } else if value == i64::MIN {
+                    out_of_range.push((field_name, "INT64_MIN".to_owned()));
+                } else {
+                    out_of_range.push((field_name, format!("{value}LL")));
+                }
+            } else if value <= i32::MAX as u64 {
+                in_range.push((field_name, format!("{value}")));
+            } else {
+                out_of_range.push((field_name, format!("{value}ULL")));
+            }
+        }
+        if in_range.is_empty() && out_of_range.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(output, "// {}", self.tdb.types[ti].full_name)?;
+        if !in_range.is_empty() {
+            writeln!(output, "enum {name} {{")?;
+            for (field_name, value) in in_range {
+                writeln!(output, "    {field_name} = {value},")?;
+            }
+            writeln!(output, "}};")?;
+        }
+        let c_type = self.c_type(underlying);
+        for (field_name, value) in out_of_range {
+            writeln!(output, "static const {c_type} {field_name} = {value};")?;
+        }
+        writeln!(output)?;
+        Ok(())
+    }
+
+    fn write_struct(&self, output: &mut impl Write, ti: usize) -> Result<()> {
+        let t = &self.tdb.types[ti];
+        let name = &self.names[ti];
+        let size = self.size(ti);
+        writeln!(output, "// {} (hash {:08X})", t.full_name, t.hash)?;
+        writeln!(output, "struct {name} {{")?;
+        let mut pos = 0;
+        for (offset, c_type, len, field_name) in self.layout(ti) {
+            if len == 0 {
+                continue;
+            }
+            if offset < pos {
+                writeln!(
+                    output,
+                    "    // overlapped: {c_type} {field_name} @ 0x{offset:X}"
+                )?;
+                continue;
+            }
+            if offset > pos {
+                writeln!(output, "    uint8_t _pad_{pos:X}[0x{:X}];", offset - pos)?;
+            }
+            writeln!(output, "    {c_type} {field_name}; // 0x{offset:X}")?;
+            pos = offset + len;
+        }
+        if pos < size {
+            writeln!(output, "    uint8_t _pad_{pos:X}[0x{:X}];", size - pos)?;
+        }
+        writeln!(output, "}};")?;
+        writeln!(output)?;
+        Ok(())
+    }
+
+    /// `declarator` is the function name, or `(*name)` for a function pointer
+    fn method_prototype(&self, ti: usize, method: &MethodInfo, declarator: &str) -> String {
+        let mut params = vec!["void* vmctx".to_owned()];
+        if !method.flags.contains(MethodAttribute::STATIC) {
+            let this = if self.tdb.is_value_type(ti) && self.has_struct(ti) {
+                format!("struct {}*", self.names[ti])
+            } else {
+                self.c_type(ti)
+            };
+            params.push(format!("{this} this_"));
+        }
+        for param in &method.params {
+            params.push(format!(
+                "{} {}",
+                self.c_type(param.ti),
+                c_identifier(&param.name)
+            ));
+        }
+        format!(
+            "{} {declarator}({})",
+            self.c_type(method.ret.ti),
+            params.join(", ")
+        )
+    }
+
+    fn write_vtable(&self, output: &mut impl Write, ti: usize) -> Result<()> {
+        let t = &self.tdb.types[ti];
+        if t.vtable.is_empty() {
+            return Ok(());
+        }
+        writeln!(output, "struct {}_vtable {{", self.names[ti])?;
+        for (i, slot) in t.vtable.iter().enumerate() {
+            let method = match slot.method {
+                VtableSlotMethod::Mi(mi) => self.tdb.types[slot.ti].methods.get(mi),
+                VtableSlotMethod::Monomorphized(mmi) => {
+                    self.tdb.types[slot.ti].monomorphized_methods.get(&mmi)
+                }
+            };
+            match method {
+                Some(method) => {
+                    let declarator = format!("(*{}_{i})", c_identifier(&method.name));
+                    writeln!(
+                        output,
+                        "    {};",
+                        self.method_prototype(slot.ti, method, &declarator)
+                    )?
+                }
+                None => writeln!(output, "    void* slot_{i};")?,
+            }
+        }
+        writeln!(output, "}};")?;
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+impl Tdb {
+    pub fn write_c_header(&self, path: &str, options: &crate::TdbOptions) -> Result<()> {
+        let mut output = std::io::BufWriter::new(File::create(path)?);
+        let header = CHeaderGen::new(self, options);
+
+        writeln!(output, "#pragma once")?;
+        writeln!(output, "#include <stdbool.h>")?;
+        writeln!(output, "#include <stdint.h>")?;
+        writeln!(output)?;
+
+        for ti in 0..self.types.len() {
+            if header.has_struct(ti) {
+                writeln!(output, "struct {};", header.names[ti])?;
+                if !self.types[ti].vtable.is_empty() {
+                    writeln!(output, "struct {}_vtable;", header.names[ti])?;
+                }
+            }
+        }
+        writeln!(output)?;
+
+        for ti in 0..self.types.len() {
+            if header.tdb.is_enum(ti) {
+                header.write_enum(&mut output, ti)?;
+            }
+        }
+
+        writeln!(output, "#pragma pack(push, 1)")?;
+        writeln!(output)?;
+        for ti in header.struct_order() {
+            header.write_struct(&mut output, ti)?;
+            header.write_vtable(&mut output, ti)?;
+        }
+        writeln!(output, "#pragma pack(pop)")?;
+        writeln!(output)?;
+
+        for ti in 0..self.types.len() {
+            if !header.has_struct(ti) {
+                continue;
+            }
+            // C has no overloading, so number the overloads after the first one,
+            // skipping numbers that collide with other method names
+            let methods: Vec<&MethodInfo> = self.types[ti]
+                .methods
+                .iter()
+                .filter(|method| !method.flags.contains(MethodAttribute::ABSTRACT))
+                .collect();
+            let mut used: HashSet<String> = methods
+                .iter()
+                .map(|method| format!("{}__{}", header.names[ti], c_identifier(&method.name)))
+                .collect();
+            let mut first = HashSet::new();
+            for method in methods {
+                let base = format!("{}__{}", header.names[ti], c_identifier(&method.name));
+                let name = if first.insert(base.clone()) {
+                    base
+                } else {
+                    (1..)
+                        .map(|n| format!("{base}_{n}"))
+                        .find(|name| used.insert(name.clone()))
+                        .unwrap()
+                };
+                let prototype = header.method_prototype(ti, method, &name);
+                let address = if !options.no_runtime && method.runtime_address != 0 {
+                    format!(" // 0x{:08X}", method.runtime_address)
+                } else {
+                    "".to_owned()
+                };
+                writeln!(output, "{prototype};{address}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
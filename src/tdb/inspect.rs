@@ -0,0 +1,251 @@
+//! Decodes managed objects in a memory dump using TDB type information.
+//!
+//! Object layout follows misc/ghidra_importTdb.py: an object is `runtime_len` bytes,
+//! fields are shifted by the header (`runtime_len - len`), and array elements
+//! start 16 bytes after the array object.
+
+use super::*;
+
+/// Maximum number of array elements printed
+const MAX_ARRAY_PRINT: u32 = 64;
+
+struct Inspector<'a, F> {
+    tdb: &'a Tdb,
+    memory: F,
+    by_vtable: HashMap<u64, usize>,
+    by_type_def: HashMap<u64, usize>,
+    max_depth: usize,
+    visited: HashSet<u64>,
+}
+
+impl<'a, F: Read + Seek> Inspector<'a, F> {
+    /// Resolves the type of the object at `address` from its header.
+    ///
+    /// The first qword of an object points to the runtime vtable info of its type,
+    /// which in turn starts with the address of the TDB type definition.
+    fn object_type(&mut self, address: u64) -> Result<usize> {
+        self.memory.seek(SeekFrom::Start(address))?;
+        let info = self.memory.read_u64()?;
+        if let Some(&ti) = self.by_vtable.get(&info) {
+            return Ok(ti);
+        }
+        self.memory.seek(SeekFrom::Start(info))?;
+        let type_def = self.memory.read_u64()?;
+        self.by_type_def
+            .get(&type_def)
+            .copied()
+            .with_context(|| format!("Unknown object type at 0x{address:016X}"))
+    }
+
+    fn read_primitive(&mut self, system_type: u64) -> Result<Option<String>> {
+        let m = &mut self.memory;
+        Ok(Some(match system_type {
+            13 => m.read_u8()?.to_string(),
+            14 => m.read_i8()?.to_string(),
+            15 => {
+                let c = m.read_u16()?;
+                format!("{:?}", char::from_u32(c.into()).unwrap_or('?'))
+            }
+            16 => m.read_i16()?.to_string(),
+            17 => m.read_u16()?.to_string(),
+            18 => m.read_i32()?.to_string(),
+            19 => m.read_u32()?.to_string(),
+            20 => m.read_i64()?.to_string(),
+            21 => m.read_u64()?.to_string(),
+            22 => m.read_f32()?.to_string(),
+            23 => m.read_f64()?.to_string(),
+            24 | 25 => format!("0x{:X}", m.read_u64()?),
+            26 => (m.read_u8()? != 0).to_string(),
+            _ => return Ok(None),
+        }))
+    }
+
+    fn read_enum(&mut self, ti: usize, address: u64) -> Result<String> {
+        let tdb = self.tdb;
+        let underlying = tdb.enum_underlying(ti).context("Enum without value")?;
+        let size = tdb.types[underlying].len;
+        self.memory.seek(SeekFrom::Start(address))?;
+        let mut bytes = vec![0; size];
+        self.memory.read_exact(&mut bytes)?;
+        let name = tdb.types[ti].fields.iter().find(|f| {
+            f.flags.contains(FieldAttribute::LITERAL)
+                && matches!(&f.value, Some(ValueInfo::Bytes(b)) if *b == bytes)
+        });
+        self.memory.seek(SeekFrom::Start(address))?;
+        let value = self
+            .read_primitive(tdb.types[underlying].system_type)?
+            .unwrap_or_default();
+        Ok(match name {
+            Some(field) => format!("{}::{} ({value})", tdb.types[ti].name, field.name),
+            None => format!("{}({value})", tdb.types[ti].name),
+        })
+    }
+
+    fn read_string(&mut self, address: u64) -> Result<String> {
+        self.memory.seek(SeekFrom::Start(address + 0x10))?;
+        let len = self.memory.read_u32()?;
+        if len > 0x10000 {
+            bail!("String too long")
+        }
+        let mut utf16 = vec![];
+        for _ in 0..len {
+            utf16.push(self.memory.read_u16()?);
+        }
+        Ok(String::from_utf16_lossy(&utf16))
+    }
+
+    /// Prints a value of type `ti` stored at `address`.
+    /// For reference types, `address` is where the pointer is stored.
+    fn print_value(
+        &mut self,
+        output: &mut impl Write,
+        ti: usize,
+        address: u64,
+        depth: usize,
+    ) -> Result<()> {
+        let tdb = self.tdb;
+        let t = &tdb.types[ti];
+        if tdb.is_enum(ti) {
+            let value = self.read_enum(ti, address)?;
+            writeln!(output, "{value}")?;
+        } else if tdb.is_value_type(ti) {
+            self.memory.seek(SeekFrom::Start(address))?;
+            if let Some(value) = self.read_primitive(t.system_type)? {
+                writeln!(output, "{value}")?;
+            } else {
+                writeln!(output, "{} {{", t.full_name)?;
+                self.print_fields(output, ti, address, 0, depth + 1)?;
+                writeln!(output, "{:indent$}}}", "", indent = depth * 4)?;
+            }
+        } else {
+            self.memory.seek(SeekFrom::Start(address))?;
+            let pointer = self.memory.read_u64()?;
+            self.print_object(output, pointer, depth)?;
+        }
+        Ok(())
+    }
+
+    fn print_object(&mut self, output: &mut impl Write, address: u64, depth: usize) -> Result<()> {
+        if address == 0 {
+            writeln!(output, "null")?;
+            return Ok(());
+        }
+        let ti = match self.object_type(address) {
+            Ok(ti) => ti,
+            Err(e) => {
+                writeln!(output, "0x{address:016X} <{e}>")?;
+                return Ok(());
+            }
+        };
+        let tdb = self.tdb;
+        let t = &tdb.types[ti];
+
+        if t.full_name == "System.String" {
+            let s = self.read_string(address)?;
+            writeln!(output, "{s:?}")?;
+            return Ok(());
+        }
+
+        if depth >= self.max_depth || !self.visited.insert(address) {
+            writeln!(output, "{} @ 0x{address:016X} {{ .. }}", t.full_name)?;
+            return Ok(());
+        }
+
+        writeln!(output, "{} @ 0x{address:016X} {{", t.full_name)?;
+        let indent = (depth + 1) * 4;
+        if let Some(element) = t.ti_dearray {
+            let (runtime_len, element_len) = (
+                t.runtime_len as u64,
+                if tdb.is_value_type(element) {
+                    tdb.types[element].len as u64
+                } else {
+                    8
+                },
+            );
+            self.memory
+                .seek(SeekFrom::Start(address + runtime_len + 12))?;
+            let count = self.memory.read_u32()?;
+            writeln!(output, "{:indent$}count: {count}", "")?;
+            for i in 0..count.min(MAX_ARRAY_PRINT) {
+                write!(output, "{:indent$}[{i}]: ", "")?;
+                let element_address = address + runtime_len + 16 + u64::from(i) * element_len;
+                self.print_value(output, element, element_address, depth + 1)?;
+            }
+            if count > MAX_ARRAY_PRINT {
+                writeln!(output, "{:indent$}...", "")?;
+            }
+        } else {
+            let header_len = t.runtime_len.saturating_sub(t.len);
+            self.print_fields(output, ti, address, header_len, depth + 1)?;
+        }
+        writeln!(output, "{:indent$}}}", "", indent = depth * 4)?;
+        Ok(())
+    }
+
+    fn print_fields(
+        &mut self,
+        output: &mut impl Write,
+        ti: usize,
+        address: u64,
+        header_len: usize,
+        depth: usize,
+    ) -> Result<()> {
+        let tdb = self.tdb;
+        let mut chain = vec![];
+        let mut current = Some(ti);
+        while let Some(cur) = current {
+            chain.push(cur);
+            current = tdb.types[cur].ti_base;
+        }
+
+        let indent = depth * 4;
+        for &cur in chain.iter().rev() {
+            for field in &tdb.types[cur].fields {
+                if field.flags.contains(FieldAttribute::STATIC) {
+                    continue;
+                }
+                write!(output, "{:indent$}{}: ", "", field.name)?;
+                let field_address = address + (field.position as usize + header_len) as u64;
+                if let Err(e) = self.print_value(output, field.ti, field_address, depth) {
+                    writeln!(output, "<{e}>")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Tdb {
+    /// Prints the managed object at `address` and the objects it references,
+    /// up to `max_depth` levels.
+    pub fn inspect_object<F: Read + Seek>(
+        &self,
+        memory: F,
+        address: u64,
+        max_depth: usize,
+        mut output: impl Write,
+    ) -> Result<()> {
+        let mut inspector = Inspector {
+            tdb: self,
+            memory,
+            by_vtable: self
+                .types
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.runtime_vtable != 0)
+                .map(|(ti, t)| (t.runtime_vtable, ti))
+                .collect(),
+            by_type_def: self
+                .types
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.type_def_address != 0)
+                .map(|(ti, t)| (t.type_def_address, ti))
+                .collect(),
+            max_depth,
+            visited: HashSet::new(),
+        };
+        inspector.object_type(address)?;
+        inspector.print_object(&mut output, address, 0)
+    }
+}
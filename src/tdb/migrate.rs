@@ -0,0 +1,287 @@
+//! Compares RSZ field layouts between two TDB dumps and proposes
+//! a version-gated layout that reads data from both title updates.
+
+use super::rsz_code::*;
+use super::*;
+
+struct LayoutField {
+    name: String,
+    rust_type: String,
+    comment: Option<String>,
+    /// Full name of the field type in TDB, used for comparison
+    tdb_type: String,
+}
+
+impl PartialEq for LayoutField {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.tdb_type == other.tdb_type
+    }
+}
+
+fn layout(tdb: &Tdb, codegen: &RszCodeGen, ti: usize) -> Vec<LayoutField> {
+    let types = &tdb.types;
+    let mut fields = vec![];
+    if let Some(base) = codegen.flattened_base(ti) {
+        fields.push(LayoutField {
+            name: "base".to_owned(),
+            rust_type: format!("Flatten<{}>", codegen.rust_name(base)),
+            comment: None,
+            tdb_type: types[base].full_name.clone(),
+        })
+    }
+    for field in codegen.serialized_fields(ti) {
+        let (rust_type, comment) = codegen.field_type(field.ti, &mut vec![]);
+        fields.push(LayoutField {
+            name: field_name(&field.name),
+            rust_type,
+            comment,
+            tdb_type: types[field.ti].full_name.clone(),
+        })
+    }
+    fields
+}
+
+/// Indices of the longest common subsequence
+fn lcs(old: &[LayoutField], new: &[LayoutField]) -> Vec<(usize, usize)> {
+    let mut table = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i][j] = if old[i] == new[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            result.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+enum Gate {
+    Always,
+    Old,
+    New,
+}
+
+struct TypeMigration {
+    full_name: String,
+    rust_name: String,
+    old_crc: u32,
+    new_crc: u32,
+    added: Vec<String>,
+    removed: Vec<String>,
+    retyped: Vec<String>,
+    reordered: Vec<String>,
+    merged: Vec<(Gate, LayoutField)>,
+}
+
+fn migrate_type(
+    (old_tdb, old_codegen): (&Tdb, &RszCodeGen),
+    (new_tdb, new_codegen): (&Tdb, &RszCodeGen),
+    old_ti: usize,
+    new_ti: usize,
+) -> TypeMigration {
+    let old = layout(old_tdb, old_codegen, old_ti);
+    let new = layout(new_tdb, new_codegen, new_ti);
+    let common = lcs(&old, &new);
+
+    let mut added = vec![];
+    let mut removed = vec![];
+    let mut retyped = vec![];
+    let mut reordered = vec![];
+
+    let mut old_kept = vec![false; old.len()];
+    let mut new_kept = vec![false; new.len()];
+    for &(i, j) in &common {
+        old_kept[i] = true;
+        new_kept[j] = true;
+    }
+
+    for (field, _) in old.iter().zip(&old_kept).filter(|(_, kept)| !**kept) {
+        match new.iter().find(|f| f.name == field.name) {
+            None => removed.push(field.name.clone()),
+            Some(f) if f.tdb_type != field.tdb_type => retyped.push(format!(
+                "{}: {} -> {}",
+                field.name, field.tdb_type, f.tdb_type
+            )),
+            Some(_) => reordered.push(field.name.clone()),
+        }
+    }
+    for (field, _) in new.iter().zip(&new_kept).filter(|(_, kept)| !**kept) {
+        if !old.iter().any(|f| f.name == field.name) {
+            added.push(field.name.clone())
+        }
+    }
+
+    // Merge both layouts like a diff: between two common fields,
+    // old-only fields come before new-only fields
+    let new_names: HashSet<String> = new.iter().map(|f| f.name.clone()).collect();
+    let mut merged = vec![];
+    let mut old = old.into_iter().enumerate().peekable();
+    let mut new = new.into_iter().enumerate().peekable();
+    let bounds = common.iter().map(|&(i, j)| (Some(i), Some(j)));
+    for (ci, cj) in bounds.chain([(None, None)]) {
+        while let Some((i, _)) = old.peek() {
+            if Some(*i) == ci {
+                break;
+            }
+            let (_, mut field) = old.next().unwrap();
+            if new_names.contains(&field.name) {
+                // The name is taken by the field in the new layout
+                field.name += "_old";
+            }
+            merged.push((Gate::Old, field));
+        }
+        while let Some((j, _)) = new.peek() {
+            if Some(*j) == cj {
+                break;
+            }
+            let (_, field) = new.next().unwrap();
+            merged.push((Gate::New, field));
+        }
+        if ci.is_some() {
+            old.next();
+            let (_, field) = new.next().unwrap();
+            merged.push((Gate::Always, field));
+        }
+    }
+
+    TypeMigration {
+        full_name: new_tdb.types[new_ti].full_name.clone(),
+        rust_name: new_codegen.rust_name(new_ti),
+        old_crc: old_tdb.types[old_ti].crc,
+        new_crc: new_tdb.types[new_ti].crc,
+        added,
+        removed,
+        retyped,
+        reordered,
+        merged,
+    }
+}
+
+impl Tdb {
+    /// Compares the RSZ layout of types between `old` and `self` (new).
+    /// If `type_names` is empty, all types with changed CRC are compared.
+    pub fn write_rsz_migration(
+        &self,
+        old: &Tdb,
+        mut output: impl Write,
+        type_names: &[String],
+        old_version: u32,
+        new_version: u32,
+    ) -> Result<()> {
+        if new_version <= old_version {
+            bail!("New version {new_version} is not after old version {old_version}")
+        }
+        let old_codegen = RszCodeGen::new(old);
+        let new_codegen = RszCodeGen::new(self);
+
+        let pairs: Vec<(usize, usize)> = if type_names.is_empty() {
+            self.types
+                .iter()
+                .enumerate()
+                .filter_map(|(new_ti, new_type)| {
+                    let old_ti = old_codegen.find(&new_type.full_name).ok()?;
+                    let old_type = &old.types[old_ti];
+                    (old_type.crc != new_type.crc
+                        && matches!(
+                            new_codegen.kind(new_ti),
+                            TypeKind::ValueType | TypeKind::Class | TypeKind::UserData
+                        ))
+                    .then_some((old_ti, new_ti))
+                })
+                .collect()
+        } else {
+            type_names
+                .iter()
+                .map(|name| Ok((old_codegen.find(name)?, new_codegen.find(name)?)))
+                .collect::<Result<_>>()?
+        };
+
+        for (old_ti, new_ti) in pairs {
+            let m = migrate_type((old, &old_codegen), (self, &new_codegen), old_ti, new_ti);
+            let changed = !(m.added.is_empty()
+                && m.removed.is_empty()
+                && m.retyped.is_empty()
+                && m.reordered.is_empty());
+
+            writeln!(
+                output,
+                "// {}: CRC {:08X} -> {:08X}",
+                m.full_name, m.old_crc, m.new_crc
+            )?;
+            if !changed {
+                writeln!(
+                    output,
+                    "// Layout unchanged. Only the new CRC needs to be added."
+                )?;
+                writeln!(output, "//     0x{:08X} = {new_version},", m.new_crc)?;
+                writeln!(output)?;
+                continue;
+            }
+            for (label, list) in [
+                ("Added", &m.added),
+                ("Removed", &m.removed),
+                ("Retyped", &m.retyped),
+                ("Reordered", &m.reordered),
+            ] {
+                for field in list {
+                    writeln!(output, "// {label}: {field}")?;
+                }
+            }
+
+            writeln!(output, "rsz_struct! {{")?;
+            writeln!(output, "    #[rsz(\"{}\",", m.full_name)?;
+            writeln!(output, "        0x{:08X} = {new_version},", m.new_crc)?;
+            writeln!(output, "        0x{:08X} = {old_version},", m.old_crc)?;
+            writeln!(output, "    )]")?;
+            writeln!(output, "    #[derive(Debug, Serialize)]")?;
+            writeln!(output, "    pub struct {} {{", m.rust_name)?;
+            for (gate, field) in &m.merged {
+                let ty = match gate {
+                    Gate::Always => field.rust_type.clone(),
+                    // Removed fields exist in all versions before the new one
+                    Gate::Old => {
+                        format!("Versioned<{}, 0, {}>", field.rust_type, new_version - 1)
+                    }
+                    Gate::New => format!("Versioned<{}, {new_version}>", field.rust_type),
+                };
+                let comment = field
+                    .comment
+                    .as_ref()
+                    .map_or(String::new(), |c| format!(" // {c}"));
+                // A removed base is renamed to base_old if the new layout has one
+                if matches!(field.name.as_str(), "base" | "base_old") {
+                    if matches!(gate, Gate::Always) {
+                        writeln!(output, "        #[serde(flatten)]")?;
+                    } else {
+                        writeln!(
+                            output,
+                            "        // Review manually: the base differs between versions, \
+                            so it is not flattened"
+                        )?;
+                    }
+                }
+                writeln!(output, "        pub {}: {ty},{comment}", field.name)?;
+            }
+            writeln!(output, "    }}")?;
+            writeln!(output, "}}")?;
+            writeln!(output)?;
+        }
+
+        Ok(())
+    }
+}
@@ -10,6 +10,13 @@ use std::fs::File;
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
+mod c_header;
+mod inspect;
+mod migrate;
+mod rsz_code;
+mod rsz_schema;
+mod shell;
+
 bitflags! {
     #[derive(Serialize, Clone, Debug, PartialEq, Eq, Copy)]
     #[serde(into = "u16")]
@@ -748,6 +755,7 @@ struct TypeInfo {
     flags: TypeFlag,
 
     hash: u32,
+    crc: u32,
     assembly: usize,
     mi_default_ctor: Option<usize>,
     attributes: Vec<AttributeInfo>,
@@ -781,7 +789,7 @@ struct AssemblyInfo {
 }
 
 #[derive(Serialize)]
-struct Tdb {
+pub struct Tdb {
     types: Vec<TypeInfo>,
     intern_strings: Vec<String>,
     assemblies: Vec<AssemblyInfo>,
@@ -901,6 +909,7 @@ impl Tdb {
             flags: TypeFlag,
             runtime_len: usize,
             hash: u32,
+            crc: u32,
 
             ctor_method_membership_index: usize,
             method_membership_start_index: usize,
@@ -940,7 +949,7 @@ impl Tdb {
                 let flags = file.read_u32()?;
                 let runtime_len = file.read_u32()?;
                 let hash = file.read_u32()?;
-                let _crc = file.read_u32()?;
+                let crc = file.read_u32()?;
 
                 let (
                     ctor_method_membership_index,
@@ -976,6 +985,7 @@ impl Tdb {
                     flags: TypeFlag::from_bits(flags).context("Unknown type flag")?,
                     runtime_len: runtime_len.try_into()?,
                     hash,
+                    crc,
 
                     ctor_method_membership_index: ctor_method_membership_index.try_into()?,
                     method_membership_start_index: method_membership_start_index.try_into()?,
@@ -1858,6 +1868,7 @@ impl Tdb {
                     element_type: instance.element_type,
                     flags: instance.flags,
                     hash: instance.hash,
+                    crc: instance.crc,
                     assembly: ty.assembly_index,
                     mi_default_ctor: (ctor != 0)
                         .then(|| to_mi_self(ctor, instance_index))
@@ -2424,14 +2435,31 @@ impl Tdb {
     }
 }
 
-pub fn print<F: Read + Seek>(file: F, base_address: u64, options: crate::TdbOptions) -> Result<()> {
-    if options.json.is_none() && options.json_split.is_none() && options.cs.is_none() {
-        eprintln!("Please specify at least one of --json, --json-split, --map, --cs");
-        return Ok(());
+/// via.clr.VMObjType of value types
+const VMOBJ_VALUE_TYPE: u64 = 5;
+
+impl Tdb {
+    fn is_value_type(&self, ti: usize) -> bool {
+        self.types[ti].vmobj_type == VMOBJ_VALUE_TYPE
     }
 
-    let tdb = Tdb::new(file, base_address)?;
+    fn is_enum(&self, ti: usize) -> bool {
+        self.types[ti]
+            .ti_base
+            .map_or(false, |base| self.types[base].full_name == "System.Enum")
+    }
 
+    /// The type of `value__` field of an enum
+    fn enum_underlying(&self, ti: usize) -> Option<usize> {
+        self.types[ti]
+            .fields
+            .iter()
+            .find(|f| !f.flags.contains(FieldAttribute::STATIC))
+            .map(|f| f.ti)
+    }
+}
+
+pub fn print(tdb: &Tdb, options: crate::TdbOptions) -> Result<()> {
     if let Some(json) = &options.json {
         tdb.write_json(json)?;
     }
@@ -2441,5 +2469,8 @@ pub fn print<F: Read + Seek>(file: F, base_address: u64, options: crate::TdbOpti
     if let Some(cs) = &options.cs {
         tdb.write_cs(cs, &options)?;
     }
+    if let Some(c_header) = &options.c_header {
+        tdb.write_c_header(c_header, &options)?;
+    }
     Ok(())
 }
@@ -0,0 +1,429 @@
+//! Generates `rsz_struct!` / `rsz_enum!` / `rsz_bitflags!` definitions from TDB type metadata.
+//!
+//! The output is a starting point: field types that don't have an RSZ counterpart
+//! are emitted as `()` with a TODO comment so that they fail to compile until fixed by hand.
+
+use super::*;
+use std::collections::BTreeSet;
+
+fn primitive_type(full_name: &str) -> Option<&'static str> {
+    Some(match full_name {
+        "System.Boolean" => "bool",
+        "System.SByte" => "i8",
+        "System.Byte" => "u8",
+        "System.Int16" => "i16",
+        "System.UInt16" => "u16",
+        "System.Int32" => "i32",
+        "System.UInt32" => "u32",
+        "System.Int64" => "i64",
+        "System.UInt64" => "u64",
+        "System.Single" => "f32",
+        "System.String" => "String",
+        "System.Guid" => "Guid",
+        "via.vec2" | "via.Float2" => "Vec2",
+        "via.vec3" | "via.Float3" => "Vec3",
+        "via.vec4" | "via.Float4" => "Vec4",
+        "via.Int3" => "IVec3",
+        "via.Quaternion" => "Quat",
+        "via.mat4" => "Mat4x4",
+        "via.Color" => "u32",
+        _ => return None,
+    })
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "box", "yield",
+];
+
+/// "<MaxCount>k__BackingField" / "_MaxCount" / "MaxCount" -> "max_count"
+pub(super) fn field_name(name: &str) -> String {
+    let name = name
+        .strip_prefix('<')
+        .and_then(|n| n.split_once('>'))
+        .map_or(name, |(n, _)| n);
+    let name = name.trim_start_matches('_');
+
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let prev_lower =
+                i > 0 && (chars[i - 1].is_ascii_lowercase() || chars[i - 1].is_ascii_digit());
+            let next_lower = chars.get(i + 1).map_or(false, |n| n.is_ascii_lowercase());
+            let prev_upper = i > 0 && chars[i - 1].is_ascii_uppercase();
+            if !result.is_empty()
+                && !result.ends_with('_')
+                && (prev_lower || (prev_upper && next_lower))
+            {
+                result.push('_');
+            }
+            result.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            result.push(c);
+        } else if !result.ends_with('_') {
+            result.push('_');
+        }
+    }
+
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    if RUST_KEYWORDS.contains(&result.as_str()) {
+        result.push('_');
+    }
+    result
+}
+
+/// "MAX_COUNT" style for bitflags
+fn const_name(name: &str) -> String {
+    field_name(name).trim_end_matches('_').to_uppercase()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum TypeKind {
+    Enum,
+    Flags,
+    ValueType,
+    UserData,
+    Class,
+}
+
+pub(super) struct RszCodeGen<'a> {
+    tdb: &'a Tdb,
+    by_name: HashMap<&'a str, usize>,
+}
+
+impl<'a> RszCodeGen<'a> {
+    pub(super) fn new(tdb: &'a Tdb) -> Self {
+        let by_name = tdb
+            .types
+            .iter()
+            .enumerate()
+            .map(|(ti, t)| (t.full_name.as_str(), ti))
+            .collect();
+        RszCodeGen { tdb, by_name }
+    }
+
+    pub(super) fn find(&self, full_name: &str) -> Result<usize> {
+        self.by_name
+            .get(full_name)
+            .copied()
+            .with_context(|| format!("Type {full_name} not found"))
+    }
+
+    fn base_names(&self, ti: usize) -> impl Iterator<Item = &'a str> + '_ {
+        let mut current = self.tdb.types[ti].ti_base;
+        std::iter::from_fn(move || {
+            let ti = current?;
+            current = self.tdb.types[ti].ti_base;
+            Some(self.tdb.types[ti].full_name.as_str())
+        })
+    }
+
+    pub(super) fn kind(&self, ti: usize) -> TypeKind {
+        let type_info = &self.tdb.types[ti];
+        let direct_base = type_info
+            .ti_base
+            .map(|b| self.tdb.types[b].full_name.as_str());
+        if direct_base == Some("System.Enum") {
+            let is_flags = type_info
+                .attributes
+                .iter()
+                .any(|a| self.tdb.types[a.ti_attribute].full_name == "System.FlagsAttribute");
+            if is_flags {
+                TypeKind::Flags
+            } else {
+                TypeKind::Enum
+            }
+        } else if direct_base == Some("System.ValueType") {
+            TypeKind::ValueType
+        } else if self.base_names(ti).any(|b| b == "via.UserData") {
+            TypeKind::UserData
+        } else {
+            TypeKind::Class
+        }
+    }
+
+    /// Struct / enum name in Rust. Nested types are prefixed with the outer type name.
+    pub(super) fn rust_name(&self, ti: usize) -> String {
+        let type_info = &self.tdb.types[ti];
+        let name: String = type_info
+            .name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        match &type_info.parent {
+            Some(TypeParent::OuterType(outer)) => self.rust_name(*outer) + &name,
+            _ => name,
+        }
+    }
+
+    /// Template name and arguments if this is a constructed generic type
+    fn generic_args(&self, ti: usize) -> Option<(&'a str, &'a [usize])> {
+        match &self.tdb.types[ti].generics {
+            Some(Generics::Constructed {
+                ti_template,
+                ti_args,
+            }) => Some((self.tdb.types[*ti_template].full_name.as_str(), ti_args)),
+            _ => None,
+        }
+    }
+
+    /// Instance fields that are serialized
+    pub(super) fn serialized_fields(&self, ti: usize) -> impl Iterator<Item = &'a FieldInfo> {
+        self.tdb.types[ti].fields.iter().filter(|f| {
+            !f.flags.intersects(
+                FieldAttribute::STATIC | FieldAttribute::LITERAL | FieldAttribute::NO_SERIALIZE,
+            )
+        })
+    }
+
+    /// The base type to be flattened into the struct, if it has its own fields
+    pub(super) fn flattened_base(&self, ti: usize) -> Option<usize> {
+        let base = self.tdb.types[ti].ti_base?;
+        let base_name = self.tdb.types[base].full_name.as_str();
+        if base_name.starts_with("System.") || base_name.starts_with("via.") {
+            return None;
+        }
+        Some(base)
+    }
+
+    /// Returns the Rust type of a field, and a comment describing the original type if needed.
+    /// Types that need their own definitions are pushed to `deps`.
+    pub(super) fn field_type(&self, ti: usize, deps: &mut Vec<usize>) -> (String, Option<String>) {
+        let type_info = &self.tdb.types[ti];
+        let full_name = type_info.full_name.as_str();
+        if let Some(primitive) = primitive_type(full_name) {
+            let comment = (full_name == "via.Color").then(|| full_name.to_owned());
+            return (primitive.to_owned(), comment);
+        }
+
+        if let Some(element) = type_info.ti_dearray {
+            let (element, comment) = self.field_type(element, deps);
+            return (format!("Vec<{element}>"), comment);
+        }
+
+        if let Some((template, args)) = self.generic_args(ti) {
+            if template == "System.Collections.Generic.List`1" && args.len() == 1 {
+                let (element, comment) = self.field_type(args[0], deps);
+                return (format!("Vec<{element}>"), comment);
+            }
+            return ("()".to_owned(), Some(format!("TODO: {full_name}")));
+        }
+
+        match self.kind(ti) {
+            TypeKind::UserData => ("ExternUser<()>".to_owned(), Some(full_name.to_owned())),
+            TypeKind::Enum | TypeKind::Flags | TypeKind::ValueType | TypeKind::Class => {
+                if full_name.starts_with("System.") || full_name.starts_with("via.") {
+                    return ("()".to_owned(), Some(format!("TODO: {full_name}")));
+                }
+                deps.push(ti);
+                (self.rust_name(ti), None)
+            }
+        }
+    }
+
+    /// Collects the given types and everything they depend on, dependencies first.
+    pub(super) fn collect(&self, roots: &[usize]) -> Vec<usize> {
+        fn visit(
+            codegen: &RszCodeGen,
+            ti: usize,
+            visited: &mut BTreeSet<usize>,
+            order: &mut Vec<usize>,
+        ) {
+            if !visited.insert(ti) {
+                return;
+            }
+            let mut deps = vec![];
+            if matches!(
+                codegen.kind(ti),
+                TypeKind::ValueType | TypeKind::Class | TypeKind::UserData
+            ) {
+                deps.extend(codegen.flattened_base(ti));
+                for field in codegen.serialized_fields(ti) {
+                    codegen.field_type(field.ti, &mut deps);
+                }
+            }
+            for dep in deps {
+                visit(codegen, dep, visited, order);
+            }
+            order.push(ti);
+        }
+
+        let mut visited = BTreeSet::new();
+        let mut order = vec![];
+        for &root in roots {
+            visit(self, root, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn enum_values(&self, ti: usize) -> Vec<(&'a str, i64)> {
+        let type_info = &self.tdb.types[ti];
+        let signed = type_info
+            .fields
+            .iter()
+            .find(|f| f.name == "value__")
+            .map_or(true, |f| {
+                self.tdb.types[f.ti].full_name.starts_with("System.Int")
+                    || self.tdb.types[f.ti].full_name == "System.SByte"
+            });
+        type_info
+            .fields
+            .iter()
+            .filter(|f| f.flags.contains(FieldAttribute::LITERAL))
+            .filter_map(|f| {
+                let Some(ValueInfo::Bytes(bytes)) = &f.value else {
+                    return None;
+                };
+                let mut buf = [0; 8];
+                let len = bytes.len().min(8);
+                buf[..len].copy_from_slice(&bytes[..len]);
+                let mut value = i64::from_le_bytes(buf);
+                if signed && (1..8).contains(&len) && bytes[len - 1] & 0x80 != 0 {
+                    value |= -1i64 << (len * 8);
+                }
+                Some((f.name.as_str(), value))
+            })
+            .collect()
+    }
+
+    fn underlying_type(&self, ti: usize) -> String {
+        self.tdb.types[ti]
+            .fields
+            .iter()
+            .find(|f| f.name == "value__")
+            .and_then(|f| primitive_type(&self.tdb.types[f.ti].full_name))
+            .unwrap_or("i32")
+            .to_owned()
+    }
+
+    pub(super) fn write_type(
+        &self,
+        output: &mut impl Write,
+        ti: usize,
+        version: Option<u32>,
+    ) -> Result<()> {
+        let type_info = &self.tdb.types[ti];
+        let full_name = &type_info.full_name;
+        let name = self.rust_name(ti);
+
+        if hash_as_utf8(full_name) != type_info.hash {
+            bail!("Mismatched hash for {}", full_name)
+        }
+
+        match self.kind(ti) {
+            TypeKind::Enum => {
+                writeln!(output, "// {full_name}")?;
+                writeln!(output, "rsz_enum! {{")?;
+                writeln!(output, "    #[rsz({})]", self.underlying_type(ti))?;
+                writeln!(
+                    output,
+                    "    #[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]"
+                )?;
+                writeln!(output, "    pub enum {name} {{")?;
+                for (variant, value) in self.enum_values(ti) {
+                    writeln!(output, "        {variant} = {value},")?;
+                }
+                writeln!(output, "    }}")?;
+                writeln!(output, "}}")?;
+            }
+            TypeKind::Flags => {
+                writeln!(output, "// {full_name}")?;
+                writeln!(output, "rsz_bitflags! {{")?;
+                writeln!(
+                    output,
+                    "    pub struct {name}: {} {{",
+                    self.underlying_type(ti)
+                )?;
+                for (flag, value) in self.enum_values(ti) {
+                    if value != 0 {
+                        writeln!(output, "        const {} = 0x{value:X};", const_name(flag))?;
+                    }
+                }
+                writeln!(output, "    }}")?;
+                writeln!(output, "}}")?;
+            }
+            kind @ (TypeKind::ValueType | TypeKind::Class | TypeKind::UserData) => {
+                writeln!(output, "rsz_struct! {{")?;
+                if kind == TypeKind::ValueType {
+                    writeln!(output, "    #[rsz()]")?;
+                } else if let Some(version) = version {
+                    writeln!(output, "    #[rsz(\"{full_name}\",")?;
+                    writeln!(output, "        0x{:08X} = {version},", type_info.crc)?;
+                    writeln!(output, "    )]")?;
+                } else {
+                    writeln!(output, "    #[rsz(\"{full_name}\")]")?;
+                }
+                writeln!(output, "    #[derive(Debug, Serialize)]")?;
+                writeln!(output, "    pub struct {name} {{")?;
+                if let Some(base) = self.flattened_base(ti) {
+                    writeln!(output, "        #[serde(flatten)]")?;
+                    writeln!(
+                        output,
+                        "        pub base: Flatten<{}>,",
+                        self.rust_name(base)
+                    )?;
+                }
+                for field in self.serialized_fields(ti) {
+                    let (ty, comment) = self.field_type(field.ti, &mut vec![]);
+                    let comment = comment.map_or(String::new(), |c| format!(" // {c}"));
+                    writeln!(
+                        output,
+                        "        pub {}: {ty},{comment}",
+                        field_name(&field.name)
+                    )?;
+                }
+                writeln!(output, "    }}")?;
+                writeln!(output, "}}")?;
+            }
+        }
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+impl Tdb {
+    /// Writes RSZ definitions for the given types and their dependencies,
+    /// followed by the registration list for `RSZ_TYPE_MAP`.
+    /// If `version` is specified, the current CRC of each type is tagged with it.
+    pub fn write_rsz_code(
+        &self,
+        mut output: impl Write,
+        type_names: &[String],
+        version: Option<u32>,
+    ) -> Result<()> {
+        let codegen = RszCodeGen::new(self);
+        let roots = type_names
+            .iter()
+            .map(|name| codegen.find(name))
+            .collect::<Result<Vec<_>>>()?;
+        let types = codegen.collect(&roots);
+
+        writeln!(output, "use super::*;")?;
+        writeln!(output, "use crate::{{rsz_bitflags, rsz_enum, rsz_struct}};")?;
+        writeln!(output, "use bitflags::*;")?;
+        writeln!(output, "use nalgebra_glm::*;")?;
+        writeln!(output, "use serde::*;")?;
+        writeln!(output)?;
+
+        for &ti in &types {
+            codegen.write_type(&mut output, ti, version)?;
+        }
+
+        let registered: Vec<String> = types
+            .iter()
+            .filter(|&&ti| matches!(codegen.kind(ti), TypeKind::Class | TypeKind::UserData))
+            .map(|&ti| codegen.rust_name(ti))
+            .collect();
+        if !registered.is_empty() {
+            writeln!(output, "// Register in RSZ_TYPE_MAP:")?;
+            writeln!(output, "// r!({});", registered.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
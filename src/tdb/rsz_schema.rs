@@ -0,0 +1,161 @@
+//! Extracts the RSZ deserialization layout of every type from the runtime
+//! type information in a full memory dump.
+
+use super::*;
+
+/// One entry of the deserializer sequence attached to a runtime type.
+///
+/// In memory it is 16 bytes:
+/// ```text
+/// u32: code:8, size:8, align:8, depth:6, is_array:1, is_static:1
+/// u32: offset in the native object
+/// u64: address of the TDB type definition of the field
+/// ```
+#[derive(Serialize)]
+struct SchemaField {
+    name: Option<String>,
+    native_type: Option<String>,
+    code: u32,
+    size: u32,
+    align: u32,
+    depth: u32,
+    offset: u32,
+    array: bool,
+    #[serde(rename = "static")]
+    static_: bool,
+}
+
+#[derive(Serialize)]
+struct SchemaType {
+    name: String,
+    hash: u32,
+    crc: u32,
+    fields: Vec<SchemaField>,
+}
+
+/// Sanity limit for the sequence length, in case of a broken pointer
+const MAX_SEQUENCE_LEN: usize = 0x1000;
+
+impl Tdb {
+    /// Walks the runtime type of every TDB type in `memory`,
+    /// and writes their RSZ field layout as JSON keyed by "hash:crc".
+    ///
+    /// `memory` must be the same address space the TDB was loaded from.
+    pub fn write_rsz_schema<F: Read + Seek>(
+        &self,
+        mut memory: F,
+        output: impl Write,
+    ) -> Result<()> {
+        let by_type_def: HashMap<u64, usize> = self
+            .types
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.type_def_address != 0)
+            .map(|(ti, t)| (t.type_def_address, ti))
+            .collect();
+
+        let mut schema = BTreeMap::new();
+        let mut skipped = 0;
+        for type_info in &self.types {
+            if type_info.runtime_info == 0 || type_info.crc == 0 {
+                continue;
+            }
+            let fields = match self.read_sequence(&mut memory, type_info, &by_type_def) {
+                Ok(Some(fields)) => fields,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Skipped {}: {e:#}", type_info.full_name);
+                    skipped += 1;
+                    continue;
+                }
+            };
+            schema.insert(
+                format!("{:08x}:{:08x}", type_info.hash, type_info.crc),
+                SchemaType {
+                    name: type_info.full_name.clone(),
+                    hash: type_info.hash,
+                    crc: type_info.crc,
+                    fields,
+                },
+            );
+        }
+
+        eprintln!("Dumped {} types, skipped {skipped}", schema.len());
+        serde_json::to_writer_pretty(output, &schema)?;
+        Ok(())
+    }
+
+    fn read_sequence<F: Read + Seek>(
+        &self,
+        memory: &mut F,
+        type_info: &TypeInfo,
+        by_type_def: &HashMap<u64, usize>,
+    ) -> Result<Option<Vec<SchemaField>>> {
+        // The runtime type starts with a vtable, followed by the hash
+        memory.seek(SeekFrom::Start(type_info.runtime_info + 0x8))?;
+        if memory.read_u32()? != type_info.hash {
+            bail!("Runtime type hash mismatch")
+        }
+        memory.seek(SeekFrom::Start(type_info.runtime_info + 0x50))?;
+        let field_list = memory.read_u64()?;
+        if field_list == 0 {
+            return Ok(None);
+        }
+        memory.seek(SeekFrom::Start(field_list + 0x28))?;
+        let sequence = memory.read_u64()?;
+        if sequence == 0 {
+            return Ok(None);
+        }
+
+        // All instance fields in the inheritance chain, for naming sequence entries
+        let mut instance_fields = vec![];
+        let mut current = Some(type_info);
+        while let Some(t) = current {
+            instance_fields.extend(
+                t.fields
+                    .iter()
+                    .filter(|f| !f.flags.contains(FieldAttribute::STATIC)),
+            );
+            current = t.ti_base.map(|base| &self.types[base]);
+        }
+
+        let mut fields = vec![];
+        memory.seek(SeekFrom::Start(sequence))?;
+        loop {
+            let (code, size, align, depth, array, static_) =
+                memory.read_u32()?.bit_split((8, 8, 8, 6, 1, 1));
+            let offset = memory.read_u32()?;
+            let type_def = memory.read_u64()?;
+            if type_def == 0 {
+                break;
+            }
+            if fields.len() == MAX_SEQUENCE_LEN {
+                bail!("Deserializer sequence too long")
+            }
+
+            let ti = by_type_def.get(&type_def).copied();
+            // TDB field positions don't count the 0x10-byte object header
+            let name = instance_fields
+                .iter()
+                .find(|f| {
+                    (f.position == offset || f.position + 0x10 == offset)
+                        && ti.map_or(true, |ti| f.ti == ti)
+                })
+                .map(|f| f.name.clone());
+
+            fields.push(SchemaField {
+                name,
+                native_type: ti.map(|ti| self.types[ti].full_name.clone()),
+                code,
+                size,
+                align,
+                depth,
+                offset,
+                array: array != 0,
+                static_: static_ != 0,
+            })
+        }
+
+        Ok(Some(fields))
+    }
+}
@@ -0,0 +1,200 @@
+//! Interactive queries over a loaded TDB.
+
+use super::*;
+use std::io::BufRead;
+
+const HELP: &str = "\
+Commands:
+    find <pattern>      list types whose full name contains the pattern
+    type <type>         show summary of a type
+    fields <type>       list fields with offsets
+    methods <type>      list methods of a type
+    method <pattern>    search methods of all types by signature
+    bases <type>        walk the inheritance chain and interfaces
+    derived <type>      list types directly derived from a type
+    crc <hex>           list types with the given RSZ CRC
+    help                show this message
+    quit                exit
+<type> can be a full name, a hash in hex, or a unique part of the name.";
+
+/// Limit on the number of search results printed
+const MAX_RESULTS: usize = 200;
+
+impl Tdb {
+    fn shell_find_type(&self, query: &str) -> Result<usize> {
+        if let Some(ti) = self.types.iter().position(|t| t.full_name == query) {
+            return Ok(ti);
+        }
+
+        if let Ok(hash) = u32::from_str_radix(query.trim_start_matches("0x"), 16) {
+            if let Some(ti) = self.types.iter().position(|t| t.hash == hash) {
+                return Ok(ti);
+            }
+        }
+
+        let query_lower = query.to_lowercase();
+        let candidates: Vec<usize> = self
+            .types
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.full_name.to_lowercase().contains(&query_lower))
+            .map(|(ti, _)| ti)
+            .take(2)
+            .collect();
+        match candidates[..] {
+            [ti] => Ok(ti),
+            [] => bail!("No type matches {query}"),
+            _ => bail!("Multiple types match {query}. Use `find` to list them"),
+        }
+    }
+
+    fn method_signature(&self, ti: usize, method: &MethodInfo) -> String {
+        let params: Vec<String> = method
+            .params
+            .iter()
+            .map(|p| format!("{} {}", self.types[p.ti].full_name, p.name))
+            .collect();
+        format!(
+            "{} {}.{}({})",
+            self.types[method.ret.ti].full_name,
+            self.types[ti].full_name,
+            method.name,
+            params.join(", ")
+        )
+    }
+
+    fn shell_command(&self, command: &str, arg: &str, output: &mut impl Write) -> Result<()> {
+        match command {
+            "help" => writeln!(output, "{HELP}")?,
+            "find" => {
+                let pattern = arg.to_lowercase();
+                let found = self
+                    .types
+                    .iter()
+                    .filter(|t| t.full_name.to_lowercase().contains(&pattern));
+                for (i, t) in found.enumerate() {
+                    if i == MAX_RESULTS {
+                        writeln!(output, "...")?;
+                        break;
+                    }
+                    writeln!(output, "{:08X} {}", t.hash, t.full_name)?;
+                }
+            }
+            "type" => {
+                let ti = self.shell_find_type(arg)?;
+                let t = &self.types[ti];
+                writeln!(output, "{}", t.full_name)?;
+                writeln!(output, "    hash: {:08X}", t.hash)?;
+                writeln!(output, "    crc: {:08X}", t.crc)?;
+                writeln!(output, "    flags: {}", display_type_flags(t.flags))?;
+                writeln!(output, "    size: 0x{:X}", t.len)?;
+                if let Some(base) = t.ti_base {
+                    writeln!(output, "    base: {}", self.types[base].full_name)?;
+                }
+                writeln!(
+                    output,
+                    "    {} fields, {} methods, {} properties, {} interfaces",
+                    t.fields.len(),
+                    t.methods.len(),
+                    t.properties.len(),
+                    t.interfaces.len()
+                )?;
+            }
+            "fields" => {
+                let ti = self.shell_find_type(arg)?;
+                for field in &self.types[ti].fields {
+                    writeln!(
+                        output,
+                        "    +0x{:04X} {}{} {}",
+                        field.position,
+                        display_field_attributes(field.flags),
+                        self.types[field.ti].full_name,
+                        field.name
+                    )?;
+                }
+            }
+            "methods" => {
+                let ti = self.shell_find_type(arg)?;
+                for method in &self.types[ti].methods {
+                    writeln!(output, "    {}", self.method_signature(ti, method))?;
+                }
+            }
+            "method" => {
+                let pattern = &arg.to_lowercase();
+                let found = self.types.iter().enumerate().flat_map(|(ti, t)| {
+                    t.methods
+                        .iter()
+                        .map(move |m| self.method_signature(ti, m))
+                        .filter(move |s| s.to_lowercase().contains(pattern))
+                });
+                for (i, signature) in found.enumerate() {
+                    if i == MAX_RESULTS {
+                        writeln!(output, "...")?;
+                        break;
+                    }
+                    writeln!(output, "{signature}")?;
+                }
+            }
+            "bases" => {
+                let mut current = Some(self.shell_find_type(arg)?);
+                let mut depth = 0;
+                while let Some(ti) = current {
+                    let t = &self.types[ti];
+                    writeln!(output, "{:depth$}{}", "", t.full_name)?;
+                    for interface in &t.interfaces {
+                        writeln!(
+                            output,
+                            "{:depth$}  + {}",
+                            "", self.types[interface.ti].full_name
+                        )?;
+                    }
+                    current = t.ti_base;
+                    depth += 2;
+                }
+            }
+            "derived" => {
+                let ti = self.shell_find_type(arg)?;
+                for t in self.types.iter().filter(|t| t.ti_base == Some(ti)) {
+                    writeln!(output, "{:08X} {}", t.hash, t.full_name)?;
+                }
+            }
+            "crc" => {
+                let crc =
+                    u32::from_str_radix(arg.trim_start_matches("0x"), 16).context("Invalid CRC")?;
+                for t in self.types.iter().filter(|t| t.crc == crc) {
+                    writeln!(output, "{:08X} {}", t.hash, t.full_name)?;
+                }
+            }
+            _ => bail!("Unknown command {command}. Type `help` for the list of commands"),
+        }
+        Ok(())
+    }
+
+    /// Reads commands line by line from `input` until EOF or `quit`.
+    pub fn shell(&self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        writeln!(
+            output,
+            "{} types loaded. Type `help` for commands.",
+            self.types.len()
+        )?;
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            let line = line.trim();
+            let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+            match command {
+                "" => (),
+                "quit" | "exit" => break,
+                _ => {
+                    if let Err(e) = self.shell_command(command, arg.trim(), &mut output) {
+                        writeln!(output, "Error: {e}")?;
+                    }
+                }
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+}
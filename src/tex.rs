@@ -544,6 +544,14 @@ impl Tex {
         })
     }
 
+    pub fn width(&self) -> u32 {
+        self.width.into()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height.into()
+    }
+
     pub fn to_rgba(&self, index: usize, mipmap: usize) -> anyhow::Result<RgbaImage> {
         self.to_rgba_swizzle(index, mipmap, "rgba")
     }
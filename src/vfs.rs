@@ -0,0 +1,397 @@
+//! Read-only virtual filesystem view of PAK files.
+//!
+//! Files with known names appear at their full path in the PAK (`natives/STM/...`),
+//! and the rest appear as `_unknown/<hash>`. File content is decompressed on first read.
+
+// Most of the API is only used by the FUSE frontend
+#![cfg_attr(not(all(unix, feature = "fuse")), allow(dead_code))]
+
+use crate::pak::*;
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Number of decompressed files kept in memory, evicting the least recently read
+const CACHE_SIZE: usize = 16;
+
+pub type NodeId = usize;
+
+pub enum VfsNode {
+    Dir {
+        parent: NodeId,
+        children: BTreeMap<String, NodeId>,
+    },
+    File {
+        index: PakFileIndex,
+        len: u64,
+    },
+}
+
+pub struct PakVfs<F> {
+    pak: PakReader<F>,
+    nodes: Vec<VfsNode>,
+    cache: VecDeque<(PakFileIndex, Vec<u8>)>,
+}
+
+impl<F: PakSource> PakVfs<F> {
+    pub const ROOT: NodeId = 0;
+
+    /// Builds the tree from files in `pak`.
+    /// Path lists should be loaded into `pak` beforehand to name the files.
+    pub fn new(pak: PakReader<F>) -> Result<PakVfs<F>> {
+        let mut vfs = PakVfs {
+            pak,
+            nodes: vec![VfsNode::Dir {
+                parent: Self::ROOT,
+                children: BTreeMap::new(),
+            }],
+            cache: VecDeque::new(),
+        };
+
+        for index in vfs.pak.all_file_indexs() {
+            let path = match vfs.pak.full_path(index) {
+                Some(path) => path.to_owned(),
+                None => format!("_unknown/{:016X}", vfs.pak.file_hash(index)),
+            };
+            let mut components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+            let file_name = components.pop().context("Empty path")?;
+
+            let mut dir = Self::ROOT;
+            for component in components {
+                dir = vfs.make_dir(dir, component)?;
+            }
+            let len = vfs.pak.file_len(index);
+            let id = vfs.nodes.len();
+            let VfsNode::Dir { children, .. } = &mut vfs.nodes[dir] else {
+                unreachable!()
+            };
+            if children.insert(file_name.to_owned(), id).is_some() {
+                bail!("Duplicate path {path}")
+            }
+            vfs.nodes.push(VfsNode::File { index, len });
+        }
+
+        Ok(vfs)
+    }
+
+    fn make_dir(&mut self, parent: NodeId, name: &str) -> Result<NodeId> {
+        let new_id = self.nodes.len();
+        let VfsNode::Dir { children, .. } = &mut self.nodes[parent] else {
+            bail!("{name} is under a file")
+        };
+        let id = *children.entry(name.to_owned()).or_insert(new_id);
+        if id == new_id {
+            self.nodes.push(VfsNode::Dir {
+                parent,
+                children: BTreeMap::new(),
+            });
+        } else if !matches!(self.nodes[id], VfsNode::Dir { .. }) {
+            bail!("{name} is both a file and a directory")
+        }
+        Ok(id)
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&VfsNode> {
+        self.nodes.get(id)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn lookup(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match self.nodes.get(dir)? {
+            VfsNode::Dir { children, .. } => children.get(name).copied(),
+            VfsNode::File { .. } => None,
+        }
+    }
+
+    /// Reads up to `size` bytes of a file from `offset`
+    pub fn read(&mut self, id: NodeId, offset: u64, size: usize) -> Result<&[u8]> {
+        let Some(&VfsNode::File { index, .. }) = self.nodes.get(id) else {
+            bail!("Not a file")
+        };
+
+        let entry = match self.cache.iter().position(|(i, _)| *i == index) {
+            Some(position) => self.cache.remove(position).unwrap(),
+            None => {
+                let data = self.pak.read_file(index)?;
+                if self.cache.len() == CACHE_SIZE {
+                    self.cache.pop_front();
+                }
+                (index, data)
+            }
+        };
+        self.cache.push_back(entry);
+
+        let data = &self.cache.back().unwrap().1;
+        let begin = usize::try_from(offset)?.min(data.len());
+        let end = begin.saturating_add(size).min(data.len());
+        Ok(&data[begin..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::hash_as_utf16;
+
+    fn path_hash(full_path: &str) -> u64 {
+        u64::from(hash_as_utf16(&full_path.to_lowercase()))
+            | (u64::from(hash_as_utf16(&full_path.to_uppercase())) << 32)
+    }
+
+    /// Writes an unencrypted PAK with uncompressed entries
+    fn build_pak(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let header_len = 0x10 + files.len() * 0x30;
+        let mut pak = vec![];
+        pak.extend_from_slice(b"KPKA");
+        pak.extend_from_slice(&4u16.to_le_bytes());
+        pak.extend_from_slice(&0u16.to_le_bytes());
+        pak.extend_from_slice(&(files.len() as u32).to_le_bytes());
+        pak.extend_from_slice(&[0; 4]);
+
+        let mut offset = header_len as u64;
+        for (path, data) in files {
+            let len = data.len() as u64;
+            pak.extend_from_slice(&path_hash(path).to_le_bytes());
+            pak.extend_from_slice(&offset.to_le_bytes());
+            pak.extend_from_slice(&len.to_le_bytes());
+            pak.extend_from_slice(&len.to_le_bytes());
+            pak.extend_from_slice(&[0; 0x10]);
+            offset += len;
+        }
+        for (_, data) in files {
+            pak.extend_from_slice(data);
+        }
+        pak
+    }
+
+    fn build_vfs(files: &[(&str, &[u8])], paths: &[&str]) -> PakVfs<Vec<u8>> {
+        let mut pak = PakReader::new(vec![build_pak(files)]).unwrap();
+        for path in paths {
+            pak.add_path(path).unwrap();
+        }
+        PakVfs::new(pak).unwrap()
+    }
+
+    fn lookup_path(vfs: &PakVfs<Vec<u8>>, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .try_fold(PakVfs::<Vec<u8>>::ROOT, |dir, name| vfs.lookup(dir, name))
+    }
+
+    #[test]
+    fn tree() {
+        let vfs = build_vfs(
+            &[
+                ("natives/STM/data/a.user.2", b"first"),
+                ("natives/STM/data/sub/b.user.2", b"second"),
+                ("natives/STM/other.user.2", b"unnamed"),
+            ],
+            &["data/a.user", "data/sub/b.user"],
+        );
+
+        let Some(VfsNode::Dir { children, .. }) = vfs.node(PakVfs::<Vec<u8>>::ROOT) else {
+            panic!("Root is not a directory")
+        };
+        let root: Vec<&str> = children.keys().map(|s| s.as_str()).collect();
+        assert_eq!(root, ["_unknown", "natives"]);
+
+        let a = lookup_path(&vfs, "natives/STM/data/a.user.2").unwrap();
+        assert!(matches!(vfs.node(a), Some(VfsNode::File { len: 5, .. })));
+        let sub = lookup_path(&vfs, "natives/STM/data/sub").unwrap();
+        assert!(matches!(vfs.node(sub), Some(VfsNode::Dir { .. })));
+        assert!(lookup_path(&vfs, "natives/STM/data/sub/b.user.2").is_some());
+
+        let unknown = format!("_unknown/{:016X}", path_hash("natives/STM/other.user.2"));
+        assert!(lookup_path(&vfs, &unknown).is_some());
+
+        assert!(lookup_path(&vfs, "natives/STM/data/c.user.2").is_none());
+        // Files have no children
+        assert!(vfs.lookup(a, "x").is_none());
+        // Root, natives, STM, data, sub, _unknown, and three files
+        assert_eq!(vfs.node_count(), 9);
+    }
+
+    #[test]
+    fn read_at_offset() {
+        let mut vfs = build_vfs(&[("natives/STM/a.user.2", b"0123456789")], &["a.user"]);
+        let a = lookup_path(&vfs, "natives/STM/a.user.2").unwrap();
+
+        assert_eq!(vfs.read(a, 0, 4).unwrap(), b"0123");
+        assert_eq!(vfs.read(a, 6, 100).unwrap(), b"6789");
+        assert_eq!(vfs.read(a, 10, 4).unwrap(), b"");
+        assert_eq!(vfs.read(a, 100, 4).unwrap(), b"");
+        assert!(vfs.read(PakVfs::<Vec<u8>>::ROOT, 0, 4).is_err());
+    }
+
+    #[test]
+    fn cache_eviction() {
+        let paths: Vec<String> = (0..=CACHE_SIZE).map(|i| format!("f{i}.user")).collect();
+        let full_paths: Vec<String> = paths
+            .iter()
+            .map(|path| format!("natives/STM/{path}.2"))
+            .collect();
+        let data: Vec<Vec<u8>> = (0..=CACHE_SIZE).map(|i| vec![i as u8; 4]).collect();
+        let files: Vec<(&str, &[u8])> = full_paths
+            .iter()
+            .zip(&data)
+            .map(|(path, data)| (path.as_str(), data.as_slice()))
+            .collect();
+        let paths: Vec<&str> = paths.iter().map(|s| s.as_str()).collect();
+        let mut vfs = build_vfs(&files, &paths);
+
+        let ids: Vec<NodeId> = full_paths
+            .iter()
+            .map(|path| lookup_path(&vfs, path).unwrap())
+            .collect();
+        let index = |vfs: &PakVfs<Vec<u8>>, id: NodeId| match vfs.node(id) {
+            Some(&VfsNode::File { index, .. }) => index,
+            _ => panic!("Not a file"),
+        };
+        let cached = |vfs: &PakVfs<Vec<u8>>, id: NodeId| {
+            let index = index(vfs, id);
+            vfs.cache.iter().any(|(i, _)| *i == index)
+        };
+
+        for &id in &ids[..CACHE_SIZE] {
+            vfs.read(id, 0, 4).unwrap();
+        }
+        assert_eq!(vfs.cache.len(), CACHE_SIZE);
+
+        // A cache hit makes the first file the most recently read
+        assert_eq!(vfs.read(ids[0], 0, 4).unwrap(), [0; 4]);
+        assert_eq!(vfs.cache.len(), CACHE_SIZE);
+
+        assert_eq!(
+            vfs.read(ids[CACHE_SIZE], 0, 4).unwrap(),
+            [CACHE_SIZE as u8; 4]
+        );
+        assert_eq!(vfs.cache.len(), CACHE_SIZE);
+        assert!(cached(&vfs, ids[0]));
+        assert!(!cached(&vfs, ids[1]));
+        assert!(cached(&vfs, ids[CACHE_SIZE]));
+
+        // Evicted files are read again from the PAK
+        assert_eq!(vfs.read(ids[1], 1, 4).unwrap(), [1; 3]);
+    }
+}
+
+#[cfg(all(unix, feature = "fuse"))]
+pub mod fuse {
+    use super::*;
+    use fuser::*;
+    use std::ffi::OsStr;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const TTL: Duration = Duration::from_secs(3600);
+
+    /// FUSE inode numbers start from 1 at the root
+    fn to_ino(id: NodeId) -> u64 {
+        id as u64 + 1
+    }
+
+    fn to_id(ino: u64) -> NodeId {
+        (ino - 1) as NodeId
+    }
+
+    pub struct PakFs<F>(pub PakVfs<F>);
+
+    impl<F: PakSource> PakFs<F> {
+        fn attr(&self, node_id: NodeId) -> Option<FileAttr> {
+            let (kind, size, perm, nlink) = match self.0.node(node_id)? {
+                VfsNode::Dir { .. } => (FileType::Directory, 0, 0o555, 2),
+                VfsNode::File { len, .. } => (FileType::RegularFile, *len, 0o444, 1),
+            };
+            Some(FileAttr {
+                ino: to_ino(node_id),
+                size,
+                blocks: size.div_ceil(512),
+                atime: UNIX_EPOCH,
+                mtime: UNIX_EPOCH,
+                ctime: UNIX_EPOCH,
+                crtime: UNIX_EPOCH,
+                kind,
+                perm,
+                nlink,
+                uid: 0,
+                gid: 0,
+                rdev: 0,
+                blksize: 4096,
+                flags: 0,
+            })
+        }
+    }
+
+    impl<F: PakSource> Filesystem for PakFs<F> {
+        fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+            let found = name
+                .to_str()
+                .and_then(|name| self.0.lookup(to_id(parent), name))
+                .and_then(|child| self.attr(child));
+            match found {
+                Some(attr) => reply.entry(&TTL, &attr, 0),
+                None => reply.error(libc::ENOENT),
+            }
+        }
+
+        fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
+            match self.attr(to_id(ino)) {
+                Some(attr) => reply.attr(&TTL, &attr),
+                None => reply.error(libc::ENOENT),
+            }
+        }
+
+        fn read(
+            &mut self,
+            _req: &Request,
+            ino: u64,
+            _fh: u64,
+            offset: i64,
+            size: u32,
+            _flags: i32,
+            _lock_owner: Option<u64>,
+            reply: ReplyData,
+        ) {
+            match self.0.read(to_id(ino), offset as u64, size as usize) {
+                Ok(data) => reply.data(data),
+                Err(e) => {
+                    eprintln!("Failed to read inode {ino}: {e:#}");
+                    reply.error(libc::EIO)
+                }
+            }
+        }
+
+        fn readdir(
+            &mut self,
+            _req: &Request,
+            ino: u64,
+            _fh: u64,
+            offset: i64,
+            mut reply: ReplyDirectory,
+        ) {
+            let Some(VfsNode::Dir { parent, children }) = self.0.node(to_id(ino)) else {
+                reply.error(libc::ENOTDIR);
+                return;
+            };
+            let entries = [(to_id(ino), ".".to_owned()), (*parent, "..".to_owned())]
+                .into_iter()
+                .chain(children.iter().map(|(name, &child)| (child, name.clone())));
+            for (i, (child, name)) in entries.enumerate().skip(offset as usize) {
+                let kind = match self.0.node(child) {
+                    Some(VfsNode::File { .. }) => FileType::RegularFile,
+                    _ => FileType::Directory,
+                };
+                if reply.add(to_ino(child), (i + 1) as i64, kind, name) {
+                    break;
+                }
+            }
+            reply.ok()
+        }
+    }
+
+    pub fn mount<F: PakSource + Send + 'static>(vfs: PakVfs<F>, mountpoint: &str) -> Result<()> {
+        let options = [MountOption::RO, MountOption::FSName("mhrice".to_owned())];
+        fuser::mount2(PakFs(vfs), mountpoint, &options)?;
+        Ok(())
+    }
+}
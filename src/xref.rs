@@ -0,0 +1,253 @@
+//! Cross-reference index of outgoing references from files in the PAK.
+//!
+//! The index is stored as a tab-separated text file, one reference per line:
+//! `<file>\t<format>\t<kind>\t<target>\t<note>`
+
+use crate::gui::*;
+use crate::mesh::*;
+use crate::msg::*;
+use crate::pak::*;
+use crate::pfb::*;
+use crate::rcol::*;
+use crate::rsz::*;
+use crate::scn::*;
+use crate::user::*;
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{BufRead, Cursor, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum XrefKind {
+    /// A path to another file in the PAK
+    Path,
+    /// GUID of a MSG entry, found in RSZ data
+    Msg,
+    /// Material name in a MESH file
+    Material,
+}
+
+impl XrefKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            XrefKind::Path => "path",
+            XrefKind::Msg => "msg",
+            XrefKind::Material => "material",
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        Ok(match s {
+            "path" => XrefKind::Path,
+            "msg" => XrefKind::Msg,
+            "material" => XrefKind::Material,
+            _ => bail!("Unknown xref kind {s}"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct XrefEntry {
+    /// The referencing file: its path if known, or `PakFileIndex::short_string` otherwise
+    pub file: String,
+    pub format: &'static str,
+    pub kind: XrefKind,
+    /// Path, GUID or material name being referenced
+    pub target: String,
+    /// Extra information. MSG entry name for `XrefKind::Msg`
+    pub note: String,
+}
+
+pub struct XrefIndex {
+    pub entries: Vec<XrefEntry>,
+}
+
+const FORMATS: &[&str] = &["USER", "PFB", "SCN", "RCOL", "GUI", "MESH"];
+
+/// (kind, target, note)
+type Ref = (XrefKind, String, String);
+
+fn collect_rsz(rsz: &Rsz, msg_guids: &HashMap<[u8; 16], String>, refs: &mut BTreeSet<Ref>) {
+    for slot in rsz.extern_slots.values() {
+        refs.insert((XrefKind::Path, slot.path.clone(), String::new()));
+    }
+
+    // There is no type information for RSZ data in general,
+    // so look for any GUID that matches a known MSG entry
+    for begin in (0..rsz.data.len().saturating_sub(15)).step_by(4) {
+        let bytes: [u8; 16] = rsz.data[begin..begin + 16].try_into().unwrap();
+        if let Some(name) = msg_guids.get(&bytes) {
+            refs.insert((XrefKind::Msg, String::from(Guid { bytes }), name.clone()));
+        }
+    }
+}
+
+fn collect_gui(object: &PlayObject, refs: &mut BTreeSet<Ref>) {
+    for field in object.properties.iter().chain(&object.variables) {
+        if let FieldValue::Texture(path) = &field.value {
+            if !path.is_empty() {
+                refs.insert((XrefKind::Path, path.clone(), String::new()));
+            }
+        }
+    }
+}
+
+/// Collects references of a file, and returns its format, or `None` if it is not indexed
+fn collect_file(
+    content: &[u8],
+    msg_guids: &HashMap<[u8; 16], String>,
+    refs: &mut BTreeSet<Ref>,
+) -> Result<Option<&'static str>> {
+    if content.len() < 8 {
+        return Ok(None);
+    }
+    let format = if &content[0..3] == b"USR" {
+        let user = User::new(Cursor::new(content))?;
+        for path in user.resource_names {
+            refs.insert((XrefKind::Path, path, String::new()));
+        }
+        collect_rsz(&user.rsz, msg_guids, refs);
+        "USER"
+    } else if &content[0..3] == b"PFB" {
+        let pfb = Pfb::new(Cursor::new(content))?;
+        for path in pfb.resource_names {
+            refs.insert((XrefKind::Path, path, String::new()));
+        }
+        collect_rsz(&pfb.rsz, msg_guids, refs);
+        "PFB"
+    } else if &content[0..3] == b"SCN" {
+        let scn = Scn::new(Cursor::new(content))?;
+        for path in scn.resource_names.into_iter().chain(scn.prefab_paths) {
+            refs.insert((XrefKind::Path, path, String::new()));
+        }
+        collect_rsz(&scn.rsz, msg_guids, refs);
+        "SCN"
+    } else if &content[0..4] == b"RCOL" {
+        let rcol = Rcol::new(Cursor::new(content), false)?;
+        collect_rsz(&rcol.rsz, msg_guids, refs);
+        "RCOL"
+    } else if &content[4..8] == b"GUIR" {
+        let gui = Gui::new(Cursor::new(content))?;
+        collect_gui(&gui.root, refs);
+        for object in gui.controls.iter().flat_map(|c| &c.play_objects) {
+            collect_gui(object, refs);
+        }
+        "GUI"
+    } else if &content[0..4] == b"MESH" {
+        let mesh = Mesh::new(Cursor::new(content))?;
+        for name in mesh.material_names {
+            refs.insert((XrefKind::Material, name, String::new()));
+        }
+        "MESH"
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(format))
+}
+
+impl XrefIndex {
+    /// Indexes all files in the PAK. Files that fail to parse are reported and skipped.
+    /// Referencing files are named by their paths if they are loaded into `pak`.
+    pub fn build<F: PakSource>(pak: &PakReader<F>) -> Result<XrefIndex> {
+        let indexs = pak.all_file_indexs();
+
+        eprintln!("Collecting MSG GUIDs..");
+        let mut msg_guids = HashMap::new();
+        for &index in &indexs {
+            let file = pak.read_file(index)?;
+            if file.len() < 8 || file[4..8] != b"GMSG"[..] {
+                continue;
+            }
+            let msg = match Msg::new(Cursor::new(&file)) {
+                Ok(msg) => msg,
+                Err(e) => {
+                    eprintln!("Skipped {}: {e:#}", pak.display_name(index));
+                    continue;
+                }
+            };
+            for entry in msg.entries {
+                msg_guids.insert(entry.guid.bytes, entry.name);
+            }
+        }
+
+        eprintln!("Collecting references..");
+        let mut entries = vec![];
+        let mut skipped = 0;
+        for index in indexs {
+            let content = pak.read_file(index)?;
+            let file = pak.display_name(index);
+            let mut refs = BTreeSet::new();
+            let format = match collect_file(&content, &msg_guids, &mut refs) {
+                Ok(Some(format)) => format,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Skipped {file}: {e:#}");
+                    skipped += 1;
+                    continue;
+                }
+            };
+            entries.extend(refs.into_iter().map(|(kind, target, note)| XrefEntry {
+                file: file.clone(),
+                format,
+                kind,
+                target,
+                note,
+            }));
+        }
+        if skipped != 0 {
+            eprintln!("Skipped {skipped} files that failed to parse");
+        }
+
+        Ok(XrefIndex { entries })
+    }
+
+    pub fn save(&self, mut output: impl Write) -> Result<()> {
+        for entry in &self.entries {
+            writeln!(
+                output,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.file,
+                entry.format,
+                entry.kind.as_str(),
+                entry.target,
+                entry.note
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn load(input: impl BufRead) -> Result<XrefIndex> {
+        let mut entries = vec![];
+        for (line_number, line) in input.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let columns: Vec<&str> = line.split('\t').collect();
+            let &[file, format, kind, target, note] = &columns[..] else {
+                bail!("Bad xref line {}", line_number + 1)
+            };
+            let format = FORMATS
+                .iter()
+                .copied()
+                .find(|&f| f == format)
+                .with_context(|| format!("Unknown format {format}"))?;
+            entries.push(XrefEntry {
+                file: file.to_owned(),
+                format,
+                kind: XrefKind::parse(kind)?,
+                target: target.to_owned(),
+                note: note.to_owned(),
+            })
+        }
+        Ok(XrefIndex { entries })
+    }
+
+    /// Finds references whose target or note contains the query, case insensitively.
+    pub fn query<'a>(&'a self, query: &str) -> impl Iterator<Item = &'a XrefEntry> + 'a {
+        let query = query.to_lowercase();
+        self.entries.iter().filter(move |entry| {
+            entry.target.to_lowercase().contains(&query)
+                || entry.note.to_lowercase().contains(&query)
+        })
+    }
+}